@@ -0,0 +1,70 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use edgedb_derive::Queryable;
+use edgedb_protocol::value::Value;
+use edgedb_tokio::Client;
+use serde::Deserialize;
+
+// Compares three ways of getting the same rows out of EdgeDB:
+// * as Value, the dynamic type the client falls back to
+// * as json, deserialized into a struct with serde
+// * straight into a struct with Queryable
+// for a few different row counts. The rows are free objects built in the query itself, so
+// no data has to be inserted first, but the benchmark still needs a running instance
+// (the same one `cargo run` uses):
+//
+// cargo bench --bench decode
+//
+// Every measurement includes the round trip to the server, which is the same for the three
+// variants, so the differences between them are the cost of decoding.
+
+const QUERY: &str = "for i in range_unpack(range(0, <int64>$0)) union {
+    username := 'user' ++ <str>i,
+    number := i,
+    active := i % 2 = 0,
+}";
+
+#[derive(Debug, Deserialize, Queryable)]
+struct Row {
+    username: String,
+    number: i64,
+    active: bool,
+}
+
+fn decode(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let client: Client = rt
+        .block_on(edgedb_tokio::create_client())
+        .expect("benchmarks need a running EdgeDB instance");
+
+    let mut group = c.benchmark_group("decode");
+    for rows in [10i64, 1_000, 10_000] {
+        group.throughput(Throughput::Elements(rows as u64));
+
+        group.bench_with_input(BenchmarkId::new("value", rows), &rows, |b, &rows| {
+            b.to_async(&rt).iter(|| async {
+                let res: Vec<Value> = client.query(QUERY, &(rows,)).await.unwrap();
+                assert_eq!(res.len(), rows as usize);
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("json_serde", rows), &rows, |b, &rows| {
+            b.to_async(&rt).iter(|| async {
+                // query_json returns the whole result as a single json array
+                let json = client.query_json(QUERY, &(rows,)).await.unwrap();
+                let res: Vec<Row> = serde_json::from_str(&json).unwrap();
+                assert_eq!(res.len(), rows as usize);
+            })
+        });
+
+        group.bench_with_input(BenchmarkId::new("queryable", rows), &rows, |b, &rows| {
+            b.to_async(&rt).iter(|| async {
+                let res: Vec<Row> = client.query(QUERY, &(rows,)).await.unwrap();
+                assert_eq!(res.len(), rows as usize);
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
@@ -0,0 +1,81 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use edgedb_client_example::values;
+use edgedb_protocol::{
+    codec::{ObjectShape, ShapeElement},
+    model::Json,
+    value::Value,
+};
+use libfuzzer_sys::fuzz_target;
+
+// Feeds arbitrary Value trees to values::pretty and values::to_json, which must never panic
+// or overflow the stack. Run with cargo-fuzz (needs a nightly toolchain):
+//
+// cargo +nightly fuzz run values
+//
+// Value doesn't implement Arbitrary, so the fuzzer generates this smaller mirror of it and
+// converts it. Objects get their own list of field names, independent of the fields, so
+// shapes and fields that don't line up are generated too. Json can be any string, valid
+// json or not.
+#[derive(Debug, Arbitrary)]
+enum FuzzValue {
+    Nothing,
+    Str(String),
+    Uuid(u128),
+    Bool(bool),
+    Int16(i16),
+    Int32(i32),
+    Int64(i64),
+    Float32(f32),
+    Float64(f64),
+    Json(String),
+    Object {
+        names: Vec<(String, bool)>,
+        fields: Vec<Option<FuzzValue>>,
+    },
+    Set(Vec<FuzzValue>),
+    Array(Vec<FuzzValue>),
+    Tuple(Vec<FuzzValue>),
+}
+
+fn convert(value: FuzzValue) -> Value {
+    let all = |items: Vec<FuzzValue>| items.into_iter().map(convert).collect();
+    match value {
+        FuzzValue::Nothing => Value::Nothing,
+        FuzzValue::Str(s) => Value::Str(s),
+        FuzzValue::Uuid(n) => Value::Uuid(uuid::Uuid::from_u128(n)),
+        FuzzValue::Bool(b) => Value::Bool(b),
+        FuzzValue::Int16(n) => Value::Int16(n),
+        FuzzValue::Int32(n) => Value::Int32(n),
+        FuzzValue::Int64(n) => Value::Int64(n),
+        FuzzValue::Float32(n) => Value::Float32(n),
+        FuzzValue::Float64(n) => Value::Float64(n),
+        FuzzValue::Json(s) => Value::Json(Json::new_unchecked(s)),
+        FuzzValue::Object { names, fields } => Value::Object {
+            shape: ObjectShape::new(
+                names
+                    .into_iter()
+                    .map(|(name, implicit)| ShapeElement {
+                        flag_implicit: implicit,
+                        flag_link_property: false,
+                        flag_link: false,
+                        cardinality: None,
+                        name,
+                    })
+                    .collect(),
+            ),
+            fields: fields.into_iter().map(|f| f.map(convert)).collect(),
+        },
+        FuzzValue::Set(items) => Value::Set(all(items)),
+        FuzzValue::Array(items) => Value::Array(all(items)),
+        FuzzValue::Tuple(items) => Value::Tuple(all(items)),
+    }
+}
+
+fuzz_target!(|value: FuzzValue| {
+    let value = convert(value);
+    let _ = values::pretty(&value);
+    // Errors are fine (too deep, invalid json), panics aren't
+    let _ = values::to_json(&value);
+});
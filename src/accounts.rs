@@ -0,0 +1,118 @@
+use std::str::FromStr;
+
+use comfy_table::Table;
+use edgedb_derive::Queryable;
+use edgedb_protocol::model::Datetime;
+use edgedb_tokio::Client;
+use uuid::Uuid;
+
+use crate::telemetry;
+
+// Typed queries behind the `account` subcommands. Each function is a thin wrapper around
+// a single query so the path from parsed arguments to decoded output stays easy to follow.
+
+#[derive(Debug, Clone, Queryable)]
+pub struct AccountSummary {
+    pub username: String,
+    pub id: Uuid,
+    pub created_at: Datetime,
+}
+
+// Accounts can be looked up by either of their exclusive properties.
+#[derive(Debug, Clone)]
+pub enum AccountKey {
+    Id(Uuid),
+    Username(String),
+}
+
+// Anything that parses as a uuid is treated as an id, everything else as a username.
+impl FromStr for AccountKey {
+    type Err = std::convert::Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match Uuid::parse_str(s) {
+            Ok(id) => AccountKey::Id(id),
+            Err(_) => AccountKey::Username(s.to_string()),
+        })
+    }
+}
+
+pub async fn create(
+    client: &Client,
+    username: &str,
+) -> Result<AccountSummary, edgedb_tokio::Error> {
+    telemetry::query_required_single(
+        client,
+        "account.create",
+        "select (insert Account { username := <str>$0 }) { username, id, created_at }",
+        &(username,),
+    )
+    .await
+}
+
+pub async fn get(
+    client: &Client,
+    key: &AccountKey,
+) -> Result<Option<AccountSummary>, edgedb_tokio::Error> {
+    match key {
+        AccountKey::Id(id) => {
+            telemetry::query_single(
+                client,
+                "account.get_by_id",
+                "select Account { username, id, created_at } filter .id = <uuid>$0",
+                &(*id,),
+            )
+            .await
+        }
+        AccountKey::Username(username) => {
+            telemetry::query_single(
+                client,
+                "account.get_by_username",
+                "select Account { username, id, created_at } filter .username = <str>$0",
+                &(username,),
+            )
+            .await
+        }
+    }
+}
+
+pub async fn list(
+    client: &Client,
+    offset: i64,
+    limit: i64,
+) -> Result<Vec<AccountSummary>, edgedb_tokio::Error> {
+    telemetry::query(
+        client,
+        "account.list",
+        "select Account { username, id, created_at }
+        order by .created_at desc then .id
+        offset <int64>$0
+        limit <int64>$1",
+        &(offset, limit),
+    )
+    .await
+}
+
+// Returns the id of the deleted Account, or None if there was nothing to delete.
+pub async fn delete(client: &Client, id: Uuid) -> Result<Option<Uuid>, edgedb_tokio::Error> {
+    telemetry::query_single(
+        client,
+        "account.delete",
+        "select (delete Account filter .id = <uuid>$0).id",
+        &(id,),
+    )
+    .await
+}
+
+pub fn print_table(accounts: &[AccountSummary]) {
+    let mut table = Table::new();
+    table.set_header(vec!["id", "username", "created_at"]);
+    for account in accounts {
+        table.add_row(vec![
+            account.id.to_string(),
+            account.username.clone(),
+            account.created_at.to_string(),
+        ]);
+    }
+    println!("{table}");
+}
@@ -0,0 +1,176 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
+use edgedb_tokio::Client;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::{
+    http::{reqwest_client, HttpSettings},
+    policies::admin_client,
+    random_name,
+};
+
+// Semantic search with the AI extension (ext::ai, EdgeDB 5+). Where the embeddings example
+// computes vectors in Rust and stores them itself, here the server does the work:
+//
+// * The schema has a deferred ext::ai::index on SearchablePost, a Post with the index (in
+//   dbschema/extensions/ai.esdl), over its title and body. Whenever one is inserted or
+//   updated the server asks the provider (OpenAI here) for an embedding of the text in the
+//   background and keeps it next to the Post.
+// * The provider's API key is extension configuration, set with plain EdgeQL through the
+//   binary client, like the auth example does for ext::auth.
+// * A search needs the question as a vector from the same model. The extension serves an
+//   OpenAI-style /ai/embeddings endpoint over http for that, so the key stays on the
+//   server. The vector then goes into ext::ai::search(SearchablePost, <array<float32>>$0),
+//   which returns each Post with its cosine distance, decoded into (String, f64) tuples.
+//
+// The key is taken from OPENAI_API_KEY the first time. As the index is deferred, new Posts
+// only show up in searches once their embeddings have come back, which run waits for.
+
+const MODEL: &str = "text-embedding-3-small";
+
+#[derive(Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+}
+
+async fn configure(client: &Client) -> Result<(), anyhow::Error> {
+    let configured: bool = client
+        .query_required_single(
+            "select exists cfg::Config.extensions[is ext::ai::Config]
+                .providers[is ext::ai::OpenAIProviderConfig]",
+            &(),
+        )
+        .await?;
+    if configured {
+        return Ok(());
+    }
+    let key = std::env::var("OPENAI_API_KEY")
+        .context("set OPENAI_API_KEY to configure the OpenAI provider")?;
+    // Configuration doesn't take query arguments, so the key is quoted into the query
+    let key = key.replace('\\', "\\\\").replace('\'', "\\'");
+    client
+        .execute(
+            &format!(
+                "configure current database insert
+                ext::ai::OpenAIProviderConfig {{ secret := '{key}' }};"
+            ),
+            &(),
+        )
+        .await?;
+    Ok(())
+}
+
+// The embedding of `text`, computed by the provider through the extension's http endpoint
+pub async fn embed(settings: &HttpSettings, text: &str) -> Result<Vec<f32>, anyhow::Error> {
+    let mut request = reqwest_client(settings)?
+        .post(format!("{}/ai/embeddings", settings.database_url()))
+        .json(&json!({ "model": MODEL, "input": text }));
+    if let Some(password) = &settings.password {
+        request = request.basic_auth(&settings.user, Some(password));
+    }
+    let response: EmbeddingsResponse = request
+        .send()
+        .await?
+        .error_for_status()
+        .context("getting an embedding")?
+        .json()
+        .await?;
+    let data = response.data.into_iter().next();
+    Ok(data.context("no embedding in the response")?.embedding)
+}
+
+// The titles of `username`'s Posts closest in meaning to `embedding`, closest first
+pub async fn search(
+    client: &Client,
+    username: &str,
+    embedding: Vec<f32>,
+    limit: usize,
+) -> Result<Vec<(String, f64)>, edgedb_tokio::Error> {
+    client
+        .query(
+            "with results := (
+                for result in ext::ai::search(SearchablePost, <array<float32>>$0) union (
+                    select (result.object.title, result.distance)
+                    filter result.object.author.username = <str>$1
+                )
+            )
+            select results order by results.1 limit <int64>$2",
+            &(embedding, username, limit as i64),
+        )
+        .await
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    configure(client).await?;
+    let settings = HttpSettings::from_env()?;
+
+    let admin = admin_client(client);
+    let username = format!("ai{}", random_name());
+    let posts = [
+        (
+            "Training a puppy",
+            "Short sessions, lots of treats and patience",
+        ),
+        (
+            "Fixing a flat tyre",
+            "Find the puncture with soapy water, then patch it",
+        ),
+        (
+            "Weeknight pasta",
+            "Garlic, olive oil, chilli and whatever greens are left",
+        ),
+    ];
+    for (title, body) in posts {
+        admin
+            .execute(
+                "insert SearchablePost {
+                    author := (
+                        insert Account { username := <str>$0 }
+                        unless conflict on .username else (select Account)
+                    ),
+                    title := <str>$1,
+                    body := <str>$2,
+                }",
+                &(&username, title, body),
+            )
+            .await?;
+    }
+
+    let res = async {
+        let question = "How do I teach my dog to sit?";
+        let embedding = embed(&settings, question).await?;
+
+        // Wait for the server to embed the new Posts
+        let start = Instant::now();
+        let results = loop {
+            let results = search(client, &username, embedding.clone(), 3).await?;
+            if results.len() == posts.len() {
+                break results;
+            }
+            if start.elapsed() > Duration::from_secs(60) {
+                bail!("only {} Posts were embedded after a minute", results.len());
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        };
+
+        println!("{question}");
+        for (title, distance) in &results {
+            println!("  {distance:.3}  {title}");
+        }
+        assert_eq!(results[0].0, "Training a puppy");
+        Ok(())
+    }
+    .await;
+
+    admin
+        .execute("delete Account filter .username = <str>$0", &(&username,))
+        .await?;
+    res
+}
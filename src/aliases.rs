@@ -0,0 +1,77 @@
+use edgedb_protocol::value::Value;
+use edgedb_tokio::Client;
+
+use crate::{
+    accounts::AccountSummary,
+    display_result,
+    factory::{AccountFactory, PostFactory},
+    random_name,
+};
+
+// A schema alias queried from Rust: `alias Author` in dbschema/default.esdl is the Accounts
+// that have written a Post, with a post_count added to them. Queries use it like a type,
+// so `select Author { username, id, created_at }` decodes into the same AccountSummary as
+// a select of Account, and filters, ordering and shapes all work on it as usual.
+//
+// Without the alias, every query that wants authors repeats `filter exists
+// .<author[is Post]` (and the count), and a copy that drifts makes two screens disagree
+// about who counts as an author. With it the definition lives in one place, is reviewed
+// with the schema, and changing it is a migration instead of a search through the Rust
+// code. It also keeps the backlink syntax out of the queries in the application.
+//
+// Keep filtering in the client for conditions that come from the request (a search term,
+// a page), and use an alias for conditions that are part of what the data means. An alias
+// isn't stored: it's expanded into each query like a macro, so it costs the same as the
+// repeated filter, and it can't be inserted into or updated.
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let prefix = format!("alias{}", random_name());
+    let author = AccountFactory::new()
+        .username(format!("{prefix}_author"))
+        .with_posts(3)
+        .create(client)
+        .await?;
+    let reader = AccountFactory::new()
+        .username(format!("{prefix}_reader"))
+        .create(client)
+        .await?;
+
+    let res = async {
+        let query = "select Author { username, id, created_at }
+            filter .username like <str>$0 ++ '%'";
+        let authors: Vec<AccountSummary> = client.query(query, &(&prefix,)).await?;
+        display_result(query, &authors);
+        let usernames: Vec<&str> = authors.iter().map(|a| a.username.as_str()).collect();
+        assert_eq!(usernames, [author.username.as_str()]);
+
+        // The same objects as Account, with the computed property on top
+        let query = "select Author.post_count filter Author.id = <uuid>$0";
+        let post_count: i64 = client.query_required_single(query, &(author.id,)).await?;
+        display_result(query, &post_count);
+        assert_eq!(post_count, 3);
+
+        // Nothing is stored for the alias, so the reader becomes an Author as soon as
+        // they've written a Post
+        let query = "select count(Author filter .id = <uuid>$0)";
+        let count: i64 = client.query_required_single(query, &(reader.id,)).await?;
+        display_result(query, &count);
+        assert_eq!(count, 0);
+        let as_reader = client.with_globals_fn(|globals| {
+            globals.set("current_user", Value::Uuid(reader.id));
+        });
+        PostFactory::new().create(&as_reader).await?;
+        let count: i64 = client.query_required_single(query, &(reader.id,)).await?;
+        display_result(query, &count);
+        assert_eq!(count, 1);
+        Ok(())
+    }
+    .await;
+
+    client
+        .execute(
+            "delete Account filter .username like <str>$0 ++ '%'",
+            &(&prefix,),
+        )
+        .await?;
+    res
+}
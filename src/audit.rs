@@ -0,0 +1,191 @@
+use comfy_table::Table;
+use edgedb_derive::Queryable;
+use edgedb_protocol::{
+    model::{Datetime, Json},
+    query_arg::QueryArgs,
+};
+use edgedb_tokio::Client;
+use uuid::Uuid;
+
+use crate::export::valid_type_name;
+
+// An audit log for compliance: every write made through Audited records who made it and
+// the object's properties before and after, as json, in an AuditEntry. The write and its
+// entry are in the same transaction, so there's never a change without an entry or an
+// entry for a change that was rolled back. `cargo run -- audit` lists the entries.
+//
+// Audited wraps a Client, and the actor is the Account in its `global current_user`, so
+// for the web example's per-request clients it's whoever is logged in. Each write:
+// 1. reads the object with `select <json>(select Type { * } ...)`
+// 2. runs the write, which returns the id of the object it wrote
+// 3. reads the object again, and inserts the AuditEntry with both
+// A write that finds nothing to change (a wrong id, or an object the access policies hide)
+// records nothing.
+//
+// Things to know:
+// * `{ * }` is every property of the type, so everything stored on it ends up in the log.
+//   Keep secrets like password hashes on types that aren't audited this way.
+// * Only writes made through Audited are recorded. Updates to Account are recorded for
+//   any client by a trigger instead (see the triggers example), which can't be forgotten
+//   but only sees what EdgeQL can.
+// * The entries keep the object's id, not a link, so they're still there after it's
+//   deleted.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Insert,
+    Update,
+    Delete,
+}
+
+impl Action {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Action::Insert => "insert",
+            Action::Update => "update",
+            Action::Delete => "delete",
+        }
+    }
+}
+
+#[derive(Debug, Queryable)]
+pub struct AuditRow {
+    pub at: Datetime,
+    pub actor: Option<String>,
+    pub action: String,
+    pub object_type: String,
+    pub object_id: Uuid,
+    pub old_value: Option<Json>,
+    pub new_value: Option<Json>,
+}
+
+const INSERT_ENTRY: &str = "insert AuditEntry {
+    actor := (select Account filter .id = global current_user),
+    object_type := <str>$0,
+    object_id := <uuid>$1,
+    action := <str>$2,
+    old_value := <optional json>$3,
+    new_value := <optional json>$4,
+}";
+
+#[derive(Clone)]
+pub struct Audited {
+    client: Client,
+}
+
+impl Audited {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+
+    // Runs `query`, which inserts a `type_name` and returns its id
+    pub async fn insert<A: QueryArgs>(
+        &self,
+        type_name: &str,
+        query: &str,
+        args: &A,
+    ) -> Result<Option<Uuid>, anyhow::Error> {
+        self.write(type_name, Action::Insert, None, query, args)
+            .await
+    }
+
+    // Runs `query`, which updates the `type_name` with `id` and returns its id
+    pub async fn update<A: QueryArgs>(
+        &self,
+        type_name: &str,
+        id: Uuid,
+        query: &str,
+        args: &A,
+    ) -> Result<Option<Uuid>, anyhow::Error> {
+        self.write(type_name, Action::Update, Some(id), query, args)
+            .await
+    }
+
+    pub async fn delete(&self, type_name: &str, id: Uuid) -> Result<Option<Uuid>, anyhow::Error> {
+        let query = format!("select (delete {type_name} filter .id = <uuid>$0).id");
+        self.write(type_name, Action::Delete, Some(id), &query, &(id,))
+            .await
+    }
+
+    async fn write<A: QueryArgs>(
+        &self,
+        type_name: &str,
+        action: Action,
+        id: Option<Uuid>,
+        query: &str,
+        args: &A,
+    ) -> Result<Option<Uuid>, anyhow::Error> {
+        // The name goes into the query text
+        anyhow::ensure!(
+            valid_type_name(type_name),
+            "invalid type name {type_name:?}"
+        );
+        let snapshot = format!("select <json>(select {type_name} {{ * }} filter .id = <uuid>$0)");
+        let written = self
+            .client
+            .transaction(|mut tx| {
+                let snapshot = &snapshot;
+                async move {
+                    let old_value: Option<Json> = match id {
+                        Some(id) => tx.query_single(snapshot, &(id,)).await?,
+                        None => None,
+                    };
+                    let Some(written) = tx.query_single::<Uuid, _>(query, args).await? else {
+                        return Ok(None);
+                    };
+                    let new_value: Option<Json> = tx.query_single(snapshot, &(written,)).await?;
+                    tx.execute(
+                        INSERT_ENTRY,
+                        &(type_name, written, action.as_str(), old_value, new_value),
+                    )
+                    .await?;
+                    Ok(Some(written))
+                }
+            })
+            .await?;
+        Ok(written)
+    }
+}
+
+// The newest entries first, for one object or for all of them
+pub async fn recent(
+    client: &Client,
+    object: Option<Uuid>,
+    limit: i64,
+) -> Result<Vec<AuditRow>, edgedb_tokio::Error> {
+    client
+        .query(
+            "with object := <optional uuid>$0
+            select AuditEntry {
+                at,
+                actor := .actor.username,
+                action,
+                object_type,
+                object_id,
+                old_value,
+                new_value,
+            }
+            filter not exists object or .object_id ?= object
+            order by .at desc
+            limit <int64>$1",
+            &(object, limit),
+        )
+        .await
+}
+
+pub fn print_table(rows: &[AuditRow]) {
+    let mut table = Table::new();
+    table.set_header(vec!["at", "actor", "action", "object", "before", "after"]);
+    let json = |value: &Option<Json>| value.as_ref().map(|json| json.to_string());
+    for row in rows {
+        table.add_row(vec![
+            row.at.to_string(),
+            row.actor.clone().unwrap_or_default(),
+            row.action.clone(),
+            format!("{} {}", row.object_type, row.object_id),
+            json(&row.old_value).unwrap_or_default(),
+            json(&row.new_value).unwrap_or_default(),
+        ]);
+    }
+    println!("{table}");
+}
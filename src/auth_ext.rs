@@ -0,0 +1,186 @@
+use anyhow::{anyhow, Context};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use edgedb_protocol::value::Value;
+use edgedb_tokio::Client;
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    display_result,
+    http::{reqwest_client, HttpSettings},
+    random_name,
+};
+
+// The auth extension (ext::auth) handles signing users up and in over http, and hands back
+// an auth token plus the id of the user's ext::auth::Identity. From then on it's ordinary
+// binary-protocol queries: the identity id goes into a global and the schema takes it from
+// there (see the current_identity_id global and IdentityAccount, an Account with a link to
+// its identity, in dbschema/extensions/auth_ext.esdl).
+//
+// The flow below is the email/password one using PKCE:
+// 1. Make up a random verifier and send its sha256 (the challenge) with the signup.
+// 2. The extension answers with a one-time code tied to that challenge.
+// 3. Exchange the code plus the original verifier for the auth token and identity id.
+// Only whoever holds the verifier can finish the flow, which is what makes it safe to pass
+// the code through a browser redirect in a real application.
+
+const PROVIDER: &str = "builtin::local_emailpassword";
+
+#[derive(Debug, Deserialize)]
+struct CodeResponse {
+    code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TokenResponse {
+    pub auth_token: String,
+    pub identity_id: Uuid,
+}
+
+struct Pkce {
+    verifier: String,
+    challenge: String,
+}
+
+impl Pkce {
+    fn new() -> Self {
+        let verifier: String = std::iter::repeat_with(fastrand::alphanumeric)
+            .take(64)
+            .collect();
+        let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+        Self {
+            verifier,
+            challenge,
+        }
+    }
+}
+
+// The extension needs a signing key and at least one provider. Configuration is plain
+// EdgeQL, so the binary client can set it up. Verification emails are turned off since
+// there's no SMTP server to send them. A new signing key on every run invalidates earlier
+// tokens, which is fine here but a real deployment sets it once.
+async fn configure(client: &Client) -> Result<(), edgedb_tokio::Error> {
+    let signing_key: String = std::iter::repeat_with(fastrand::alphanumeric)
+        .take(48)
+        .collect();
+    client
+        .execute(
+            &format!(
+                "configure current database set
+                ext::auth::AuthConfig::auth_signing_key := '{signing_key}';"
+            ),
+            &(),
+        )
+        .await?;
+    let configured: bool = client
+        .query_required_single(
+            "select exists cfg::Config.extensions[is ext::auth::AuthConfig]
+                .providers[is ext::auth::EmailPasswordProviderConfig]",
+            &(),
+        )
+        .await?;
+    if !configured {
+        client
+            .execute(
+                "configure current database insert
+                ext::auth::EmailPasswordProviderConfig { require_verification := false };",
+                &(),
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    configure(client).await?;
+
+    let settings = HttpSettings::from_env()?;
+    let http = reqwest_client(&settings)?;
+    let auth_url = format!("{}/ext/auth", settings.database_url());
+
+    // 1 and 2: sign up
+    let email = format!("{}@example.com", random_name());
+    let password = random_name();
+    let pkce = Pkce::new();
+    let signup: CodeResponse = http
+        .post(format!("{auth_url}/register"))
+        .json(&json!({
+            "provider": PROVIDER,
+            "challenge": pkce.challenge,
+            "email": email,
+            "password": password,
+            "verify_url": "http://localhost:3000/verify",
+        }))
+        .send()
+        .await?
+        .error_for_status()
+        .context("signing up")?
+        .json()
+        .await?;
+
+    // 3: exchange the code for a token
+    let token: TokenResponse = http
+        .get(format!("{auth_url}/token"))
+        .query(&[("code", &signup.code), ("verifier", &pkce.verifier)])
+        .send()
+        .await?
+        .error_for_status()
+        .context("exchanging the code for a token")?
+        .json()
+        .await?;
+    println!("Signed up {email}, identity id {}\n", token.identity_id);
+
+    // Signing in later works the same way, starting from /authenticate
+    let pkce = Pkce::new();
+    let signin: CodeResponse = http
+        .post(format!("{auth_url}/authenticate"))
+        .json(&json!({
+            "provider": PROVIDER,
+            "challenge": pkce.challenge,
+            "email": email,
+            "password": password,
+        }))
+        .send()
+        .await?
+        .error_for_status()
+        .context("signing in")?
+        .json()
+        .await?;
+    let signed_in: TokenResponse = http
+        .get(format!("{auth_url}/token"))
+        .query(&[("code", &signin.code), ("verifier", &pkce.verifier)])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    assert_eq!(signed_in.identity_id, token.identity_id);
+
+    // Now back to the binary protocol, with the identity id as a global
+    let identity_client = client.with_globals_fn(|globals| {
+        globals.set("current_identity_id", Value::Uuid(token.identity_id));
+    });
+
+    // The Account is linked through global current_identity, which the schema computes
+    // from current_identity_id, so the query never handles the identity's id itself
+    let query = "select (
+        insert IdentityAccount {
+            username := <str>$0,
+            identity := global current_identity,
+        }
+    ) { username, identity: { id } }";
+    let res: Value = identity_client
+        .query_required_single(query, &(random_name(),))
+        .await?;
+    display_result(query, &res);
+
+    // And the computed global finds it again from the identity alone
+    let query = "select global identity_account.username";
+    let username: Option<String> = identity_client.query_single(query, &()).await?;
+    display_result(query, &username);
+    username.ok_or_else(|| anyhow!("no Account found for the identity"))?;
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use std::{
+    fs::File,
+    io::Read,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use anyhow::{bail, ensure, Context};
+
+use crate::connection::ConnectOpts;
+
+// Backups from the same codebase as the application: `dump` and `restore` run the edgedb
+// CLI (or `gel`, its newer name, with --cli gel) with connection flags from the same
+// ConnectOpts the client was built with, so the backup is always of the database the
+// application uses.
+//
+// The client library has no dump protocol of its own, so shelling out is the supported
+// way to do this. The CLI has to be on the PATH.
+//
+// Checks on the artifacts:
+// * after a dump, and before a restore, the file has to start with the dump format's
+//   magic bytes, which catches empty, truncated or wrong files before they're relied on
+// * after a restore, the database has to have the schema's types in it
+//
+// A restore only works into an empty database. To restore next to the original rather
+// than over it, create a fresh branch (EdgeDB 5+) or database (EdgeDB 4) and point the
+// restore at it with --database:
+//
+//   edgedb branch create restored --empty      (or: edgedb database create restored)
+//   cargo run -- --database restored restore backup.dump
+
+// The first bytes of every file written by `edgedb dump`
+const DUMP_MAGIC: &[u8] = b"\xff\xd8\x00\x00\xd8EDGEDB\x00DUMP\x00";
+
+fn run_cli(cli: &str, connection: &ConnectOpts, args: &[&str]) -> Result<(), anyhow::Error> {
+    let status = Command::new(cli)
+        .args(connection.cli_args())
+        .args(args)
+        .stdin(Stdio::null())
+        .status()
+        .with_context(|| format!("couldn't run `{cli}`, is it installed and on the PATH?"))?;
+    if !status.success() {
+        bail!("`{cli} {}` failed with {status}", args.join(" "));
+    }
+    Ok(())
+}
+
+// Returns the size of the dump in bytes
+pub fn verify_dump(path: &Path) -> Result<u64, anyhow::Error> {
+    let mut file = File::open(path).with_context(|| format!("couldn't open {}", path.display()))?;
+    let mut magic = [0; DUMP_MAGIC.len()];
+    let is_dump = file.read_exact(&mut magic).is_ok() && magic == DUMP_MAGIC;
+    ensure!(is_dump, "{} is not an EdgeDB dump", path.display());
+    Ok(file.metadata()?.len())
+}
+
+pub fn dump(cli: &str, connection: &ConnectOpts, path: &Path) -> Result<u64, anyhow::Error> {
+    let path_arg = path.to_str().context("dump path has to be valid utf-8")?;
+    run_cli(cli, connection, &["dump", path_arg])?;
+    verify_dump(path)
+}
+
+// Returns the number of object types in the restored schema
+pub async fn restore(
+    cli: &str,
+    connection: &ConnectOpts,
+    path: &Path,
+) -> Result<i64, anyhow::Error> {
+    verify_dump(path)?;
+    let path_arg = path.to_str().context("dump path has to be valid utf-8")?;
+    run_cli(cli, connection, &["restore", path_arg])?;
+
+    let client = connection.client().await?;
+    let types: i64 = client
+        .query_required_single(
+            "select count(schema::ObjectType filter .name like 'default::%')",
+            &(),
+        )
+        .await?;
+    ensure!(
+        types > 0,
+        "the restored database has no types in the default module"
+    );
+    Ok(types)
+}
@@ -0,0 +1,176 @@
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::Context;
+use edgedb_derive::Queryable;
+use edgedb_protocol::model::Datetime;
+use edgedb_tokio::Client;
+use tokio::time::MissedTickBehavior;
+use uuid::Uuid;
+
+use crate::{backup, connection::ConnectOpts};
+
+// Backups on a schedule, for an instance without managed backups: every `every` the
+// database is dumped with backup::dump into `dir`, under a name with the time it started
+// (backup-2024-01-31-120000.dump, so the files also sort by age).
+//
+// Each run is recorded as a BackupRun object in the database being backed up. It's
+// inserted before the dump starts and updated when it ends, with the size of the file or
+// the error, so a run that never finished shows up as one without a finished_at. The
+// BackupRuns are what the retention policy works from: after a successful dump only the
+// newest `keep` successful backups are kept, and the files of older ones are deleted and
+// their BackupRuns marked as pruned. The metadata stays behind, as a history of backups.
+//
+// The runs are awaited one at a time, so a dump that takes longer than `every` never
+// overlaps the next one. MissedTickBehavior::Skip then drops the ticks that were missed
+// rather than starting a burst of dumps to catch up. A failed run is reported on stderr
+// and the schedule carries on: one failed dump shouldn't stop the backups that come
+// after it.
+
+#[derive(Queryable)]
+struct Started {
+    started_at: Datetime,
+    stamp: String,
+}
+
+#[derive(Queryable)]
+struct OldBackup {
+    id: Uuid,
+    path: String,
+}
+
+pub struct BackupOutcome {
+    pub path: PathBuf,
+    pub size: u64,
+    pub pruned: Vec<PathBuf>,
+}
+
+async fn dump(cli: &str, connection: &ConnectOpts, path: &Path) -> Result<u64, anyhow::Error> {
+    // The CLI blocks until the dump is written, so it gets a thread of its own
+    let (cli, connection, path) = (cli.to_string(), connection.clone(), path.to_path_buf());
+    tokio::task::spawn_blocking(move || backup::dump(&cli, &connection, &path)).await?
+}
+
+// Deletes the files of all but the newest `keep` successful backups
+async fn prune(client: &Client, keep: usize) -> Result<Vec<PathBuf>, anyhow::Error> {
+    let old: Vec<OldBackup> = client
+        .query(
+            "select BackupRun { id, path }
+            filter exists .size_bytes and not exists .pruned_at
+            order by .started_at desc
+            offset <int64>$0",
+            &(keep as i64,),
+        )
+        .await?;
+    let mut pruned = Vec::with_capacity(old.len());
+    for backup in old {
+        match std::fs::remove_file(&backup.path) {
+            // Deleted by hand already, which is as good
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            res => res?,
+        }
+        client
+            .execute(
+                "update BackupRun filter .id = <uuid>$0
+                set { pruned_at := datetime_of_statement() }",
+                &(backup.id,),
+            )
+            .await?;
+        pruned.push(backup.path.into());
+    }
+    Ok(pruned)
+}
+
+// Dumps the database into `dir` once and applies the retention policy
+pub async fn backup_once(
+    client: &Client,
+    cli: &str,
+    connection: &ConnectOpts,
+    dir: &Path,
+    keep: usize,
+) -> Result<BackupOutcome, anyhow::Error> {
+    anyhow::ensure!(keep > 0, "at least one backup has to be kept");
+    std::fs::create_dir_all(dir)?;
+    let started: Started = client
+        .query_required_single(
+            "with started_at := datetime_of_statement()
+            select {
+                started_at := started_at,
+                stamp := to_str(started_at, 'YYYY-MM-DD-HH24MISS'),
+            }",
+            &(),
+        )
+        .await?;
+    let path = dir.join(format!("backup-{}.dump", started.stamp));
+    let path_str = path
+        .to_str()
+        .context("backup directory has to be valid utf-8")?;
+    let id: Uuid = client
+        .query_required_single(
+            "select (insert BackupRun { path := <str>$0, started_at := <datetime>$1 }).id",
+            &(path_str, started.started_at),
+        )
+        .await?;
+
+    let size = match dump(cli, connection, &path).await {
+        Ok(size) => size,
+        Err(e) => {
+            // Don't leave half a dump behind where it could be mistaken for a backup
+            let _ = std::fs::remove_file(&path);
+            client
+                .execute(
+                    "update BackupRun filter .id = <uuid>$0
+                    set { finished_at := datetime_of_statement(), error := <str>$1 }",
+                    &(id, format!("{e:#}")),
+                )
+                .await?;
+            return Err(e);
+        }
+    };
+    client
+        .execute(
+            "update BackupRun filter .id = <uuid>$0
+            set { finished_at := datetime_of_statement(), size_bytes := <int64>$1 }",
+            &(id, size as i64),
+        )
+        .await?;
+
+    let pruned = prune(client, keep).await?;
+    Ok(BackupOutcome { path, size, pruned })
+}
+
+// Backs up every `every`, stopping after `runs` backups if given.
+pub async fn run(
+    client: &Client,
+    cli: &str,
+    connection: &ConnectOpts,
+    dir: &Path,
+    every: Duration,
+    keep: usize,
+    runs: Option<usize>,
+) {
+    let mut interval = tokio::time::interval(every);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    let mut ticks = 0;
+    while runs.is_none_or(|runs| ticks < runs) {
+        interval.tick().await;
+        ticks += 1;
+        match backup_once(client, cli, connection, dir, keep).await {
+            Ok(outcome) => {
+                println!(
+                    "Backed up {} bytes to {}",
+                    outcome.size,
+                    outcome.path.display()
+                );
+                for path in outcome.pruned {
+                    println!("Pruned {}", path.display());
+                }
+            }
+            Err(e) => eprintln!("Backup failed: {e:#}"),
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use edgedb_tokio::{Builder, Client};
+use hdrhistogram::Histogram;
+use tokio::task::JoinSet;
+
+use crate::random_name;
+
+// Insert throughput at different levels of concurrency, to help pick max_concurrency and a
+// batch size for a given machine and instance.
+//
+// For each level a fresh client is built with max_concurrency set to the level, so the pool
+// has exactly as many connections as there are tasks inserting. The tasks share a counter
+// and keep taking the next batch of rows until all of them are inserted. With a batch size
+// of 1 every row is its own query (and its own implicit transaction); larger batches insert
+// many rows per query from an array argument, trading round trips for bigger transactions.
+//
+// Throughput usually grows with concurrency until the server's CPU or the disk saturates
+// and then flattens out or drops again: the level where it stops growing is a good
+// max_concurrency. Compare a few batch sizes too, batching often helps more than
+// concurrency does.
+//
+// Throughput alone hides the tail: a level can have the best rows/sec while a few queries
+// wait much longer than the rest for a connection or a lock. Every query's latency is
+// recorded in an HDR histogram (microsecond resolution, 3 significant digits), and with
+// --histogram the percentiles are printed for each level.
+
+const INSERT_QUERY: &str = "for n in array_unpack(<array<int64>>$0) union (
+    insert BenchRow { run := <str>$1, n := n, payload := 'row ' ++ <str>n }
+)";
+
+pub struct WriteResult {
+    pub concurrency: usize,
+    pub rows: usize,
+    pub elapsed: Duration,
+    // Latency of each insert query, in microseconds
+    pub latency: Histogram<u64>,
+}
+
+// Up to a minute, anything slower is recorded as a minute
+fn new_histogram() -> Histogram<u64> {
+    Histogram::new_with_bounds(1, 60_000_000, 3).expect("valid histogram bounds")
+}
+
+pub fn print_percentiles(latency: &Histogram<u64>) {
+    let ms = |micros: u64| micros as f64 / 1000.0;
+    println!(
+        "    p50 {:.2}ms  p95 {:.2}ms  p99 {:.2}ms  p99.9 {:.2}ms  max {:.2}ms  ({} queries)",
+        ms(latency.value_at_quantile(0.5)),
+        ms(latency.value_at_quantile(0.95)),
+        ms(latency.value_at_quantile(0.99)),
+        ms(latency.value_at_quantile(0.999)),
+        ms(latency.max()),
+        latency.len(),
+    );
+}
+
+impl WriteResult {
+    pub fn rows_per_sec(&self) -> f64 {
+        self.rows as f64 / self.elapsed.as_secs_f64()
+    }
+}
+
+pub async fn connect(concurrency: usize) -> Result<Client, edgedb_tokio::Error> {
+    let config = Builder::new()
+        .max_concurrency(concurrency)
+        .build_env()
+        .await?;
+    let client = Client::new(&config);
+    // Connect before the clock starts
+    client.ensure_connected().await?;
+    Ok(client)
+}
+
+// Inserts `rows` BenchRows tagged with `run` using `concurrency` tasks
+pub async fn insert_rows(
+    client: &Client,
+    run: &str,
+    rows: usize,
+    concurrency: usize,
+    batch_size: usize,
+) -> Result<WriteResult, anyhow::Error> {
+    let next = Arc::new(AtomicUsize::new(0));
+    let start = Instant::now();
+    let mut tasks = JoinSet::new();
+    for _ in 0..concurrency {
+        let client = client.clone();
+        let next = next.clone();
+        let run = run.to_string();
+        tasks.spawn(async move {
+            // One histogram per task so recording doesn't need a lock, merged at the end
+            let mut latency = new_histogram();
+            loop {
+                let from = next.fetch_add(batch_size, Ordering::Relaxed);
+                if from >= rows {
+                    return Ok::<_, edgedb_tokio::Error>(latency);
+                }
+                let batch: Vec<i64> = (from..(from + batch_size).min(rows))
+                    .map(|n| n as i64)
+                    .collect();
+                let query_start = Instant::now();
+                client.execute(INSERT_QUERY, &(batch, &run)).await?;
+                latency.saturating_record(query_start.elapsed().as_micros() as u64);
+            }
+        });
+    }
+    let mut latency = new_histogram();
+    while let Some(res) = tasks.join_next().await {
+        latency.add(res??)?;
+    }
+    Ok(WriteResult {
+        concurrency,
+        rows,
+        elapsed: start.elapsed(),
+        latency,
+    })
+}
+
+pub async fn bench_writes(
+    rows: usize,
+    levels: &[usize],
+    batch_size: usize,
+    histogram: bool,
+) -> Result<(), anyhow::Error> {
+    anyhow::ensure!(batch_size > 0, "the batch size must be at least 1");
+    let run = random_name();
+    let mut results = Vec::new();
+    for &concurrency in levels {
+        let client = connect(concurrency).await?;
+        let res = insert_rows(&client, &run, rows, concurrency, batch_size).await?;
+        println!(
+            "concurrency {:>3}: {} rows in {:.2?} ({:.0} rows/sec)",
+            res.concurrency,
+            res.rows,
+            res.elapsed,
+            res.rows_per_sec()
+        );
+        if histogram {
+            print_percentiles(&res.latency);
+        }
+        results.push(res);
+    }
+
+    println!("\nconcurrency,rows_per_sec");
+    for res in &results {
+        println!("{},{:.0}", res.concurrency, res.rows_per_sec());
+    }
+
+    // Clean up after ourselves. A single delete of every row of the run is fine here, but
+    // for millions of rows it's kinder to the server to delete in batches.
+    let client = connect(1).await?;
+    client
+        .execute("delete BenchRow filter .run = <str>$0", &(&run,))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,142 @@
+use std::future::Future;
+
+use edgedb_protocol::{query_arg::QueryArgs, QueryResult};
+use edgedb_tokio::Client;
+use tokio::runtime::{self, Runtime};
+use uuid::Uuid;
+
+use crate::{connection::ConnectOpts, random_name};
+
+// The client from synchronous code: a CLI without an async main, a rayon pipeline, a
+// callback from a C library. BlockingClient owns a tokio runtime of its own and a Client
+// created on it, and each method is the async one run to completion with block_on.
+//
+// The runtime is a multi-threaded one with its own worker threads. They drive the
+// connections, so any number of threads can be inside block_on at once and all of them
+// make progress, which is what a thread pool of callers needs. The pitfalls:
+//
+// * Never use it from async code. block_on inside a runtime panics ("Cannot start a
+//   runtime from within a runtime"), and dropping a BlockingClient there panics too.
+//   Async code has the async Client. Going the other way (async code calling something
+//   that uses a BlockingClient) needs spawn_blocking or a thread, as main does for run.
+// * Don't swap in a current_thread runtime and Handle::block_on from other threads. Such
+//   a runtime only does any work while one thread is inside its block_on, so the others
+//   wait for replies that nothing reads: a deadlock, not an error.
+// * Don't call it from inside a transaction closure. The transaction holds a connection
+//   while the blocking call waits for another one, and a pool that's all held by waiting
+//   transactions never frees one up.
+// * The pool is shared: 16 threads blocking on queries at once use as many connections
+//   as the pool allows, and the rest wait their turn.
+
+pub struct BlockingClient {
+    runtime: Runtime,
+    client: Client,
+}
+
+impl BlockingClient {
+    pub fn connect(connection: &ConnectOpts) -> Result<Self, anyhow::Error> {
+        let runtime = runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()?;
+        let client = runtime.block_on(connection.client())?;
+        Ok(BlockingClient { runtime, client })
+    }
+
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    pub fn query<R, A>(&self, query: &str, arguments: &A) -> Result<Vec<R>, edgedb_tokio::Error>
+    where
+        R: QueryResult,
+        A: QueryArgs,
+    {
+        self.block_on(self.client.query(query, arguments))
+    }
+
+    pub fn query_single<R, A>(
+        &self,
+        query: &str,
+        arguments: &A,
+    ) -> Result<Option<R>, edgedb_tokio::Error>
+    where
+        R: QueryResult,
+        A: QueryArgs,
+    {
+        self.block_on(self.client.query_single(query, arguments))
+    }
+
+    pub fn query_required_single<R, A>(
+        &self,
+        query: &str,
+        arguments: &A,
+    ) -> Result<R, edgedb_tokio::Error>
+    where
+        R: QueryResult,
+        A: QueryArgs,
+    {
+        self.block_on(self.client.query_required_single(query, arguments))
+    }
+
+    pub fn execute<A: QueryArgs>(
+        &self,
+        query: &str,
+        arguments: &A,
+    ) -> Result<(), edgedb_tokio::Error> {
+        self.block_on(self.client.execute(query, arguments))
+    }
+}
+
+// Inserts an Account from each of `threads` threads sharing the client, like a rayon
+// par_iter would
+fn insert_from_threads(
+    client: &BlockingClient,
+    prefix: &str,
+    threads: i64,
+) -> Result<Vec<Uuid>, edgedb_tokio::Error> {
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..threads)
+            .map(|n| {
+                scope.spawn(move || {
+                    client.query_required_single(
+                        "select (insert Account {
+                            username := <str>$0 ++ '_' ++ <str><int64>$1
+                        }).id",
+                        &(prefix, n),
+                    )
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("a worker panicked"))
+            .collect()
+    })
+}
+
+// Synchronous all the way down: call it from a thread that isn't running async code
+pub fn run(connection: &ConnectOpts) -> Result<(), anyhow::Error> {
+    let client = BlockingClient::connect(connection)?;
+
+    let query = "select 'Hello from ' ++ <str>$0";
+    let greeting: String = client.query_required_single(query, &("a blocking call",))?;
+    println!("{greeting}\n");
+
+    let prefix = format!("blocking{}", random_name());
+    let res = insert_from_threads(&client, &prefix, 8)
+        .map_err(anyhow::Error::from)
+        .and_then(|inserted| {
+            let query = "select count(Account filter .username like <str>$0 ++ '%')";
+            let count: i64 = client.query_required_single(query, &(&prefix,))?;
+            println!("{} threads inserted {count} Accounts", inserted.len());
+            assert_eq!(count, 8);
+            Ok(())
+        });
+
+    client.execute(
+        "delete Account filter .username like <str>$0 ++ '%'",
+        &(&prefix,),
+    )?;
+    res
+}
@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use edgedb_protocol::model::Datetime;
+use edgedb_tokio::Client;
+
+use crate::progress;
+
+// Deleting a lot of objects, here audit log entries past their retention period. A single
+// `delete AuditEntry filter .at < cutoff` is one statement and so one transaction: on a
+// big table it runs for a long time, holds its locks until the end, can hit the session's
+// query timeout and then rolls back everything it did.
+//
+// Instead the objects are deleted in bounded batches, each its own short transaction:
+//
+//   select count((delete (select AuditEntry filter ... order by .at limit <int64>$2)))
+//
+// until a batch deletes nothing. Other queries get to run between batches, a failure only
+// loses the batch it happened in, and running it again carries on where it stopped.
+//
+// The cutoff is worked out once at the start, so entries written while it runs aren't
+// picked up (and the loop can't chase a moving target). With dry_run the matching entries
+// are only counted.
+
+#[derive(Debug, PartialEq)]
+pub struct PruneReport {
+    pub matched: i64,
+    pub deleted: i64,
+    pub batches: usize,
+}
+
+const FILTER: &str = "filter .at < <datetime>$0
+    and (not exists <optional str>$1 or .object_type ?= <optional str>$1)";
+
+pub async fn cutoff(client: &Client, older_than: Duration) -> Result<Datetime, anyhow::Error> {
+    let older_than =
+        edgedb_protocol::model::Duration::from_micros(older_than.as_micros().try_into()?);
+    Ok(client
+        .query_required_single(
+            "select datetime_of_statement() - <duration>$0",
+            &(older_than,),
+        )
+        .await?)
+}
+
+pub async fn count(
+    client: &Client,
+    cutoff: Datetime,
+    object_type: Option<&str>,
+) -> Result<i64, edgedb_tokio::Error> {
+    client
+        .query_required_single(
+            &format!("select count((select AuditEntry {FILTER}))"),
+            &(cutoff, object_type.map(str::to_string)),
+        )
+        .await
+}
+
+pub async fn delete_batch(
+    client: &Client,
+    cutoff: Datetime,
+    object_type: Option<&str>,
+    batch_size: i64,
+) -> Result<i64, edgedb_tokio::Error> {
+    client
+        .query_required_single(
+            &format!(
+                "select count((delete (
+                    select AuditEntry {FILTER} order by .at limit <int64>$2
+                )))"
+            ),
+            &(cutoff, object_type.map(str::to_string), batch_size),
+        )
+        .await
+}
+
+// Deletes the AuditEntries older than `older_than` (of one object type, if given)
+// `batch_size` at a time, with a progress bar
+pub async fn prune_audit(
+    client: &Client,
+    older_than: Duration,
+    object_type: Option<&str>,
+    batch_size: i64,
+    dry_run: bool,
+) -> Result<PruneReport, anyhow::Error> {
+    anyhow::ensure!(batch_size > 0, "batch size has to be at least 1");
+    let cutoff = cutoff(client, older_than).await?;
+    let matched = count(client, cutoff, object_type).await?;
+    let mut report = PruneReport {
+        matched,
+        deleted: 0,
+        batches: 0,
+    };
+    if dry_run || matched == 0 {
+        return Ok(report);
+    }
+
+    let bar = progress::bar(matched as u64, "deleted");
+    loop {
+        let deleted = delete_batch(client, cutoff, object_type, batch_size).await?;
+        if deleted == 0 {
+            break;
+        }
+        report.deleted += deleted;
+        report.batches += 1;
+        bar.inc(deleted as u64);
+        bar.set_message(format!("in {} batches", report.batches));
+    }
+    bar.finish();
+    Ok(report)
+}
@@ -0,0 +1,226 @@
+use comfy_table::Table;
+use edgedb_derive::Queryable;
+use edgedb_errors::MissingRequiredError;
+use edgedb_tokio::Client;
+use uuid::Uuid;
+
+use crate::{display_result, random_name};
+
+// Which Rust type a property or link decodes into, by its cardinality. Task in
+// dbschema/default.esdl has one of each, and Task below is the struct they decode into:
+//
+//   modifier          | Rust field | when there's nothing
+//   ------------------+------------+------------------------------------------
+//   required (single) | T          | can't happen, the insert fails
+//   optional (single) | Option<T>  | None
+//   required multi    | Vec<T>     | can't happen, the insert fails
+//   optional multi    | Vec<T>     | an empty Vec (not Option<Vec<T>>)
+//
+// T is the scalar's Rust type for a property, and a Queryable struct for a link's shape.
+// Computed fields follow what the expression can return: `count(...)` is always one
+// value, a backlink is a multi. The same goes for the whole result: query_required_single
+// returns T, query_single Option<T> and query Vec<T>.
+//
+// Getting it wrong fails when the result is decoded, not when the code compiles: a String
+// for a multi doesn't match the descriptor at all, and a String for an optional property
+// decodes until the first time it's empty. Both are shown at the end of run.
+
+#[derive(Debug, Queryable)]
+pub struct Person {
+    pub username: String,
+}
+
+#[derive(Debug, Queryable)]
+pub struct Task {
+    // required single
+    pub title: String,
+    // optional single
+    pub description: Option<String>,
+    // required multi, never empty
+    pub tags: Vec<String>,
+    // optional multi, empty rather than None
+    pub notes: Vec<String>,
+    pub owner: Person,
+    pub assignee: Option<Person>,
+    pub watchers: Vec<Person>,
+    pub reviewers: Vec<Person>,
+}
+
+const SHAPE: &str = "{
+    title,
+    description,
+    tags,
+    notes,
+    owner: { username },
+    assignee: { username },
+    watchers: { username } order by .username,
+    reviewers: { username } order by .username,
+}";
+
+// The wrong types for an optional property and for a multi property
+#[derive(Debug, Queryable)]
+pub struct DescriptionAsString {
+    pub title: String,
+    pub description: String,
+}
+
+#[derive(Debug, Queryable)]
+pub struct TagsAsString {
+    pub title: String,
+    pub tags: String,
+}
+
+pub fn print_matrix() {
+    let mut table = Table::new();
+    table.set_header(vec!["modifier", "Rust field", "when there's nothing"]);
+    table.add_row(vec![
+        "required (single)",
+        "T",
+        "can't happen, the insert fails",
+    ]);
+    table.add_row(vec!["optional (single)", "Option<T>", "None"]);
+    table.add_row(vec![
+        "required multi",
+        "Vec<T>",
+        "can't happen, the insert fails",
+    ]);
+    table.add_row(vec!["optional multi", "Vec<T>", "an empty Vec"]);
+    println!("{table}");
+}
+
+const INSERT: &str = "select (insert Task {
+    title := <str>$0,
+    description := <optional str>$1,
+    tags := array_unpack(<array<str>>$2),
+    notes := array_unpack(<array<str>>$3),
+    owner := (select Account filter .id = <uuid>$4),
+    assignee := (select Account filter .id = <optional uuid>$5),
+    watchers := (select Account filter .id in array_unpack(<array<uuid>>$6)),
+    reviewers := (select Account filter .id in array_unpack(<array<uuid>>$7)),
+}).id";
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    print_matrix();
+
+    let prefix = format!("tasks{}", random_name());
+    let mut accounts = Vec::new();
+    for name in ["ada", "bob", "cy"] {
+        let id: Uuid = client
+            .query_required_single(
+                "select (insert Account { username := <str>$0 }).id",
+                &(format!("{prefix}_{name}"),),
+            )
+            .await?;
+        accounts.push(id);
+    }
+    let [ada, bob, cy] = accounts[..] else {
+        unreachable!()
+    };
+
+    let res = async {
+        // Everything set
+        let full: Uuid = client
+            .query_required_single(
+                INSERT,
+                &(
+                    format!("{prefix} full"),
+                    Some("All of them".to_string()),
+                    vec!["b".to_string(), "a".to_string()],
+                    vec!["a note".to_string()],
+                    ada,
+                    Some(bob),
+                    vec![bob, cy],
+                    vec![cy],
+                ),
+            )
+            .await?;
+        // Only what's required
+        let minimal: Uuid = client
+            .query_required_single(
+                INSERT,
+                &(
+                    format!("{prefix} minimal"),
+                    None::<String>,
+                    vec!["a".to_string()],
+                    Vec::<String>::new(),
+                    ada,
+                    None::<Uuid>,
+                    vec![ada],
+                    Vec::<Uuid>::new(),
+                ),
+            )
+            .await?;
+
+        let query = format!("select Task {SHAPE} filter .id = <uuid>$0");
+        let mut task: Task = client.query_required_single(&query, &(full,)).await?;
+        // A multi property has no order of its own
+        task.tags.sort();
+        display_result(&query, &task);
+        assert_eq!(task.description.as_deref(), Some("All of them"));
+        assert_eq!(task.tags, ["a", "b"]);
+        assert_eq!(task.notes, ["a note"]);
+        assert_eq!(task.owner.username, format!("{prefix}_ada"));
+        assert!(task.assignee.is_some());
+        assert_eq!(task.watchers.len(), 2);
+        assert_eq!(task.reviewers.len(), 1);
+
+        let task: Task = client.query_required_single(&query, &(minimal,)).await?;
+        display_result(&query, &task);
+        assert_eq!(task.description, None);
+        assert_eq!(task.tags, ["a"]);
+        assert!(task.notes.is_empty());
+        assert!(task.assignee.is_none());
+        assert_eq!(task.watchers.len(), 1);
+        assert!(task.reviewers.is_empty());
+
+        // A required multi can't be empty
+        let res = client
+            .query_required_single::<Uuid, _>(
+                INSERT,
+                &(
+                    format!("{prefix} no tags"),
+                    None::<String>,
+                    Vec::<String>::new(),
+                    Vec::<String>::new(),
+                    ada,
+                    None::<Uuid>,
+                    vec![ada],
+                    Vec::<Uuid>::new(),
+                ),
+            )
+            .await;
+        display_result("insert without tags", &res);
+        assert!(res.is_err_and(|e| e.is::<MissingRequiredError>()));
+
+        // The wrong Rust types: T for an optional property works while it's set...
+        let query = "select Task { title, description } filter .id = <uuid>$0";
+        let res: Result<DescriptionAsString, _> =
+            client.query_required_single(query, &(full,)).await;
+        display_result(query, &res);
+        assert!(res.is_ok());
+        // ...and fails for the first Task without one
+        let res: Result<DescriptionAsString, _> =
+            client.query_required_single(query, &(minimal,)).await;
+        display_result(query, &res);
+        assert!(res.is_err());
+        // T for a multi never works
+        let query = "select Task { title, tags } filter .id = <uuid>$0";
+        let res: Result<TagsAsString, _> = client.query_required_single(query, &(minimal,)).await;
+        display_result(query, &res);
+        assert!(res.is_err());
+        Ok(())
+    }
+    .await;
+
+    // The Tasks first, as their links keep the Accounts from being deleted
+    client
+        .execute("delete Task filter .title like <str>$0 ++ '%'", &(&prefix,))
+        .await?;
+    client
+        .execute(
+            "delete Account filter .username like <str>$0 ++ '%'",
+            &(&prefix,),
+        )
+        .await?;
+    res
+}
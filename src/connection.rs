@@ -0,0 +1,54 @@
+use edgedb_tokio::{Builder, Client};
+
+// The connection options that can be given on the command line, on top of what
+// Builder::build_env picks up by itself (the EDGEDB_* environment variables and the
+// project linked with `edgedb project init`). With none of them set the result is the same
+// as edgedb_tokio::create_client().
+//
+// The same options are turned into flags for the edgedb CLI by cli_args, so commands that
+// shell out to it (see backup.rs) talk to the same database as the client. Anything set
+// through the environment doesn't need translating, as the CLI reads the same variables.
+
+#[derive(Debug, Clone, Default)]
+pub struct ConnectOpts {
+    pub instance: Option<String>,
+    pub dsn: Option<String>,
+    pub database: Option<String>,
+}
+
+impl ConnectOpts {
+    pub fn builder(&self) -> Result<Builder, edgedb_tokio::Error> {
+        let mut builder = Builder::new();
+        if let Some(instance) = &self.instance {
+            builder.instance(instance)?;
+        }
+        if let Some(dsn) = &self.dsn {
+            builder.dsn(dsn)?;
+        }
+        if let Some(database) = &self.database {
+            builder.database(database)?;
+        }
+        Ok(builder)
+    }
+
+    pub async fn client(&self) -> Result<Client, edgedb_tokio::Error> {
+        let config = self.builder()?.build_env().await?;
+        Ok(Client::new(&config))
+    }
+
+    pub fn cli_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        let flags = [
+            ("--instance", &self.instance),
+            ("--dsn", &self.dsn),
+            ("--database", &self.database),
+        ];
+        for (flag, value) in flags {
+            if let Some(value) = value {
+                args.push(flag.to_string());
+                args.push(value.clone());
+            }
+        }
+        args
+    }
+}
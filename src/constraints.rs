@@ -0,0 +1,111 @@
+use edgedb_errors::ConstraintViolationError;
+use edgedb_tokio::Client;
+
+use crate::{display_result, random_name};
+
+// Constraints with their own error messages (see Profile in dbschema/default.esdl), and
+// how a client tells the failures apart so a form can show the message next to the field
+// it's about.
+//
+// Every violation is a ConstraintViolationError, so the error kind alone only says that
+// some constraint failed. The rest is in the error itself:
+// * the message is the constraint's errmessage, written for the person filling in the form
+// * the details name the constraint and what it's on, e.g. "violated constraint
+//   'std::max_len_value' on property 'handle' of object type 'default::Profile'"
+// A constraint on a property names the property, which is the field to highlight. An
+// expression constraint on the whole type (here: team plans need a company) has no single
+// property, so it's shown for the form as a whole.
+//
+// The details are text meant for people, not an API, which is why violation falls back to
+// no field rather than failing when they don't have the expected shape.
+
+#[derive(Debug, PartialEq)]
+pub struct Violation {
+    // The property the constraint is on, None for constraints on the whole object
+    pub field: Option<String>,
+    // e.g. std::max_len_value
+    pub constraint: Option<String>,
+    pub message: String,
+}
+
+// The text between `'` after the first `prefix`
+fn quoted_after<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+    let start = text.find(prefix)? + prefix.len();
+    let rest = &text[start..];
+    rest.find('\'').map(|end| &rest[..end])
+}
+
+pub fn parse_details(message: &str, details: &str) -> Violation {
+    Violation {
+        field: quoted_after(details, "property '").map(str::to_string),
+        constraint: quoted_after(details, "constraint '").map(str::to_string),
+        message: message.to_string(),
+    }
+}
+
+// The violation, if that's what the error is
+pub fn violation(error: &edgedb_tokio::Error) -> Option<Violation> {
+    if !error.is::<ConstraintViolationError>() {
+        return None;
+    }
+    let message = error.initial_message().unwrap_or("constraint violated");
+    Some(parse_details(message, error.details().unwrap_or_default()))
+}
+
+const INSERT: &str = "insert Profile {
+    handle := <str>$0,
+    plan := <str>$1,
+    company := <optional str>$2,
+}";
+
+async fn insert(
+    client: &Client,
+    handle: &str,
+    plan: &str,
+    company: Option<&str>,
+) -> Result<(), edgedb_tokio::Error> {
+    client
+        .execute(INSERT, &(handle, plan, company.map(str::to_string)))
+        .await
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let handle = random_name().to_lowercase();
+
+    let res = async {
+        insert(client, &handle, "team", Some("Acme")).await?;
+        println!("Inserted profile {handle}\n");
+
+        // Each of these breaks one rule, and the expected field to highlight
+        let attempts = [
+            (handle.clone(), "free", None, Some("handle")),
+            ("ab".to_string(), "free", None, Some("handle")),
+            ("a".repeat(21), "free", None, Some("handle")),
+            ("Not-Valid".to_string(), "free", None, Some("handle")),
+            (format!("{handle}_2"), "gold", None, Some("plan")),
+            (format!("{handle}_3"), "team", None, None),
+        ];
+        for (attempt, plan, company, field) in attempts {
+            let res = insert(client, &attempt, plan, company).await;
+            display_result(INSERT, &res);
+            let error = res.expect_err("the insert breaks a constraint");
+            let violation = violation(&error).expect("a constraint violation");
+            match &violation.field {
+                Some(field) => println!("Highlight {field}: {}", violation.message),
+                None => println!("Show on the form: {}", violation.message),
+            }
+            println!("Constraint: {:?}\n", violation.constraint);
+            assert_eq!(violation.field.as_deref(), field);
+        }
+        Ok(())
+    }
+    .await;
+
+    client
+        .execute(
+            "delete Profile filter .handle like <str>$0 ++ '%'",
+            &(handle,),
+        )
+        .await?;
+    res
+}
@@ -0,0 +1,191 @@
+use std::{fs::File, path::Path};
+
+use csv::StringRecord;
+use edgedb_protocol::model::Json;
+use edgedb_tokio::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::{policies::admin_client, progress};
+
+// Bulk loading Posts (and their authors) from a csv file with a header row:
+//
+//   username,title,body
+//   alice,Hello,My first post
+//   bob,"Commas, quotes and newlines","are fine when the field is ""quoted"""
+//
+// The file is read one record at a time with the csv crate, so memory use doesn't depend
+// on the size of the file. Each record is deserialized into PostRecord and validated into
+// a PostRow. Records that fail either step are rejected with their line number and the
+// reason, and the import carries on without them. Valid rows are collected into batches,
+// and each batch goes to EdgeDB as a json argument in a transaction of two queries: one
+// that creates any Accounts that don't exist yet, and one that inserts the Posts.
+//
+// The rejected records can be written to a csv of their own with write_rejected, to fix
+// them and import just those again.
+//
+// The progress bar is driven by the reader's position in the file, so there's no need to
+// count the records up front.
+
+// A Post and its author's username, as read from a csv record or (see ingest.rs) a line
+// of json
+#[derive(Deserialize)]
+pub struct PostRecord {
+    username: String,
+    title: String,
+    body: String,
+}
+
+#[derive(Serialize)]
+pub struct PostRow {
+    username: String,
+    title: String,
+    body: String,
+}
+
+#[derive(Serialize)]
+pub struct Rejected {
+    pub line: u64,
+    pub reason: String,
+}
+
+pub struct ImportReport {
+    pub imported: usize,
+    pub rejected: Vec<Rejected>,
+}
+
+pub fn validate(record: PostRecord) -> Result<PostRow, String> {
+    let username = record.username.trim();
+    if username.is_empty() {
+        return Err("username is empty".into());
+    }
+    if username.chars().any(char::is_whitespace) {
+        return Err(format!("username {username:?} contains whitespace"));
+    }
+    if username.chars().count() > 50 {
+        return Err("username is longer than 50 characters".into());
+    }
+    let title = record.title.trim();
+    if title.is_empty() {
+        return Err("title is empty".into());
+    }
+    if record.body.trim().is_empty() {
+        return Err("body is empty".into());
+    }
+    Ok(PostRow {
+        username: username.to_string(),
+        title: title.to_string(),
+        body: record.body,
+    })
+}
+
+// Inserts the Posts with their authors and returns how many there were. Needs a client
+// that doesn't apply access policies, like the one run makes.
+pub async fn insert_batch(client: &Client, batch: &[PostRow]) -> Result<usize, anyhow::Error> {
+    let batch = Json::new_unchecked(serde_json::to_string(batch)?);
+    let inserted = client
+        .transaction(|mut tx| {
+            let batch = batch.clone();
+            async move {
+                tx.execute(
+                    "for username in distinct (
+                        for row in json_array_unpack(<json>$0) union <str>row['username']
+                    ) union (
+                        insert Account { username := username } unless conflict on .username
+                    )",
+                    &(batch.clone(),),
+                )
+                .await?;
+                tx.query_required_single::<i64, _>(
+                    "with inserted := (
+                        for row in json_array_unpack(<json>$0) union (
+                            insert Post {
+                                author := assert_exists((
+                                    select Account filter .username = <str>row['username']
+                                )),
+                                title := <str>row['title'],
+                                body := <str>row['body'],
+                            }
+                        )
+                    )
+                    select count(inserted)",
+                    &(batch,),
+                )
+                .await
+            }
+        })
+        .await?;
+    Ok(inserted as usize)
+}
+
+pub async fn run(
+    client: &Client,
+    path: &Path,
+    batch_size: usize,
+) -> Result<ImportReport, anyhow::Error> {
+    anyhow::ensure!(batch_size > 0, "batch size has to be at least 1");
+    // Inserts Posts for any author
+    let client = admin_client(client);
+
+    let file = File::open(path)?;
+    let bar = progress::bytes_bar(file.metadata()?.len());
+    // flexible: a record with missing or extra fields is rejected by deserialize below
+    // instead of failing the whole read
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(file);
+    let headers = reader.headers()?.clone();
+    let mut report = ImportReport {
+        imported: 0,
+        rejected: Vec::new(),
+    };
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut record = StringRecord::new();
+    loop {
+        let more = match reader.read_record(&mut record) {
+            Ok(more) => more,
+            Err(e) if matches!(e.kind(), csv::ErrorKind::Io(_)) => return Err(e.into()),
+            // e.g. a field that isn't valid utf-8
+            Err(e) => {
+                report.rejected.push(Rejected {
+                    line: e.position().map_or(0, |p| p.line()),
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+        if more {
+            // Line numbers start at 1 with the header
+            let line = record.position().map_or(0, |p| p.line());
+            let row = record
+                .deserialize::<PostRecord>(Some(&headers))
+                .map_err(|e| e.to_string())
+                .and_then(validate);
+            match row {
+                Ok(row) => batch.push(row),
+                Err(reason) => report.rejected.push(Rejected { line, reason }),
+            }
+        }
+        if (batch.len() == batch_size || !more) && !batch.is_empty() {
+            report.imported += insert_batch(&client, &batch).await?;
+            batch.clear();
+            bar.set_position(reader.position().byte());
+            bar.set_message(format!(
+                "{} imported, {} rejected",
+                report.imported,
+                report.rejected.len()
+            ));
+        }
+        if !more {
+            break;
+        }
+    }
+    bar.finish();
+    Ok(report)
+}
+
+pub fn write_rejected(path: &Path, rejected: &[Rejected]) -> Result<(), anyhow::Error> {
+    let mut writer = csv::Writer::from_path(path)?;
+    for rejected in rejected {
+        writer.serialize(rejected)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
@@ -0,0 +1,83 @@
+use edgedb_tokio::Client;
+
+use crate::{display_result, highlight::format_query, random_name};
+
+// DDL straight from the client: a scratch type is created, filled, altered and dropped
+// with ordinary execute calls, as schema changes are just EdgeQL statements too.
+//
+// This is a demo of what's possible, not how an application should change its schema.
+// DDL run like this changes the database without a migration file, so the schema in
+// dbschema/ no longer describes the database: `edgedb migration create` would try to drop
+// anything created here, and other copies of the database (staging, a teammate's local
+// instance) never get the change. Migrations are reviewed, repeatable and ordered, which
+// is what production schema changes need. Runtime DDL is for things like test fixtures
+// and throwaway experiments, and even then the scratch type is always dropped again, here
+// whether or not the steps in between worked.
+//
+// What can stop it from working:
+// * Capabilities: every statement is checked against what the connection is allowed to
+//   do. The binary protocol client allows DDL, but the EdgeQL-over-HTTP endpoint and
+//   read-only transactions don't, and fail with a DisabledCapabilityError.
+// * The allow_bare_ddl setting: with `configure instance set allow_bare_ddl :=
+//   cfg::AllowBareDDL.NeverAllow` (a common choice once a project uses migrations)
+//   the server rejects DDL outside of migrations.
+// * Names can't be query arguments, so type and property names are formatted into the
+//   statements. Only ever do that with names the code made up itself.
+// * Each DDL statement takes a lock on the schema and invalidates cached query plans, so
+//   running it often on a busy database slows everything else down.
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    // A type per run, so runs in parallel don't get in each other's way
+    let name = format!("Scratch{}", random_name());
+
+    let query = format!("create type default::{name} {{ create required property label -> str; }}");
+    client.execute(&query, &()).await?;
+    println!("Executed: {}\n", format_query(&query, "Executed: ".len()));
+
+    let res = async {
+        let query = format!(
+            "for label in {{'one', 'two', 'three'}} union (insert {name} {{ label := label }})"
+        );
+        client.execute(&query, &()).await?;
+        println!("Executed: {}\n", format_query(&query, "Executed: ".len()));
+
+        // Adding a required property to a type that already has objects needs a value for
+        // them, which the default gives
+        let query = format!(
+            "alter type default::{name} {{
+                create required property size -> int64 {{ set default := 0; }};
+            }}"
+        );
+        client.execute(&query, &()).await?;
+        println!("Executed: {}\n", format_query(&query, "Executed: ".len()));
+
+        let query = format!("update {name} set {{ size := len(.label) }}");
+        client.execute(&query, &()).await?;
+        let query = format!("select ({name}.label, {name}.size) order by {name}.label");
+        let rows: Vec<(String, i64)> = client.query(&query, &()).await?;
+        display_result(&query, &rows);
+        assert_eq!(
+            rows,
+            [
+                ("one".to_string(), 3),
+                ("three".to_string(), 5),
+                ("two".to_string(), 3)
+            ]
+        );
+
+        // The new type is in the schema module like every other one
+        let query = "select count(schema::ObjectType filter .name = <str>$0)";
+        let count: i64 = client
+            .query_required_single(query, &(format!("default::{name}"),))
+            .await?;
+        display_result(query, &count);
+        assert_eq!(count, 1);
+        Ok(())
+    }
+    .await;
+
+    let query = format!("drop type default::{name}");
+    client.execute(&query, &()).await?;
+    println!("Executed: {}\n", format_query(&query, "Executed: ".len()));
+    res
+}
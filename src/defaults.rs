@@ -0,0 +1,96 @@
+use edgedb_derive::Queryable;
+use edgedb_protocol::{model::Datetime, value::Value};
+use edgedb_tokio::Client;
+use uuid::Uuid;
+
+use crate::{display_result, random_name};
+
+// Values the server fills in by itself, from the schema of Post in dbschema/default.esdl:
+// * `default` is used when an insert doesn't give a value: created_at and updated_at get
+//   the time of the statement, and the author is the Account in the current_user global.
+//   An insert that does give a value overrides the default.
+// * `rewrite insert` and `rewrite update` run on every insert or update, and their value
+//   wins over anything the query sets. updated_at is rewritten on each update, and the
+//   slug is made from the title. In a rewrite `__specified__.title` says whether the
+//   statement set the title, so an update of the body alone keeps the old slug.
+//
+// None of these are sent by the client, but they're ordinary properties afterwards. The
+// values are in the object straight away, so wrapping the insert or update in a select
+// with a shape returns them in the same round trip, and they decode like any other field.
+
+#[derive(Debug, Queryable)]
+pub struct PostRow {
+    pub title: String,
+    pub slug: Option<String>,
+    pub author_name: String,
+    pub created_at: Datetime,
+    pub updated_at: Datetime,
+}
+
+const SHAPE: &str = "{ title, slug, author_name := .author.username, created_at, updated_at }";
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let username = format!("defaults{}", random_name());
+    let id: Uuid = client
+        .query_required_single(
+            "select (insert Account { username := <str>$0 }).id",
+            &(&username,),
+        )
+        .await?;
+    let author = client.with_globals_fn(|globals| {
+        globals.set("current_user", Value::Uuid(id));
+    });
+
+    let res = async {
+        // Only the title and body are sent, and the slug the query sets is rewritten
+        let query = format!(
+            "select (insert Post {{
+                title := <str>$0,
+                body := <str>$1,
+                slug := 'ignored',
+            }}) {SHAPE}"
+        );
+        let inserted: PostRow = author
+            .query_required_single(&query, &("Defaults, and Rewrites", "First draft"))
+            .await?;
+        display_result(&query, &inserted);
+        assert_eq!(inserted.slug.as_deref(), Some("defaults-and-rewrites"));
+        assert_eq!(inserted.author_name, username);
+        assert_eq!(inserted.created_at, inserted.updated_at);
+
+        // Without the title, the slug stays as it was and updated_at moves on. The author
+        // has this one Post.
+        let query = format!(
+            "select (update Post filter .author.id ?= global current_user set {{
+                body := <str>$0
+            }}) {SHAPE}"
+        );
+        let edited: PostRow = author
+            .query_required_single(&query, &("Second draft",))
+            .await?;
+        display_result(&query, &edited);
+        assert_eq!(edited.slug, inserted.slug);
+        assert_eq!(edited.created_at, inserted.created_at);
+        assert!(edited.updated_at > inserted.updated_at);
+
+        // A new title makes a new slug
+        let query = format!(
+            "select (update Post filter .author.id ?= global current_user set {{
+                title := <str>$0
+            }}) {SHAPE}"
+        );
+        let renamed: PostRow = author
+            .query_required_single(&query, &("Rewrites in EdgeDB 3",))
+            .await?;
+        display_result(&query, &renamed);
+        assert_eq!(renamed.slug.as_deref(), Some("rewrites-in-edgedb-3"));
+        assert!(renamed.updated_at > edited.updated_at);
+        Ok(())
+    }
+    .await;
+
+    client
+        .execute("delete Account filter .id = <uuid>$0", &(id,))
+        .await?;
+    res
+}
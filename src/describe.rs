@@ -0,0 +1,30 @@
+use edgedb_tokio::Client;
+
+use crate::export::valid_type_name;
+
+// The server's own description of a type, for checking a Queryable struct against it.
+// `describe type X as text` lists the type's properties and links (including inherited
+// ones with `verbose`), their cardinality, target types and constraints.
+//
+// Queryable decodes fields by position, so a struct has to list its fields in the same
+// order as the query's shape, and with matching types. A mismatch fails at runtime with a
+// descriptor error that names the field, and this is the quickest way to see what the
+// field should have been: `cargo run -- describe Account`.
+
+pub async fn describe_type(
+    client: &Client,
+    type_name: &str,
+    verbose: bool,
+) -> Result<String, anyhow::Error> {
+    // The name goes into the query text, as describe doesn't take arguments
+    anyhow::ensure!(
+        valid_type_name(type_name),
+        "invalid type name {type_name:?}"
+    );
+    let query = if verbose {
+        format!("describe type {type_name} as text verbose")
+    } else {
+        format!("describe type {type_name} as text")
+    };
+    Ok(client.query_required_single(&query, &()).await?)
+}
@@ -0,0 +1,124 @@
+use edgedb_derive::Queryable;
+use edgedb_tokio::Client;
+
+use crate::random_name;
+
+// Nearest-neighbor search over embedding vectors with the pgvector extension, the retrieval
+// half of a RAG (retrieval-augmented generation) app: documents are stored with an
+// embedding of their content, and the ones closest to the embedding of a question are
+// what gets handed to the language model as context.
+//
+// * The schema (dbschema/default.esdl) declares Embedding as a vector with a fixed number
+//   of dimensions and Document with an Embedding property, plus an ivfflat index using
+//   cosine distance so the search doesn't compare against every Document.
+// * Vectors go into queries as plain Vec<f32> arguments, which are array<float32> on the
+//   EdgeDB side and cast to Embedding there. No vector type is needed in Rust.
+// * ext::pgvector::cosine_distance gives 0 for vectors pointing the same way and up to 2
+//   for opposite ones. Ordering by it and taking the first few is the nearest-neighbor
+//   query. The distance is a float64, decoded into the Neighbor struct with its content.
+//
+// Embeddings come from a model (OpenAI, Cohere, a local sentence-transformers model...)
+// called from Rust before inserting. To keep the example self-contained the vectors here
+// are made up, with 3 dimensions standing for how much a text is about animals, food and
+// computers.
+
+#[derive(Debug, Queryable)]
+pub struct Neighbor {
+    pub content: String,
+    pub distance: f64,
+}
+
+pub async fn insert(
+    client: &Client,
+    collection: &str,
+    content: &str,
+    embedding: Vec<f32>,
+) -> Result<(), edgedb_tokio::Error> {
+    client
+        .execute(
+            "insert Document {
+                collection := <str>$0,
+                content := <str>$1,
+                embedding := <Embedding><array<float32>>$2,
+            }",
+            &(collection, content, embedding),
+        )
+        .await
+}
+
+// The `limit` Documents in `collection` closest to `embedding`, closest first
+pub async fn nearest(
+    client: &Client,
+    collection: &str,
+    embedding: Vec<f32>,
+    limit: usize,
+) -> Result<Vec<Neighbor>, edgedb_tokio::Error> {
+    client
+        .query(
+            "with query := <Embedding><array<float32>>$1
+            select Document {
+                content,
+                distance := ext::pgvector::cosine_distance(.embedding, query),
+            }
+            filter .collection = <str>$0
+            order by .distance
+            limit <int64>$2",
+            &(collection, embedding, limit as i64),
+        )
+        .await
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let collection = format!("embeddings{}", random_name());
+    let documents = [
+        ("Cats sleep up to sixteen hours a day", [0.9, 0.1, 0.0]),
+        ("Dogs were domesticated from wolves", [0.95, 0.05, 0.05]),
+        ("Sourdough needs a starter and patience", [0.05, 0.9, 0.1]),
+        (
+            "Rust's borrow checker prevents data races",
+            [0.0, 0.05, 0.95],
+        ),
+        (
+            "Robot vacuums keep getting confused by cats",
+            [0.6, 0.0, 0.7],
+        ),
+    ];
+    for (content, embedding) in documents {
+        insert(client, &collection, content, embedding.to_vec()).await?;
+    }
+
+    let res = async {
+        for (question, embedding) in [
+            ("Which pets are good for a small flat?", vec![1.0, 0.0, 0.0]),
+            ("How do I bake bread?", vec![0.0, 1.0, 0.0]),
+            ("Can computers look after animals?", vec![0.5, 0.0, 0.5]),
+        ] {
+            let neighbors = nearest(client, &collection, embedding, 2).await?;
+            println!("{question}");
+            for neighbor in &neighbors {
+                println!("  {:.3}  {}", neighbor.distance, neighbor.content);
+            }
+        }
+
+        let bread = nearest(client, &collection, vec![0.0, 1.0, 0.0], 1).await?;
+        assert_eq!(bread[0].content, "Sourdough needs a starter and patience");
+        let mixed = nearest(client, &collection, vec![0.5, 0.0, 0.5], 1).await?;
+        assert_eq!(
+            mixed[0].content,
+            "Robot vacuums keep getting confused by cats"
+        );
+        // The same direction is distance 0 whatever the length
+        let same = nearest(client, &collection, vec![1.8, 0.2, 0.0], 1).await?;
+        assert!(same[0].distance < 1e-6);
+        Ok(())
+    }
+    .await;
+
+    client
+        .execute(
+            "delete Document filter .collection = <str>$0",
+            &(&collection,),
+        )
+        .await?;
+    res
+}
@@ -0,0 +1,133 @@
+use edgedb_tokio::Client;
+use uuid::Uuid;
+
+use crate::{
+    display_result, random_name,
+    search::{AccountStatus, UnknownStatus},
+};
+
+// An array of enum values in a property: SavedSearch.statuses is an
+// `array<AccountStatus>`, e.g. "everything Suspended or Closed" saved under a name.
+//
+// A single enum value goes in and out of a query as its label, a str, with a cast on the
+// EdgeQL side (see the search example). That carries over to collections, one cast for
+// the whole array:
+// * in: the Rust values become their labels, a Vec<String> bound as `<array<str>>$0`,
+//   and `<array<AccountStatus>>` casts each element. A label that isn't one of the
+//   enum's fails the cast with an InvalidValueError, so the database still checks them.
+// * out: `<array<str>>.statuses` casts each element back to its label, which decodes
+//   into a Vec<String>, parsed into AccountStatus values with FromStr.
+// Arrays keep their order and their duplicates, unlike a multi property. To use the
+// values as a set in a query, array_unpack them: `.status in array_unpack(.statuses)`.
+
+pub fn to_labels(statuses: &[AccountStatus]) -> Vec<String> {
+    statuses
+        .iter()
+        .map(|status| status.as_str().to_string())
+        .collect()
+}
+
+pub fn from_labels(labels: &[String]) -> Result<Vec<AccountStatus>, UnknownStatus> {
+    labels.iter().map(|label| label.parse()).collect()
+}
+
+pub async fn save(
+    client: &Client,
+    name: &str,
+    statuses: &[AccountStatus],
+) -> Result<Uuid, edgedb_tokio::Error> {
+    client
+        .query_required_single(
+            "select (insert SavedSearch {
+                name := <str>$0,
+                statuses := <array<AccountStatus>><array<str>>$1,
+            }).id",
+            &(name, to_labels(statuses)),
+        )
+        .await
+}
+
+pub async fn statuses(
+    client: &Client,
+    id: Uuid,
+) -> Result<Option<Vec<AccountStatus>>, anyhow::Error> {
+    let labels: Option<Vec<String>> = client
+        .query_single(
+            "select <array<str>>(select SavedSearch filter .id = <uuid>$0).statuses",
+            &(id,),
+        )
+        .await?;
+    Ok(labels.as_deref().map(from_labels).transpose()?)
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let prefix = format!("enums{}", random_name());
+    client
+        .execute(
+            "for account in {('ada', 'Active'), ('bob', 'Suspended'), ('cy', 'Closed')} union (
+                insert Account {
+                    username := <str>$0 ++ '_' ++ account.0,
+                    status := <AccountStatus>account.1,
+                }
+            )",
+            &(&prefix,),
+        )
+        .await?;
+
+    let res = async {
+        let wanted = [AccountStatus::Suspended, AccountStatus::Closed];
+        let id = save(client, &prefix, &wanted).await?;
+        let saved = statuses(client, id).await?;
+        display_result("statuses", &saved);
+        assert_eq!(saved.as_deref(), Some(&wanted[..]));
+
+        // The array as a set of values to filter by
+        let query = "with saved := (select SavedSearch filter .id = <uuid>$0)
+            select Account.username
+            filter Account.username like <str>$1 ++ '%'
+            and Account.status in array_unpack(saved.statuses)
+            order by Account.username";
+        let usernames: Vec<String> = client.query(query, &(id, &prefix)).await?;
+        display_result(query, &usernames);
+        assert_eq!(usernames, [format!("{prefix}_bob"), format!("{prefix}_cy")]);
+
+        // And the other way around, the searches that include a status
+        let query = "select SavedSearch.name
+            filter <AccountStatus><str>$0 in array_unpack(SavedSearch.statuses)
+            and SavedSearch.name = <str>$1";
+        let names: Vec<String> = client.query(query, &("Closed", &prefix)).await?;
+        display_result(query, &names);
+        assert_eq!(names, [prefix.clone()]);
+
+        // A label the enum doesn't have fails the cast, in the database
+        let res = client
+            .query_required_single::<Uuid, _>(
+                "select (insert SavedSearch {
+                    name := <str>$0,
+                    statuses := <array<AccountStatus>><array<str>>$1,
+                }).id",
+                &(&prefix, vec!["Active".to_string(), "Deleted".to_string()]),
+            )
+            .await;
+        display_result("an unknown label", &res);
+        assert!(res.is_err());
+        // and in Rust
+        assert_eq!(
+            from_labels(&["Deleted".to_string()]),
+            Err(UnknownStatus("Deleted".to_string()))
+        );
+        Ok(())
+    }
+    .await;
+
+    client
+        .execute("delete SavedSearch filter .name = <str>$0", &(&prefix,))
+        .await?;
+    client
+        .execute(
+            "delete Account filter .username like <str>$0 ++ '%'",
+            &(&prefix,),
+        )
+        .await?;
+    res
+}
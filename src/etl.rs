@@ -0,0 +1,208 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    path::PathBuf,
+    pin::Pin,
+    time::{Duration, Instant},
+};
+
+use comfy_table::Table;
+use edgedb_derive::Queryable;
+use edgedb_tokio::Client;
+use futures::{stream, Stream, TryStreamExt};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{export::valid_type_name, streaming::stream_query};
+
+// A skeleton for one-off data jobs, split into the three classic stages so each can be
+// swapped out on its own:
+//
+// * extract: a stream of raw records from a csv file (a `username` column) or from a str
+//   property of objects already in the database (e.g. `--from-type Person --field name`
+//   to give every Person an Account), read a page at a time
+// * transform: plain Rust, here normalizing the raw values into usernames (trimmed,
+//   lowercase, runs of anything but letters, digits and _ turned into a single _) and
+//   dropping empty values, reserved names and duplicates
+// * load: inserting Accounts a batch at a time, skipping usernames that are taken
+//
+// The stages are connected by streams, so records flow through one batch at a time and
+// memory doesn't grow with the input. Two progress bars show how far extraction and
+// loading have got, and the Summary at the end accounts for every record. With --dry-run
+// nothing is loaded, which is the first thing to run on a data fix.
+
+pub enum Source {
+    Csv(PathBuf),
+    // The query for the property's values, see Source::property
+    Property(String),
+}
+
+impl Source {
+    // The str values of `field` on the objects of `type_name`
+    pub fn property(type_name: &str, field: &str) -> Result<Source, anyhow::Error> {
+        // Both end up in the query text as they are
+        anyhow::ensure!(
+            valid_type_name(type_name) && valid_type_name(field) && !field.contains("::"),
+            "invalid type or field name"
+        );
+        Ok(Source::Property(format!(
+            "select {type_name} {{ id, value := <str>.{field} }}
+            filter .id > <uuid>$0
+            order by .id
+            limit <int64>$1"
+        )))
+    }
+}
+
+#[derive(Default)]
+pub struct Summary {
+    pub extracted: usize,
+    pub invalid: usize,
+    pub duplicates: usize,
+    pub inserted: usize,
+    pub already_existed: usize,
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut table = Table::new();
+        table.set_header(vec!["records", "count"]);
+        for (name, count) in [
+            ("extracted", self.extracted),
+            ("invalid or reserved", self.invalid),
+            ("duplicates", self.duplicates),
+            ("inserted", self.inserted),
+            ("already existed", self.already_existed),
+        ] {
+            table.add_row(vec![name.to_string(), count.to_string()]);
+        }
+        write!(f, "{table}\nTook {:.1?}", self.elapsed)
+    }
+}
+
+type Records<'a> = Pin<Box<dyn Stream<Item = Result<String, anyhow::Error>> + 'a>>;
+
+#[derive(Deserialize)]
+struct CsvRecord {
+    username: String,
+}
+
+#[derive(Queryable)]
+struct FieldValue {
+    id: Uuid,
+    value: Option<String>,
+}
+
+// Extract
+fn extract<'a>(
+    client: &'a Client,
+    source: &'a Source,
+    page_size: usize,
+) -> Result<Records<'a>, anyhow::Error> {
+    match source {
+        Source::Csv(path) => {
+            let records = csv::Reader::from_path(path)?
+                .into_deserialize::<CsvRecord>()
+                .map(|record| Ok(record?.username));
+            Ok(Box::pin(stream::iter(records)))
+        }
+        Source::Property(query) => {
+            let limit = page_size as i64;
+            let values = stream_query(
+                client,
+                query,
+                page_size,
+                (Uuid::nil(), limit),
+                move |last: &FieldValue| (last.id, limit),
+            )
+            // Objects without a value are skipped
+            .try_filter_map(|row| async move { Ok(row.value) })
+            .map_err(anyhow::Error::from);
+            Ok(Box::pin(values))
+        }
+    }
+}
+
+// Transform
+pub fn normalize(raw: &str) -> Option<String> {
+    let mut username = String::new();
+    for c in raw.trim().chars().flat_map(char::to_lowercase) {
+        if c.is_alphanumeric() {
+            username.push(c);
+        } else if !username.is_empty() && !username.ends_with('_') {
+            username.push('_');
+        }
+    }
+    let username = username.trim_end_matches('_');
+    // Usernames starting with "admin" are reserved by the access policies on Account
+    if username.is_empty() || username.starts_with("admin") {
+        return None;
+    }
+    Some(username.to_string())
+}
+
+// Load, returning how many of the usernames were inserted
+async fn load(client: &Client, usernames: &[String]) -> Result<usize, edgedb_tokio::Error> {
+    let inserted: i64 = client
+        .query_required_single(
+            "with inserted := (
+                for username in array_unpack(<array<str>>$0) union (
+                    insert Account { username := username } unless conflict on .username
+                )
+            )
+            select count(inserted)",
+            &(usernames.to_vec(),),
+        )
+        .await?;
+    Ok(inserted as usize)
+}
+
+pub async fn run(
+    client: &Client,
+    source: Source,
+    batch_size: usize,
+    dry_run: bool,
+) -> Result<Summary, anyhow::Error> {
+    anyhow::ensure!(batch_size > 0, "batch size has to be at least 1");
+    let start = Instant::now();
+    let progress = MultiProgress::new();
+    let style = ProgressStyle::with_template("{spinner} {prefix:>9} {pos} {msg}")?;
+    let extracted = progress.add(ProgressBar::new_spinner().with_style(style.clone()));
+    extracted.set_prefix("extracted");
+    let loaded = progress.add(ProgressBar::new_spinner().with_style(style));
+    loaded.set_prefix(if dry_run { "would load" } else { "loaded" });
+
+    let mut summary = Summary::default();
+    let mut seen = HashSet::new();
+    let mut batches = extract(client, &source, batch_size)?.try_chunks(batch_size);
+    while let Some(batch) = batches.try_next().await.map_err(|e| e.1)? {
+        summary.extracted += batch.len();
+        extracted.inc(batch.len() as u64);
+
+        let mut usernames = Vec::with_capacity(batch.len());
+        for raw in &batch {
+            match normalize(raw) {
+                None => summary.invalid += 1,
+                Some(username) if !seen.insert(username.clone()) => summary.duplicates += 1,
+                Some(username) => usernames.push(username),
+            }
+        }
+
+        if !dry_run && !usernames.is_empty() {
+            let inserted = load(client, &usernames).await?;
+            summary.inserted += inserted;
+            summary.already_existed += usernames.len() - inserted;
+        }
+        loaded.inc(usernames.len() as u64);
+        loaded.set_message(format!(
+            "{} invalid, {} duplicates",
+            summary.invalid, summary.duplicates
+        ));
+    }
+    extracted.finish();
+    loaded.finish();
+    summary.elapsed = start.elapsed();
+    Ok(summary)
+}
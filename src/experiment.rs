@@ -0,0 +1,67 @@
+use std::{fs::File, io::Write, path::Path};
+
+use crate::{bench, random_name};
+
+// Sweeps max_concurrency against a fixed workload: the same number of tasks inserting the
+// same number of rows each time, only the size of the client's connection pool changes.
+// With fewer connections than tasks the tasks queue for a connection inside the client, so
+// latency includes that wait while throughput shows whether more connections would have
+// helped at all.
+//
+// Every setting is run `repeats` times (the first run after connecting is often slower)
+// and each run is a row of csv, ready for a spreadsheet or a plotting script:
+//
+// max_concurrency,repeat,tasks,queries,elapsed_ms,queries_per_sec,p50_ms,p95_ms,p99_ms,max_ms
+//
+// Start with a setting below the number of tasks and sweep up past it. The smallest pool
+// that reaches the plateau in throughput without hurting p99 is the one to use: extra
+// connections beyond that only use up the server's connection limit.
+
+pub struct Settings {
+    pub max_concurrency: Vec<usize>,
+    pub tasks: usize,
+    pub queries: usize,
+    pub repeats: usize,
+}
+
+const HEADER: &str =
+    "max_concurrency,repeat,tasks,queries,elapsed_ms,queries_per_sec,p50_ms,p95_ms,p99_ms,max_ms";
+
+pub async fn run(settings: Settings, out: Option<&Path>) -> Result<(), anyhow::Error> {
+    let mut out: Box<dyn Write> = match out {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(std::io::stdout()),
+    };
+    writeln!(out, "{HEADER}")?;
+
+    let run = random_name();
+    for &max_concurrency in &settings.max_concurrency {
+        let client = bench::connect(max_concurrency).await?;
+        for repeat in 1..=settings.repeats {
+            let res =
+                bench::insert_rows(&client, &run, settings.queries, settings.tasks, 1).await?;
+            let ms = |micros: u64| micros as f64 / 1000.0;
+            writeln!(
+                out,
+                "{},{},{},{},{:.1},{:.1},{:.3},{:.3},{:.3},{:.3}",
+                max_concurrency,
+                repeat,
+                settings.tasks,
+                res.rows,
+                res.elapsed.as_secs_f64() * 1000.0,
+                res.rows_per_sec(),
+                ms(res.latency.value_at_quantile(0.5)),
+                ms(res.latency.value_at_quantile(0.95)),
+                ms(res.latency.value_at_quantile(0.99)),
+                ms(res.latency.max()),
+            )?;
+            out.flush()?;
+        }
+    }
+
+    let client = bench::connect(1).await?;
+    client
+        .execute("delete BenchRow filter .run = <str>$0", &(&run,))
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,203 @@
+use edgedb_tokio::Client;
+use serde_json::Value;
+
+use crate::{highlight, rollback::in_rollback};
+
+// Profiling a query from the terminal: `cargo run -- explain 'select Account { username }'`
+// runs it under `analyze` (EdgeDB 3+), which executes the query and returns the plan
+// Postgres used for it, with how long each step took and how many rows it produced, and
+// prints the plan as a tree:
+//
+// Aggregate  1.42 ms  1 row
+// └─ Seq Scan on Account  0.96 ms  1000 rows  # Account { username }
+//
+// Times are Postgres's actual total time per loop, in red when a step takes at least half
+// the query's time and in yellow at a tenth, so the slow part stands out. After the `#` is
+// the part of the EdgeQL query the step comes from, when the server says.
+//
+// analyze really runs the query, so it's run in a transaction that is always rolled back
+// (see rollback.rs): explaining an insert or a delete shows its plan without changing
+// anything. Queries with parameters can't be explained here, put the values in the query.
+//
+// The json analyze returns isn't a stable format, and its layout differs between server
+// versions, so it's read loosely rather than into structs: anything with a node type is a
+// step of the plan, and the steps nested in it, under whatever key, are its children.
+
+const LABEL: [&str; 3] = ["plan_type", "node_type", "Node Type"];
+const RELATION: [&str; 2] = ["relation_name", "Relation Name"];
+const TIME: [&str; 2] = ["actual_total_time", "Actual Total Time"];
+const ROWS: [&str; 2] = ["actual_rows", "Actual Rows"];
+const LOOPS: [&str; 2] = ["actual_loops", "Actual Loops"];
+
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+pub async fn analyze(client: &Client, query: &str) -> Result<Value, anyhow::Error> {
+    let query = query.trim().trim_end_matches(';');
+    anyhow::ensure!(!query.is_empty(), "nothing to explain");
+    let analyze = format!("analyze {query}");
+    let plan: String = in_rollback(client, |mut tx| {
+        let analyze = analyze.clone();
+        async move { tx.query_required_single(&analyze, &()).await }
+    })
+    .await?;
+    Ok(serde_json::from_str(&plan)?)
+}
+
+fn field<'a>(node: &'a Value, keys: &[&str]) -> Option<&'a Value> {
+    keys.iter().find_map(|key| node.get(key))
+}
+
+// The steps in `value`: itself if it's one, otherwise the steps nested in it
+fn steps(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(items) => items.iter().flat_map(steps).collect(),
+        Value::Object(_) if field(value, &LABEL).is_some() => vec![value],
+        Value::Object(_) => children(value),
+        _ => Vec::new(),
+    }
+}
+
+fn children(node: &Value) -> Vec<&Value> {
+    let Value::Object(fields) = node else {
+        return Vec::new();
+    };
+    fields
+        .iter()
+        .filter(|(key, _)| *key != "contexts")
+        .flat_map(|(_, value)| match value {
+            Value::Array(_) | Value::Object(_) => steps(value),
+            _ => Vec::new(),
+        })
+        .collect()
+}
+
+// The first line of the EdgeQL the step comes from, either given as text or as a range
+// of one of the query buffers
+fn context(node: &Value, buffers: Option<&Value>) -> Option<String> {
+    let context = node.get("contexts")?.get(0)?;
+    let text = match context.get("text").and_then(Value::as_str) {
+        Some(text) => text,
+        None => {
+            let buffer = buffers?.get(context.get("buffer_idx")?.as_u64()? as usize)?;
+            let start = context.get("start")?.as_u64()? as usize;
+            let end = context.get("end")?.as_u64()? as usize;
+            buffer.as_str()?.get(start..end)?
+        }
+    };
+    let line = text.lines().next()?.trim();
+    if line.chars().count() > 50 {
+        Some(format!("{}...", line.chars().take(47).collect::<String>()))
+    } else {
+        Some(line.to_string())
+    }
+}
+
+struct Render<'a> {
+    out: String,
+    buffers: Option<&'a Value>,
+    // The slowest step's time, which the others are compared to
+    total: f64,
+    colors: bool,
+}
+
+impl Render<'_> {
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.colors {
+            format!("{color}{text}{RESET}")
+        } else {
+            text.to_string()
+        }
+    }
+
+    fn line(&self, node: &Value) -> String {
+        let label = field(node, &LABEL).and_then(Value::as_str).unwrap_or("?");
+        let mut line = self.paint(BOLD, label);
+        if let Some(relation) = field(node, &RELATION).and_then(Value::as_str) {
+            line.push_str(&format!(" on {relation}"));
+        }
+        if let Some(time) = field(node, &TIME).and_then(Value::as_f64) {
+            let text = format!("{time:.2} ms");
+            let share = if self.total > 0.0 {
+                time / self.total
+            } else {
+                0.0
+            };
+            line.push_str("  ");
+            line.push_str(&match share {
+                share if share >= 0.5 => self.paint(RED, &text),
+                share if share >= 0.1 => self.paint(YELLOW, &text),
+                _ => text,
+            });
+        }
+        if let Some(rows) = field(node, &ROWS).and_then(Value::as_f64) {
+            let plural = if rows == 1.0 { "" } else { "s" };
+            line.push_str(&format!("  {rows} row{plural}"));
+        }
+        let loops = field(node, &LOOPS).and_then(Value::as_f64);
+        if let Some(loops) = loops.filter(|&loops| loops > 1.0) {
+            line.push_str(&format!(" x {loops} loops"));
+        }
+        if let Some(context) = context(node, self.buffers) {
+            line.push_str("  ");
+            line.push_str(&self.paint(DIM, &format!("# {context}")));
+        }
+        line
+    }
+
+    fn node(&mut self, node: &Value, prefix: &str, connector: &str, indent: &str) {
+        let line = self.line(node);
+        self.out.push_str(&format!("{prefix}{connector}{line}\n"));
+        let prefix = format!("{prefix}{indent}");
+        let children = children(node);
+        for (i, child) in children.iter().enumerate() {
+            if i + 1 == children.len() {
+                self.node(child, &prefix, "└─ ", "   ");
+            } else {
+                self.node(child, &prefix, "├─ ", "│  ");
+            }
+        }
+    }
+}
+
+fn slowest(nodes: &[&Value]) -> f64 {
+    nodes
+        .iter()
+        .map(|node| {
+            let time = field(node, &TIME).and_then(Value::as_f64).unwrap_or(0.0);
+            time.max(slowest(&children(node)))
+        })
+        .fold(0.0, f64::max)
+}
+
+// The plan in `analysis` (what analyze returned) as an indented tree, one step per line
+pub fn render(analysis: &Value, colors: bool) -> String {
+    let plan = analysis.get("fine_grained").unwrap_or(analysis);
+    let roots = steps(plan);
+    let mut render = Render {
+        out: String::new(),
+        buffers: analysis.get("buffers"),
+        total: slowest(&roots),
+        colors,
+    };
+    for root in roots {
+        render.node(root, "", "", "");
+    }
+    render.out
+}
+
+pub async fn run(client: &Client, query: &str) -> Result<(), anyhow::Error> {
+    let analysis = analyze(client, query).await?;
+    let label = "Analyzed: ";
+    println!("{label}{}\n", highlight::format_query(query, label.len()));
+    let tree = render(&analysis, highlight::colors_enabled());
+    anyhow::ensure!(
+        !tree.is_empty(),
+        "the server's analyze output has no plan in it"
+    );
+    print!("{tree}");
+    Ok(())
+}
@@ -0,0 +1,199 @@
+use std::{
+    io::{self, BufWriter, Write},
+    pin::pin,
+};
+
+use edgedb_protocol::value::Value;
+use edgedb_tokio::Client;
+use futures::TryStreamExt;
+use uuid::Uuid;
+
+use crate::{progress, streaming::stream_query, values};
+
+// Exporting every object of a type without holding the whole table in memory. The objects
+// are read with stream_query (see streaming.rs), paging through them in id order:
+//
+//   select Account { `created_at`, `id`, ... } filter .id > <uuid>$0 order by .id
+//   limit <int64>$1
+//
+// and each one is written out as soon as it arrives, so memory use depends on the page
+// size rather than on the size of the table.
+//
+// The type is only known at runtime, so the rows are decoded as untyped Values and turned
+// into json with values::to_json. The shape is the type's properties from the schema
+// module, like `{ * }` (no links), but without SECRET_PROPERTIES: `{ * }` would happily
+// write Account.password_hash out for whoever runs the command. An export is a copy of
+// the data outside of the database's access control, so check what a type holds before
+// exporting it, and add any new secret properties to the list.
+//
+// Json Lines gets one json object per line. Csv gets a header with the property names of
+// the first object, and then a line per object with strings written as they are,
+// empty sets as empty cells and everything else (numbers, booleans, dates, arrays...) as
+// its json.
+
+#[derive(Debug, Clone, Copy)]
+pub enum Format {
+    Csv,
+    JsonLines,
+}
+
+// The type name ends up in the query text as it is, so only plain (optionally module
+// qualified) names are allowed
+pub fn valid_type_name(name: &str) -> bool {
+    name.split("::").all(|part| {
+        let mut chars = part.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+pub fn id_of(object: &Value) -> Option<Uuid> {
+    let Value::Object { shape, fields } = object else {
+        return None;
+    };
+    shape
+        .elements
+        .iter()
+        .zip(fields)
+        .find_map(|(element, field)| match field {
+            Some(Value::Uuid(id)) if element.name == "id" => Some(*id),
+            _ => None,
+        })
+}
+
+// Never exported, whatever the type
+pub const SECRET_PROPERTIES: [&str; 1] = ["password_hash"];
+
+// The type's properties as a shape, without SECRET_PROPERTIES
+pub async fn shape(client: &Client, type_name: &str) -> Result<String, anyhow::Error> {
+    // The schema module only knows fully qualified names
+    let type_name = if type_name.contains("::") {
+        type_name.to_string()
+    } else {
+        format!("default::{type_name}")
+    };
+    let mut properties: Vec<String> = client
+        .query(
+            "select (select schema::ObjectType filter .name = <str>$0).properties.name",
+            &(&type_name,),
+        )
+        .await?;
+    anyhow::ensure!(!properties.is_empty(), "no type called {type_name}");
+    properties.sort();
+    // Backquoted so property names that are also keywords still work
+    Ok(properties
+        .iter()
+        .filter(|name| !SECRET_PROPERTIES.contains(&name.as_str()))
+        .map(|name| format!("`{name}`"))
+        .collect::<Vec<_>>()
+        .join(", "))
+}
+
+fn csv_cell(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+enum Sink<W: Write> {
+    Csv {
+        writer: csv::Writer<W>,
+        header: Option<Vec<String>>,
+    },
+    JsonLines(BufWriter<W>),
+}
+
+impl<W: Write> Sink<W> {
+    fn new(format: Format, out: W) -> Self {
+        match format {
+            Format::Csv => Sink::Csv {
+                writer: csv::Writer::from_writer(out),
+                header: None,
+            },
+            Format::JsonLines => Sink::JsonLines(BufWriter::new(out)),
+        }
+    }
+
+    fn write(&mut self, object: &serde_json::Map<String, serde_json::Value>) -> io::Result<()> {
+        match self {
+            Sink::Csv { writer, header } => {
+                if header.is_none() {
+                    let names: Vec<String> = object.keys().cloned().collect();
+                    writer.write_record(&names)?;
+                    *header = Some(names);
+                }
+                let cells = header
+                    .iter()
+                    .flatten()
+                    .map(|name| object.get(name).map(csv_cell).unwrap_or_default());
+                writer.write_record(cells)?;
+            }
+            Sink::JsonLines(writer) => {
+                serde_json::to_writer(&mut *writer, object)?;
+                writer.write_all(b"\n")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Sink::Csv { mut writer, .. } => writer.flush(),
+            Sink::JsonLines(mut writer) => writer.flush(),
+        }
+    }
+}
+
+// Writes every object of `type_name` to `out` and returns how many there were
+pub async fn run(
+    client: &Client,
+    type_name: &str,
+    format: Format,
+    page_size: usize,
+    out: impl Write,
+) -> Result<usize, anyhow::Error> {
+    anyhow::ensure!(
+        valid_type_name(type_name),
+        "invalid type name {type_name:?}"
+    );
+    anyhow::ensure!(page_size > 0, "page size has to be at least 1");
+    let shape = shape(client, type_name).await?;
+    let query = format!(
+        "select {type_name} {{ {shape} }} filter .id > <uuid>$0 order by .id limit <int64>$1"
+    );
+    // Only for the progress bar, objects inserted or deleted while the export runs make it
+    // a little off
+    let total: i64 = client
+        .query_required_single(&format!("select count({type_name})"), &())
+        .await?;
+    let bar = progress::bar(total as u64, "objects");
+    let limit = page_size as i64;
+    let mut rows = pin!(stream_query(
+        client,
+        &query,
+        page_size,
+        (Uuid::nil(), limit),
+        move |last: &Value| {
+            let id = id_of(last).expect("the shape has the id");
+            (id, limit)
+        },
+    ));
+
+    let mut sink = Sink::new(format, out);
+    let mut count = 0;
+    while let Some(row) = rows.try_next().await? {
+        let serde_json::Value::Object(object) = values::to_json(&row)? else {
+            anyhow::bail!("expected an object, got {row:?}");
+        };
+        sink.write(&object)?;
+        count += 1;
+        bar.inc(1);
+    }
+    sink.finish()?;
+    bar.finish();
+    Ok(count)
+}
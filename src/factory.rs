@@ -0,0 +1,141 @@
+use edgedb_protocol::value::Value;
+use edgedb_tokio::Client;
+use uuid::Uuid;
+
+use crate::{progress, random_name};
+
+// Builders for test and seed data, so creating an Account with a few Posts doesn't take
+// hand-written EdgeQL every time:
+//
+// let alice = AccountFactory::new().with_posts(3).create(&client).await?;
+//
+// Anything not set gets a random but realistic-looking value. The Posts are inserted by a
+// client with `global current_user` set to the new Account, just like a logged in user
+// would insert them, so they pass the access policies on Post and get their author from
+// the default on the link.
+
+const WORDS: &[&str] = &[
+    "edgedb", "rust", "query", "schema", "graph", "link", "shape", "tokio", "async", "type",
+    "policy", "global", "index", "json", "client", "server", "select", "insert",
+];
+
+fn sentence(words: usize) -> String {
+    let mut sentence: Vec<&str> = (0..words)
+        .map(|_| WORDS[fastrand::usize(..WORDS.len())])
+        .collect();
+    sentence.dedup();
+    let sentence = sentence.join(" ");
+    let mut chars = sentence.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => sentence,
+    }
+}
+
+#[derive(Debug)]
+pub struct CreatedAccount {
+    pub id: Uuid,
+    pub username: String,
+    pub posts: Vec<Uuid>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct AccountFactory {
+    username: Option<String>,
+    posts: usize,
+}
+
+impl AccountFactory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn username(mut self, username: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self
+    }
+
+    // Posts to create for the Account, each with a random title and body
+    pub fn with_posts(mut self, posts: usize) -> Self {
+        self.posts = posts;
+        self
+    }
+
+    pub async fn create(&self, client: &Client) -> Result<CreatedAccount, edgedb_tokio::Error> {
+        let username = self
+            .username
+            .clone()
+            .unwrap_or_else(|| format!("user_{}", random_name()));
+        let id: Uuid = client
+            .query_required_single(
+                "select (insert Account { username := <str>$0 }).id",
+                &(&username,),
+            )
+            .await?;
+
+        let author = client.with_globals_fn(|globals| {
+            globals.set("current_user", Value::Uuid(id));
+        });
+        let mut posts = Vec::with_capacity(self.posts);
+        for _ in 0..self.posts {
+            let post = PostFactory::new().create(&author).await?;
+            posts.push(post);
+        }
+        Ok(CreatedAccount {
+            id,
+            username,
+            posts,
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct PostFactory {
+    title: Option<String>,
+    body: Option<String>,
+}
+
+impl PostFactory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+
+    // Inserts the Post as whoever `global current_user` is on the client
+    pub async fn create(&self, client: &Client) -> Result<Uuid, edgedb_tokio::Error> {
+        let title = self.title.clone().unwrap_or_else(|| sentence(4));
+        let body = self.body.clone().unwrap_or_else(|| sentence(20) + ".");
+        client
+            .query_required_single(
+                "select (insert Post { title := <str>$0, body := <str>$1 }).id",
+                &(&title, &body),
+            )
+            .await
+    }
+}
+
+// The seed command: `accounts` Accounts with `posts` Posts each
+pub async fn seed(
+    client: &Client,
+    accounts: usize,
+    posts: usize,
+) -> Result<Vec<CreatedAccount>, edgedb_tokio::Error> {
+    let factory = AccountFactory::new().with_posts(posts);
+    let mut created = Vec::with_capacity(accounts);
+    let bar = progress::bar(accounts as u64, "Accounts");
+    for _ in 0..accounts {
+        created.push(factory.create(client).await?);
+        bar.inc(1);
+    }
+    bar.finish();
+    Ok(created)
+}
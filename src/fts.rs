@@ -0,0 +1,97 @@
+use edgedb_tokio::Client;
+
+use crate::{policies::admin_client, random_name};
+
+// Full-text search over Posts with EdgeDB's built-in fts module (EdgeDB 4+). It's part of
+// the standard library rather than an extension, so the schema doesn't need a `using
+// extension` line for it. The pieces live in three places:
+//
+// * The index, in dbschema/default.esdl: a single fts::index on Post covering title and
+//   body, each with a language (for stemming, so "tokio" also finds "Tokio's") and a
+//   weight category. A type can only have one fts::index, so every field that should be
+//   searchable goes into the same one.
+// * The query, in search below: fts::search(Post, query, ...) returns a tuple of the
+//   matching Post and its score, for Posts that match only. The query string is written
+//   like a web search: words, "quoted phrases", OR, and -word to exclude. `weights` gives
+//   the weight of categories A, B, ... in order, here making a title match count more
+//   than a body match.
+// * The results, decoded here: each row is selected as an unnamed (title, score) tuple,
+//   which decodes straight into a Rust (String, f32) tuple as the score is a float32.
+//   Named tuples don't decode into Rust tuples, hence no names.
+
+// The titles of `username`'s Posts matching `query`, with their scores, best first
+pub async fn search(
+    client: &Client,
+    username: &str,
+    query: &str,
+) -> Result<Vec<(String, f32)>, edgedb_tokio::Error> {
+    client
+        .query(
+            "with results := (
+                for result in fts::search(
+                    Post, <str>$0, language := 'eng', weights := [1.0, 0.4]
+                )
+                union (
+                    select (result.object.title, result.score)
+                    filter result.object.author.username = <str>$1
+                )
+            )
+            select results order by results.1 desc",
+            &(query, username),
+        )
+        .await
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let admin = admin_client(client);
+    let username = format!("fts{}", random_name());
+    admin
+        .execute(
+            "with author := (insert Account { username := <str>$0 })
+            for post in {
+                ('Async Rust with Tokio', 'Futures, executors and pinning'),
+                ('Cast iron care', 'Dry the pan straight away or it will rust'),
+                ('Growing tomatoes', 'Plenty of sun and not too much water'),
+            }
+            union (insert Post { author := author, title := post.0, body := post.1 })",
+            &(&username,),
+        )
+        .await?;
+
+    let res = async {
+        for query in [
+            "rust",
+            "tomato",
+            "rust -tokio",
+            "\"straight away\"",
+            "compilers",
+        ] {
+            let results = search(client, &username, query).await?;
+            println!("{query}:");
+            for (title, score) in &results {
+                println!("  {score:.3}  {title}");
+            }
+            if results.is_empty() {
+                println!("  no matches");
+            }
+        }
+
+        // A match in the title outranks a match in the body
+        let titles: Vec<String> = search(client, &username, "rust")
+            .await?
+            .into_iter()
+            .map(|(title, _)| title)
+            .collect();
+        assert_eq!(titles, ["Async Rust with Tokio", "Cast iron care"]);
+        // Stemming finds the plural
+        assert_eq!(search(client, &username, "tomato").await?.len(), 1);
+        assert!(search(client, &username, "compilers").await?.is_empty());
+        Ok(())
+    }
+    .await;
+
+    admin
+        .execute("delete Account filter .username = <str>$0", &(&username,))
+        .await?;
+    res
+}
@@ -0,0 +1,82 @@
+use edgedb_derive::Queryable;
+use edgedb_protocol::value::Value;
+use edgedb_tokio::Client;
+use uuid::Uuid;
+
+use crate::{display_result, factory::PostFactory, random_name};
+
+// Calling the functions defined in dbschema/default.esdl. They're called like the
+// standard library's, and their arguments are query arguments like any other:
+// * slugify(str) -> str returns one value, so query_required_single decodes a String. The
+//   slug rewrite on Post calls it too, so the slug rules are written down once, and
+//   a client can preview a slug with exactly the rules the server will use.
+// * excerpt(str, max_len: int64 = 40) has a parameter with a default. Leaving it out uses
+//   the default, and `max_len := <int64>$1` passes it by name. The Rust value has to be
+//   the parameter's type: an i32 for an int64 parameter fails before the query runs.
+// * posts_by(str) -> set of Post returns a set, which takes a shape, filters and ordering
+//   like a type does, and decodes with query into a Vec.
+//
+// A function is the place for logic that several queries (or the schema itself) share,
+// especially when the Rust code would otherwise have to keep a copy in step with EdgeQL.
+
+#[derive(Debug, Queryable)]
+pub struct PostSlug {
+    pub title: String,
+    pub slug: Option<String>,
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let query = "select slugify(<str>$0)";
+    let slug: String = client
+        .query_required_single(query, &("  Functions in the Schema!  ",))
+        .await?;
+    display_result(query, &slug);
+    assert_eq!(slug, "functions-in-the-schema");
+
+    let body = "Schema functions are called like the standard library ones";
+    let query = "select excerpt(<str>$0)";
+    let short: String = client.query_required_single(query, &(body,)).await?;
+    display_result(query, &short);
+    assert_eq!(short, "Schema functions are called like the...");
+    let query = "select excerpt(<str>$0, max_len := <int64>$1)";
+    let shorter: String = client.query_required_single(query, &(body, 19_i64)).await?;
+    display_result(query, &shorter);
+    assert_eq!(shorter, "Schema functions...");
+
+    // Posts are inserted by their author, so the test data is set up with the author as
+    // current_user
+    let username = format!("functions{}", random_name());
+    let id: Uuid = client
+        .query_required_single(
+            "select (insert Account { username := <str>$0 }).id",
+            &(&username,),
+        )
+        .await?;
+    let author = client.with_globals_fn(|globals| {
+        globals.set("current_user", Value::Uuid(id));
+    });
+
+    let res = async {
+        for title in ["Second post", "First post"] {
+            PostFactory::new().title(title).create(&author).await?;
+        }
+        let query = "select posts_by(<str>$0) { title, slug } order by .title";
+        let posts: Vec<PostSlug> = client.query(query, &(&username,)).await?;
+        display_result(query, &posts);
+        let titles: Vec<&str> = posts.iter().map(|post| post.title.as_str()).collect();
+        assert_eq!(titles, ["First post", "Second post"]);
+
+        // The set works anywhere a set of Posts does
+        let query = "select count(posts_by(<str>$0) filter .slug = slugify(.title))";
+        let count: i64 = client.query_required_single(query, &(&username,)).await?;
+        display_result(query, &count);
+        assert_eq!(count, 2);
+        Ok(())
+    }
+    .await;
+
+    client
+        .execute("delete Account filter .id = <uuid>$0", &(id,))
+        .await?;
+    res
+}
@@ -0,0 +1,94 @@
+use edgedb_protocol::value::Value;
+use edgedb_tokio::Client;
+
+use crate::display_result;
+
+// The three kinds of globals (see the top of dbschema/default.esdl), as seen from a client
+// derived with with_globals_fn:
+//
+// * `required global region -> str` has to have a default, and the default is what every
+//   query sees until a client sets the global. That's why a required global can't be
+//   "unset" by forgetting it: there's no error to get, just the default (the tenancy
+//   example relies on this with a default that matches nothing).
+// * `global locale -> str` is optional: without a value it's the empty set, so queries
+//   use it with ?= and ?? like any other optional value.
+// * `global greeting := ...` is computed from locale. It can't be set, only read, and
+//   follows whatever the globals it uses are set to on the same client.
+//
+// Globals are part of the client's state, which is sent along with every query. The
+// errors come from that state not matching the schema: setting a global that doesn't
+// exist (a typo, or a computed global) or setting one to a value of the wrong type makes
+// every query on that client fail, before the query itself runs.
+
+const QUERY: &str = "select {
+    region := global region,
+    locale := global locale ?? '(not set)',
+    greeting := global greeting,
+}";
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    // Nothing set: the required global has its default, the optional one is empty
+    let res: Value = client.query_required_single(QUERY, &()).await?;
+    display_result(QUERY, &res);
+    let query = "select (global region, exists global locale)";
+    let res: (String, bool) = client.query_required_single(query, &()).await?;
+    display_result(query, &res);
+    assert_eq!(res, ("eu".to_string(), false));
+
+    // Set both, and the computed global follows locale
+    let french = client.with_globals_fn(|globals| {
+        globals.set("region", Value::Str("us".into()));
+        globals.set("locale", Value::Str("fr".into()));
+    });
+    let res: Value = french.query_required_single(QUERY, &()).await?;
+    display_result(QUERY, &res);
+    let query = "select (global region, global greeting)";
+    let res: (String, String) = french.query_required_single(query, &()).await?;
+    display_result(query, &res);
+    assert_eq!(res, ("us".to_string(), "Bonjour".to_string()));
+
+    // Computed globals work anywhere an expression does
+    let query = "select global greeting ++ ', ' ++ global region ++ '!'";
+    let res: String = french.query_required_single(query, &()).await?;
+    display_result(query, &res);
+    assert_eq!(res, "Bonjour, us!");
+
+    // Deriving from a derived client keeps the other globals and overrides this one
+    let german = french.with_globals_fn(|globals| {
+        globals.set("locale", Value::Str("de".into()));
+    });
+    let query = "select (global region, global greeting)";
+    let res: (String, String) = german.query_required_single(query, &()).await?;
+    display_result(query, &res);
+    assert_eq!(res, ("us".to_string(), "Hallo".to_string()));
+
+    // The errors: every query on these clients fails, whatever it is
+    let errors = [
+        (
+            "a computed global can't be set",
+            client.with_globals_fn(|globals| {
+                globals.set("greeting", Value::Str("Hi".into()));
+            }),
+        ),
+        (
+            "a misspelled global",
+            client.with_globals_fn(|globals| {
+                globals.set("regoin", Value::Str("us".into()));
+            }),
+        ),
+        (
+            "a value of the wrong type",
+            client.with_globals_fn(|globals| {
+                globals.set("region", Value::Int64(1));
+            }),
+        ),
+    ];
+    for (what, broken) in errors {
+        let res: Result<i64, _> = broken.query_required_single("select 1", &()).await;
+        println!("{what}:");
+        display_result("select 1", &res);
+        assert!(res.is_err());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use edgedb_derive::Queryable;
+use edgedb_protocol::model::Datetime;
+use edgedb_tokio::Client;
+use uuid::Uuid;
+
+use crate::{display_result, factory::AccountFactory, random_name};
+
+// A many-to-many relationship with data on the relationship itself: an Account is in any
+// number of groups, with a role in each and the date it joined. In dbschema/default.esdl
+// that's a single multi link, UserGroup.members, with `role` and `joined_at` as link
+// properties, rather than a Membership type with two links. (The type isn't just Group
+// because `group` is an EdgeQL keyword.)
+//
+// Link properties belong to the link, not to either object, so they're read and written
+// through it:
+// * adding a member sets them in the shape of the Account being linked:
+//   `members += (select Account { @role := ... } filter ...)`
+// * changing them is the same += with the Account already linked, selected through
+//   .members so the ones not being changed keep their values
+// * `-=` removes the link, and its properties with it
+// * in a select they're `@role` and `@joined_at` on .members. Queryable decodes by name,
+//   so they're given plain names in the shape (`role := @role`) to decode into Member.
+//
+// A link property can't be required, have constraints across links or be linked to, so
+// once a relationship needs its own history, permissions or links to other objects, it's
+// time for a type of its own.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupRole {
+    Owner,
+    Admin,
+    Member,
+}
+
+impl GroupRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GroupRole::Owner => "Owner",
+            GroupRole::Admin => "Admin",
+            GroupRole::Member => "Member",
+        }
+    }
+}
+
+#[derive(Debug, Queryable)]
+pub struct Member {
+    pub username: String,
+    pub id: Uuid,
+    pub role: String,
+    pub joined_at: Datetime,
+}
+
+pub async fn create_group(client: &Client, name: &str) -> Result<Uuid, edgedb_tokio::Error> {
+    client
+        .query_required_single("select (insert UserGroup { name := <str>$0 }).id", &(name,))
+        .await
+}
+
+// Returns false if the Account was already a member, whose role is left as it was
+pub async fn add_member(
+    client: &Client,
+    group: Uuid,
+    account: Uuid,
+    role: GroupRole,
+) -> Result<bool, edgedb_tokio::Error> {
+    let query = "select count((
+        update UserGroup filter .id = <uuid>$0 and <uuid>$1 not in .members.id
+        set {
+            members += (
+                select Account {
+                    @role := <GroupRole><str>$2,
+                    @joined_at := datetime_of_statement(),
+                }
+                filter .id = <uuid>$1
+            )
+        }
+    ))";
+    let updated: i64 = client
+        .query_required_single(query, &(group, account, role.as_str()))
+        .await?;
+    Ok(updated == 1)
+}
+
+// Returns false if the Account isn't a member
+pub async fn update_role(
+    client: &Client,
+    group: Uuid,
+    account: Uuid,
+    role: GroupRole,
+) -> Result<bool, edgedb_tokio::Error> {
+    let query = "select count((
+        update UserGroup filter .id = <uuid>$0 and <uuid>$1 in .members.id
+        set {
+            members += (
+                select .members {
+                    @role := <GroupRole><str>$2,
+                    @joined_at := @joined_at,
+                }
+                filter .id = <uuid>$1
+            )
+        }
+    ))";
+    let updated: i64 = client
+        .query_required_single(query, &(group, account, role.as_str()))
+        .await?;
+    Ok(updated == 1)
+}
+
+// Returns false if the Account wasn't a member
+pub async fn remove_member(
+    client: &Client,
+    group: Uuid,
+    account: Uuid,
+) -> Result<bool, edgedb_tokio::Error> {
+    let query = "select count((
+        update UserGroup filter .id = <uuid>$0 and <uuid>$1 in .members.id
+        set {
+            members -= (select Account filter .id = <uuid>$1)
+        }
+    ))";
+    let updated: i64 = client
+        .query_required_single(query, &(group, account))
+        .await?;
+    Ok(updated == 1)
+}
+
+// The group's members, in the order they joined
+pub async fn members(client: &Client, group: Uuid) -> Result<Vec<Member>, edgedb_tokio::Error> {
+    let query = "select (select UserGroup filter .id = <uuid>$0).members {
+        username,
+        id,
+        role := <str>@role,
+        joined_at := @joined_at,
+    }
+    order by @joined_at then .username";
+    client.query(query, &(group,)).await
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let prefix = format!("group{}", random_name());
+    let mut accounts = Vec::new();
+    for name in ["ann", "bob", "cat"] {
+        let account = AccountFactory::new()
+            .username(format!("{prefix}_{name}"))
+            .create(client)
+            .await?;
+        accounts.push(account.id);
+    }
+    let [ann, bob, cat] = accounts[..] else {
+        unreachable!()
+    };
+    let group = create_group(client, &prefix).await?;
+
+    let res = async {
+        assert!(add_member(client, group, ann, GroupRole::Owner).await?);
+        assert!(add_member(client, group, bob, GroupRole::Member).await?);
+        assert!(add_member(client, group, cat, GroupRole::Member).await?);
+        // Adding a member again changes nothing
+        assert!(!add_member(client, group, bob, GroupRole::Admin).await?);
+        let before = members(client, group).await?;
+        display_result("members", &before);
+        let roles: Vec<&str> = before.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, ["Owner", "Member", "Member"]);
+
+        assert!(update_role(client, group, bob, GroupRole::Admin).await?);
+        let after = members(client, group).await?;
+        display_result("members after update_role", &after);
+        let bob_before = before
+            .iter()
+            .find(|m| m.id == bob)
+            .expect("bob is a member");
+        let bob_after = after
+            .iter()
+            .find(|m| m.id == bob)
+            .expect("bob is still a member");
+        assert_eq!(bob_after.role, "Admin");
+        // The other link property is untouched
+        assert_eq!(bob_after.joined_at, bob_before.joined_at);
+
+        assert!(remove_member(client, group, cat).await?);
+        assert!(!remove_member(client, group, cat).await?);
+        assert!(!update_role(client, group, cat, GroupRole::Owner).await?);
+        let after = members(client, group).await?;
+        display_result("members after remove_member", &after);
+        let ids: Vec<Uuid> = after.iter().map(|m| m.id).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(!ids.contains(&cat));
+        Ok(())
+    }
+    .await;
+
+    client
+        .execute("delete UserGroup filter .id = <uuid>$0", &(group,))
+        .await?;
+    client
+        .execute(
+            "delete Account filter .username like <str>$0 ++ '%'",
+            &(&prefix,),
+        )
+        .await?;
+    res
+}
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use edgedb_tokio::{Builder, Client};
+use serde::{Deserialize, Serialize};
+
+use crate::QueryableAccount;
+
+// A handler shaped like an AWS Lambda (or any other one-shot/serverless) function: create the
+// client during the cold start, run a single query per invocation and shut down cleanly.
+//
+// The Builder settings that matter when connections are short-lived:
+// * wait_until_available: how long to keep retrying while the server is unreachable. The
+//   default of 30 seconds is sensible for a long-running service, but a function that's
+//   billed by the millisecond (and has its own timeout) should give up quickly.
+// * connect_timeout: the limit for a single connection attempt, which would otherwise eat
+//   up most of the invocation's time budget on a bad network.
+// * max_concurrency: an invocation handles one event at a time, so a pool of one
+//   connection is enough. Every extra connection counts against the server's connection
+//   limit once hundreds of instances of the function are running.
+
+#[derive(Debug, Deserialize)]
+pub struct Event {
+    pub username: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Response {
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+pub async fn cold_start() -> Result<Client, edgedb_tokio::Error> {
+    let config = Builder::new()
+        .wait_until_available(Duration::from_secs(2))
+        .connect_timeout(Duration::from_secs(2))
+        .max_concurrency(1)
+        .build_env()
+        .await?;
+    let client = Client::new(&config);
+    // The pool connects lazily. Connecting here keeps the connection cost inside the cold
+    // start and turns a misconfiguration into an error before any event is accepted.
+    client.ensure_connected().await?;
+    Ok(client)
+}
+
+// Runs once per event. Get-or-create keeps the handler idempotent, which matters because
+// serverless platforms retry failed invocations.
+pub async fn handle(client: &Client, event: Event) -> Response {
+    let res: Result<QueryableAccount, _> = client
+        .query_required_single(
+            "select (
+                insert Account { username := <str>$0 }
+                unless conflict on .username
+                else (select Account)
+            ) { username, id }",
+            &(&event.username,),
+        )
+        .await;
+    match res {
+        Ok(account) => Response {
+            status: 200,
+            body: serde_json::json!({ "username": account.username, "id": account.id }),
+        },
+        Err(e) => Response {
+            status: 500,
+            body: serde_json::json!({ "error": e.to_string() }),
+        },
+    }
+}
+
+// What the platform's runtime would do: build the client once, feed it events, and drop it
+// at the end. Dropping the client closes its connections, there's nothing else to shut
+// down. If the platform freezes the process between invocations instead, the pool notices
+// the dead connection on the next query and reconnects by itself.
+pub async fn run(event: &str) -> Result<(), anyhow::Error> {
+    let client = cold_start().await?;
+    let response = handle(&client, serde_json::from_str(event)?).await;
+    println!("{}", serde_json::to_string(&response)?);
+    drop(client);
+    Ok(())
+}
@@ -0,0 +1,157 @@
+use std::{
+    env,
+    io::{self, IsTerminal},
+    iter::Peekable,
+    str::CharIndices,
+    sync::LazyLock,
+};
+
+// The queries the examples print (display_result's "Queried:" and the like), with EdgeQL
+// keywords, strings, numbers, parameters and comments in color, and re-indented so a query
+// written as an indented multi-line string in the code lines up under its label instead
+// of keeping the indentation of wherever it was in the source file.
+//
+// The lexer is a few dozen lines rather than a syntax highlighting crate: EdgeQL's tokens
+// that matter for reading are easy to pick out, and nothing here needs to be exact. A
+// keyword used as a name (`.type`, `schema::Function`) isn't colored when it comes after
+// `.`, `@` or `::`, which covers paths and qualified names. Anything it doesn't recognize
+// is printed as it is.
+//
+// Colors are only used when stdout is a terminal and NO_COLOR isn't set, so output piped
+// to a file or another program stays plain text.
+
+// Separated by whitespace
+const KEYWORDS: &str = "
+    abstract alias alter and as asc by commit configure conflict create delete desc describe
+    detached distinct drop else empty exists extending false filter first for function global
+    group if ilike in insert introspect is last like limit link module multi not offset on
+    optional or order property required rollback scalar select set single start transaction
+    true type typeof union unless update using with
+";
+
+const KEYWORD: &str = "\x1b[1;35m";
+const STRING: &str = "\x1b[32m";
+const NUMBER: &str = "\x1b[36m";
+const PARAMETER: &str = "\x1b[33m";
+const COMMENT: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+// Advances past the characters matching `f`, returning the end of the last one
+fn eat(chars: &mut Peekable<CharIndices>, mut end: usize, f: impl Fn(char) -> bool) -> usize {
+    while let Some(&(i, c)) = chars.peek() {
+        if !f(c) {
+            break;
+        }
+        end = i + c.len_utf8();
+        chars.next();
+    }
+    end
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// The query with ANSI colors around its tokens
+pub fn highlight(query: &str) -> String {
+    let mut out = String::with_capacity(query.len() * 2);
+    let mut chars = query.char_indices().peekable();
+    // The last character before this token that isn't whitespace
+    let mut previous = ' ';
+    while let Some((start, c)) = chars.next() {
+        let next = start + c.len_utf8();
+        let (end, color) = match c {
+            '#' => (eat(&mut chars, next, |c| c != '\n'), Some(COMMENT)),
+            '\'' | '"' => {
+                let mut end = query.len();
+                let mut escaped = false;
+                for (i, s) in chars.by_ref() {
+                    if escaped {
+                        escaped = false;
+                    } else if s == '\\' {
+                        escaped = true;
+                    } else if s == c {
+                        end = i + 1;
+                        break;
+                    }
+                }
+                (end, Some(STRING))
+            }
+            // A quoted name, which is never a keyword
+            '`' => {
+                let end = eat(&mut chars, next, |c| c != '`');
+                chars.next();
+                (end + 1, None)
+            }
+            '$' => (eat(&mut chars, next, is_name_char), Some(PARAMETER)),
+            c if c.is_ascii_digit() => {
+                let end = eat(&mut chars, next, |c| is_name_char(c) || c == '.');
+                // Tuple element access like `.0` is a path, not a number
+                (end, (previous != '.').then_some(NUMBER))
+            }
+            c if is_name_char(c) => {
+                let end = eat(&mut chars, next, is_name_char);
+                let word = query[start..end].to_lowercase();
+                let keyword = !matches!(previous, '.' | '@' | ':')
+                    && KEYWORDS.split_whitespace().any(|keyword| keyword == word);
+                (end, keyword.then_some(KEYWORD))
+            }
+            _ => (next, None),
+        };
+        // A closing backquote that never came
+        let end = end.min(query.len());
+        let token = &query[start..end];
+        match color {
+            Some(color) => {
+                out.push_str(color);
+                out.push_str(token);
+                out.push_str(RESET);
+            }
+            None => out.push_str(token),
+        }
+        if let Some(last) = token.chars().rev().find(|c| !c.is_whitespace()) {
+            previous = last;
+        }
+    }
+    out
+}
+
+// The query with its first line as it is and the others moved `indent` columns in, after
+// taking off the indentation they have in common
+pub fn reindent(query: &str, indent: usize) -> String {
+    let mut lines = query.trim().lines();
+    let mut out = lines.next().unwrap_or_default().to_string();
+    let rest: Vec<&str> = lines.collect();
+    let common = rest
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    for line in rest {
+        out.push('\n');
+        if !line.trim().is_empty() {
+            out.push_str(&" ".repeat(indent));
+            let line = line.get(common..).unwrap_or(line.trim_start());
+            out.push_str(line.trim_end());
+        }
+    }
+    out
+}
+
+pub fn colors_enabled() -> bool {
+    static ENABLED: LazyLock<bool> = LazyLock::new(|| {
+        io::stdout().is_terminal() && !env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+    });
+    *ENABLED
+}
+
+// A query ready to print after a label `indent` characters wide, e.g. "Queried: "
+pub fn format_query(query: &str, indent: usize) -> String {
+    let query = reindent(query, indent);
+    if colors_enabled() {
+        highlight(&query)
+    } else {
+        query
+    }
+}
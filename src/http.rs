@@ -0,0 +1,158 @@
+use anyhow::{anyhow, Context};
+use reqwest::Certificate;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{display_result, random_name, Account};
+
+// EdgeDB can also be queried over plain HTTP once the edgeql_http extension is enabled
+// in the schema (see `using extension edgeql_http;` in dbschema/default.esdl).
+//
+// The binary protocol used by edgedb-tokio is the better choice for a Rust application:
+// it pools connections, retries transactions, supports transactions at all, and decodes
+// straight into Rust types with Queryable. HTTP is handy when none of that is needed or
+// available: a serverless function that can't keep a connection pool alive, a proxy or
+// tool that only speaks HTTP, or a quick script in a language without a client.
+// Every request is a single stateless query, results always come back as json, and the
+// arguments are named ($username) instead of positional ($0).
+
+// The HTTP endpoint isn't part of the project's credentials, so it is read from the environment.
+// `edgedb instance credentials` shows the port and password of a local instance.
+pub struct HttpSettings {
+    // e.g. https://localhost:10700/db/edgedb/edgeql
+    pub url: String,
+    pub user: String,
+    // Only needed for the edgeql endpoint, extensions like auth handle their own auth
+    pub password: Option<String>,
+    // Path to the instance's tls certificate (the tls_ca shown by `edgedb instance credentials`)
+    pub tls_ca_file: Option<String>,
+}
+
+impl HttpSettings {
+    pub fn from_env() -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            url: std::env::var("EDGEDB_HTTP_URL")
+                .unwrap_or_else(|_| "https://localhost:10700/db/edgedb/edgeql".to_string()),
+            user: std::env::var("EDGEDB_HTTP_USER").unwrap_or_else(|_| "edgedb".to_string()),
+            password: std::env::var("EDGEDB_HTTP_PASSWORD").ok(),
+            tls_ca_file: std::env::var("EDGEDB_HTTP_TLS_CA_FILE").ok(),
+        })
+    }
+
+    // The database's root url, which extensions such as auth are served under
+    pub fn database_url(&self) -> &str {
+        self.url.trim_end_matches("/edgeql")
+    }
+}
+
+// The json body of a request. Variables are passed by name.
+#[derive(Serialize)]
+struct HttpQuery<'a> {
+    query: &'a str,
+    variables: serde_json::Value,
+}
+
+// Successful responses have a `data` field holding a list of results, failed ones an `error` field.
+#[derive(Deserialize)]
+struct HttpResponse<T> {
+    data: Option<Vec<T>>,
+    error: Option<HttpError>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HttpError {
+    pub message: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub code: u32,
+}
+
+pub struct HttpClient {
+    http: reqwest::Client,
+    settings: HttpSettings,
+}
+
+// Also used by other examples that talk to the instance over http, like auth_ext.
+// Local instances use a self-signed certificate, so unless its certificate file is given
+// verification is skipped. That is only acceptable for a development instance on the
+// same machine.
+pub fn reqwest_client(settings: &HttpSettings) -> Result<reqwest::Client, anyhow::Error> {
+    let mut builder = reqwest::Client::builder();
+    match &settings.tls_ca_file {
+        Some(path) => {
+            let pem = std::fs::read(path).with_context(|| format!("reading {path}"))?;
+            builder = builder.add_root_certificate(Certificate::from_pem(&pem)?);
+        }
+        None if settings.url.starts_with("https://localhost") => {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+        None => {}
+    }
+    Ok(builder.build()?)
+}
+
+impl HttpClient {
+    pub fn new(settings: HttpSettings) -> Result<Self, anyhow::Error> {
+        Ok(Self {
+            http: reqwest_client(&settings)?,
+            settings,
+        })
+    }
+
+    pub async fn query<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        variables: serde_json::Value,
+    ) -> Result<Vec<T>, anyhow::Error> {
+        let response: HttpResponse<T> = self
+            .http
+            .post(&self.settings.url)
+            .basic_auth(&self.settings.user, self.settings.password.as_ref())
+            .json(&HttpQuery { query, variables })
+            .send()
+            .await?
+            .json()
+            .await?;
+        match (response.data, response.error) {
+            (_, Some(error)) => Err(anyhow!(
+                "{} ({}, code {})",
+                error.message,
+                error.kind,
+                error.code
+            )),
+            (Some(data), None) => Ok(data),
+            (None, None) => Err(anyhow!("response had neither data nor an error")),
+        }
+    }
+}
+
+pub async fn run(client: &edgedb_tokio::Client) -> Result<(), anyhow::Error> {
+    let http = HttpClient::new(HttpSettings::from_env()?)?;
+
+    // A scalar query works the same way as with the binary client, except the result is
+    // always a list.
+    let query = "select 'This is a query fetching a string over http'";
+    let res: Vec<String> = http.query(query, json!({})).await?;
+    display_result(query, &res);
+    assert_eq!(res, vec!["This is a query fetching a string over http"]);
+
+    // Insert an Account with the binary client and then fetch it over http.
+    // The variables are passed by name and still need a cast in the query.
+    let name = random_name();
+    client
+        .execute("insert Account { username := <str>$0 };", &(&name,))
+        .await?;
+    let query = "select Account { username, id } filter .username = <str>$username";
+    let res: Vec<Account> = http.query(query, json!({ "username": name })).await?;
+    display_result(query, &res);
+    assert_eq!(res[0].username, name);
+
+    // Errors come back as json with the same message and type as the binary protocol.
+    // Here the cast on the variable is missing:
+    let query = "select Account { username, id } filter .username = $username";
+    let res: Result<Vec<Account>, _> = http.query(query, json!({ "username": name })).await;
+    display_result(query, &res);
+    assert!(format!("{res:?}").contains("QueryError"));
+
+    Ok(())
+}
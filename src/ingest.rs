@@ -0,0 +1,92 @@
+use std::time::Duration;
+
+use edgedb_tokio::Client;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+use crate::{
+    csv_import::{self, ImportReport, PostRecord, Rejected},
+    policies::admin_client,
+    progress,
+};
+
+// Piping data into EdgeDB from other tools: `ingest` reads newline-delimited json from
+// stdin, one Post per line in the same shape as the csv importer's records:
+//
+//   {"username": "alice", "title": "Hello", "body": "My first post"}
+//
+// e.g. `jq -c '.posts[]' export.json | cargo run -- ingest`. Each line is deserialized into
+// PostRecord with serde and checked by the same validate as the csv import. Lines that
+// fail are reported on stderr with their line number and skipped, everything else is
+// inserted in batches with csv_import::insert_batch.
+//
+// Input from a pipe can arrive slowly (think `tail -f`), so a batch is also written when
+// no new line has arrived for `flush_after`, instead of waiting until it's full.
+// Lines::next_line is cancel safe, so giving up on it after a timeout loses nothing.
+
+pub async fn run(
+    client: &Client,
+    input: impl AsyncBufRead + Unpin,
+    batch_size: usize,
+    flush_after: Duration,
+) -> Result<ImportReport, anyhow::Error> {
+    anyhow::ensure!(batch_size > 0, "batch size has to be at least 1");
+    // Like the csv importer, this inserts Posts for any author
+    let client = admin_client(client);
+
+    let mut lines = input.lines();
+    let mut report = ImportReport {
+        imported: 0,
+        rejected: Vec::new(),
+    };
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut line_number = 0;
+    let counter = progress::counter("lines");
+    loop {
+        counter.set_message(format!(
+            "{} imported, {} rejected",
+            report.imported,
+            report.rejected.len()
+        ));
+        let next = if batch.is_empty() {
+            lines.next_line().await?
+        } else {
+            match tokio::time::timeout(flush_after, lines.next_line()).await {
+                Ok(line) => line?,
+                // Nothing new for a while, write what there is
+                Err(_) => {
+                    report.imported += csv_import::insert_batch(&client, &batch).await?;
+                    batch.clear();
+                    continue;
+                }
+            }
+        };
+        let Some(line) = next else { break };
+        line_number += 1;
+        counter.inc(1);
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row = serde_json::from_str::<PostRecord>(&line)
+            .map_err(|e| e.to_string())
+            .and_then(csv_import::validate);
+        match row {
+            Ok(row) => batch.push(row),
+            Err(reason) => {
+                counter.suspend(|| eprintln!("line {line_number}: {reason}"));
+                report.rejected.push(Rejected {
+                    line: line_number,
+                    reason,
+                });
+            }
+        }
+        if batch.len() == batch_size {
+            report.imported += csv_import::insert_batch(&client, &batch).await?;
+            batch.clear();
+        }
+    }
+    if !batch.is_empty() {
+        report.imported += csv_import::insert_batch(&client, &batch).await?;
+    }
+    counter.finish_and_clear();
+    Ok(report)
+}
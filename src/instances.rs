@@ -0,0 +1,186 @@
+use std::{
+    collections::BTreeMap,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::anyhow;
+use dialoguer::{theme::ColorfulTheme, Select};
+use serde::Deserialize;
+
+use crate::connection::ConnectOpts;
+
+// Picking an instance from a list instead of typing its exact name. The edgedb CLI keeps
+// what it knows about local instances in its config directory:
+// * credentials/<instance>.json: the host, port, user, password and database (or branch)
+//   of every instance created or linked with the CLI
+// * projects/<dir>-<hash>/: one per directory initialized with `edgedb project init`, with
+//   the directory in project-path and the name of its instance in instance-name
+//
+// Both are read into a LocalInstance per credentials file, shown in a dialoguer Select
+// starting on the instance of the project we're in, and the name picked goes into
+// ConnectOpts.instance. That's all --instance does too, so the Builder reads the
+// credentials file again itself rather than being handed the host and password from here.
+//
+// Cloud instances (org/name) have no credentials file and aren't listed, --instance works
+// for them as usual. Nothing is written anywhere: to make the choice stick, pass the
+// printed --instance or run `edgedb project init --link` in the project.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalInstance {
+    pub name: String,
+    // host:port
+    pub address: String,
+    pub database: Option<String>,
+    // The project directories linked to the instance
+    pub projects: Vec<PathBuf>,
+}
+
+impl fmt::Display for LocalInstance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} ({}", self.name, self.address)?;
+        if let Some(database) = &self.database {
+            write!(f, ", {database}")?;
+        }
+        write!(f, ")")?;
+        for project in &self.projects {
+            write!(f, " {}", project.display())?;
+        }
+        Ok(())
+    }
+}
+
+// A credentials file, apart from the password. EdgeDB 5+ writes branch, older versions
+// database
+#[derive(Debug, Deserialize)]
+pub struct Credentials {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    pub database: Option<String>,
+    pub branch: Option<String>,
+    // The instance's own CA in PEM, for its self-signed certificate
+    pub tls_ca: Option<String>,
+    pub tls_security: Option<String>,
+}
+
+// Where the CLI keeps its config on each platform
+pub fn config_dir() -> Option<PathBuf> {
+    if cfg!(target_os = "macos") {
+        let home = env::var_os("HOME")?;
+        Some(PathBuf::from(home).join("Library/Application Support/edgedb"))
+    } else if cfg!(windows) {
+        let local = env::var_os("LOCALAPPDATA")?;
+        Some(PathBuf::from(local).join("EdgeDB").join("config"))
+    } else {
+        let config = env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+        Some(config.join("edgedb"))
+    }
+}
+
+// The instances with a credentials file in `config_dir`, sorted by name, with the
+// projects linked to them
+pub fn read_instances(config_dir: &Path) -> Result<Vec<LocalInstance>, anyhow::Error> {
+    let mut instances = BTreeMap::new();
+    let credentials_dir = config_dir.join("credentials");
+    if credentials_dir.is_dir() {
+        for entry in fs::read_dir(&credentials_dir)? {
+            let path = entry?.path();
+            if !path.extension().is_some_and(|ext| ext == "json") {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let contents = fs::read_to_string(&path)?;
+            // A file the CLI couldn't use either
+            let Ok(credentials) = serde_json::from_str::<Credentials>(&contents) else {
+                eprintln!("Skipping {}, it isn't a credentials file", path.display());
+                continue;
+            };
+            let host = credentials.host.as_deref().unwrap_or("localhost");
+            let port = credentials.port.unwrap_or(5656);
+            let instance = LocalInstance {
+                name: name.to_string(),
+                address: format!("{host}:{port}"),
+                database: credentials.branch.or(credentials.database),
+                projects: Vec::new(),
+            };
+            instances.insert(instance.name.clone(), instance);
+        }
+    }
+    let projects_dir = config_dir.join("projects");
+    if projects_dir.is_dir() {
+        for entry in fs::read_dir(&projects_dir)? {
+            let dir = entry?.path();
+            let (Ok(name), Ok(project)) = (
+                fs::read_to_string(dir.join("instance-name")),
+                fs::read_to_string(dir.join("project-path")),
+            ) else {
+                continue;
+            };
+            // Projects linked to a cloud instance have nothing to attach to
+            if let Some(instance) = instances.get_mut(name.trim()) {
+                instance.projects.push(PathBuf::from(project.trim()));
+            }
+        }
+    }
+    let mut instances: Vec<LocalInstance> = instances.into_values().collect();
+    for instance in &mut instances {
+        instance.projects.sort();
+    }
+    Ok(instances)
+}
+
+// The instance of the project `dir` is in, if it's one of them
+pub fn project_instance(instances: &[LocalInstance], dir: &Path) -> Option<usize> {
+    instances.iter().position(|instance| {
+        instance
+            .projects
+            .iter()
+            .any(|project| dir.starts_with(project))
+    })
+}
+
+// None when the list is dismissed with Esc or q
+pub fn pick(instances: &[LocalInstance]) -> Result<Option<&LocalInstance>, anyhow::Error> {
+    let current = project_instance(instances, &env::current_dir()?);
+    let choice = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt("Connect to")
+        .items(instances)
+        .default(current.unwrap_or(0))
+        .interact_opt()?;
+    Ok(choice.map(|index| &instances[index]))
+}
+
+pub async fn run(database: Option<String>) -> Result<(), anyhow::Error> {
+    let dir = config_dir().ok_or_else(|| anyhow!("can't tell where the edgedb config is"))?;
+    let instances = read_instances(&dir)?;
+    anyhow::ensure!(
+        !instances.is_empty(),
+        "no instances in {}, create one with `edgedb project init`",
+        dir.display()
+    );
+    let Some(instance) = pick(&instances)? else {
+        println!("Nothing picked");
+        return Ok(());
+    };
+
+    let connection = ConnectOpts {
+        instance: Some(instance.name.clone()),
+        dsn: None,
+        database,
+    };
+    let client = connection.client().await?;
+    let version: String = client
+        .query_required_single("select sys::get_version_as_str()", &())
+        .await?;
+    println!("Connected to {} (EdgeDB {version})", instance.name);
+    println!(
+        "To use it for other commands: cargo run -- {} <command>",
+        connection.cli_args().join(" ")
+    );
+    Ok(())
+}
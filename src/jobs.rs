@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use anyhow::anyhow;
+use edgedb_derive::Queryable;
+use edgedb_protocol::model::Json;
+use edgedb_tokio::Client;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::telemetry;
+
+// EdgeDB as a simple task queue. Jobs are inserted with a Pending status and any number
+// of workers claim them one at a time.
+//
+// Claiming is a single update over a `limit 1` select, run inside a transaction. When two
+// workers pick the same Pending job at the same moment, EdgeDB's serializable isolation
+// lets only one of the transactions commit; the other fails with a serialization error
+// and the client retries it automatically, at which point the job is no longer Pending
+// and the retry picks up the next one. No job is ever handed to two workers and no locks
+// are needed on the Rust side.
+
+#[derive(Debug, Queryable)]
+pub struct Job {
+    pub id: Uuid,
+    pub kind: String,
+    pub payload: Json,
+}
+
+#[derive(Debug, Queryable)]
+pub struct JobCount {
+    pub status: String,
+    pub count: i64,
+}
+
+#[derive(Deserialize)]
+struct ReversePayload {
+    text: String,
+}
+
+const CLAIM_QUERY: &str = "with job := (
+        select Job
+        filter .status = JobStatus.Pending
+        order by .created_at
+        limit 1
+    )
+    select (
+        update job set {
+            status := JobStatus.Running,
+            claimed_by := <str>$0,
+            claimed_at := datetime_of_statement(),
+        }
+    ) { id, kind, payload }";
+
+pub async fn enqueue(client: &Client, count: i64) -> Result<Vec<Uuid>, edgedb_tokio::Error> {
+    telemetry::query(
+        client,
+        "jobs.enqueue",
+        "select (
+            for i in range_unpack(range(0, <int64>$0)) union (
+                insert Job {
+                    kind := 'reverse',
+                    payload := <json>{ text := 'job number ' ++ <str>i },
+                }
+            )
+        ).id",
+        &(count,),
+    )
+    .await
+}
+
+pub async fn claim(client: &Client, worker: &str) -> Result<Option<Job>, edgedb_tokio::Error> {
+    client
+        .transaction(|mut tx| async move { tx.query_single(CLAIM_QUERY, &(worker,)).await })
+        .await
+}
+
+pub async fn complete(client: &Client, id: Uuid, result: Json) -> Result<(), edgedb_tokio::Error> {
+    telemetry::execute(
+        client,
+        "jobs.complete",
+        "update Job filter .id = <uuid>$0 set {
+            status := JobStatus.Done,
+            finished_at := datetime_of_statement(),
+            result := <json>$1,
+        }",
+        &(id, result),
+    )
+    .await
+}
+
+pub async fn fail(client: &Client, id: Uuid, error: &str) -> Result<(), edgedb_tokio::Error> {
+    telemetry::execute(
+        client,
+        "jobs.fail",
+        "update Job filter .id = <uuid>$0 set {
+            status := JobStatus.Failed,
+            finished_at := datetime_of_statement(),
+            error := <str>$1,
+        }",
+        &(id, error),
+    )
+    .await
+}
+
+pub async fn counts(client: &Client) -> Result<Vec<JobCount>, edgedb_tokio::Error> {
+    telemetry::query(
+        client,
+        "jobs.counts",
+        "select (group Job by .status) {
+            status := <str>.key.status,
+            count := count(.elements),
+        } order by .status",
+        &(),
+    )
+    .await
+}
+
+// The actual work. Only one kind of job exists: reversing some text.
+async fn process(job: &Job) -> Result<serde_json::Value, anyhow::Error> {
+    match job.kind.as_str() {
+        "reverse" => {
+            let payload: ReversePayload = serde_json::from_str(&job.payload)?;
+            Ok(serde_json::json!({ "reversed": payload.text.chars().rev().collect::<String>() }))
+        }
+        other => Err(anyhow!("unknown job kind {other}")),
+    }
+}
+
+// Claims and processes jobs until the queue is empty (if `exit_when_idle`) or forever,
+// sleeping for `idle_wait` whenever there is nothing to do.
+// Note: a worker that dies mid-job leaves it Running. Real queues also requeue jobs whose
+// claimed_at is older than some timeout.
+pub async fn run_worker(
+    client: Client,
+    name: String,
+    idle_wait: Duration,
+    exit_when_idle: bool,
+) -> Result<usize, edgedb_tokio::Error> {
+    let mut processed = 0;
+    loop {
+        let Some(job) = claim(&client, &name).await? else {
+            if exit_when_idle {
+                return Ok(processed);
+            }
+            tokio::time::sleep(idle_wait).await;
+            continue;
+        };
+        match process(&job).await {
+            Ok(result) => {
+                let result = Json::new_unchecked(result.to_string());
+                complete(&client, job.id, result).await?;
+            }
+            Err(e) => fail(&client, job.id, &e.to_string()).await?,
+        }
+        println!("{name} processed job {}", job.id);
+        processed += 1;
+    }
+}
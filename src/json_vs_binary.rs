@@ -0,0 +1,161 @@
+use std::time::{Duration, Instant};
+
+use comfy_table::Table;
+use edgedb_derive::Queryable;
+use edgedb_protocol::model::Datetime;
+use edgedb_tokio::Client;
+use serde::Deserialize;
+
+use crate::{policies::admin_client, random_name};
+
+// The same nested shape fetched two ways: decoded from the binary protocol into Queryable
+// structs, and as json (query_json, or a `<json>` cast) parsed with serde. Neither is
+// always better:
+//
+// * Size: json repeats every field name for every object and writes uuids, numbers and
+//   dates as text. The binary format sends the shape's field names once, in the
+//   descriptor, and each value in its own encoding (16 bytes for a uuid, 8 for a
+//   datetime). The client doesn't expose the bytes it receives, so binary_size adds up
+//   what the encoding takes for the decoded data, which is close.
+// * Time: both are one round trip. Binary decoding is part of the query call, while json
+//   is a second pass with serde after the call returns, shown separately below. For a
+//   result this size the round trip dominates either way: measure with real data before
+//   switching for speed.
+// * Types: Queryable checks the query's descriptor against the struct before decoding
+//   anything, so a renamed field or a wrong type fails with an error naming it, and
+//   created_at arrives as a Datetime. In json a datetime is a string (and a bigint or a
+//   decimal a string or a lossy number), and a mismatch is a serde error at parse time.
+//
+// Use json when the result is passed on as json anyway (the web example returns it as
+// the response body untouched) or when the shape is built at runtime with no struct to
+// decode into. Use Queryable when Rust code works with the values.
+
+const ACCOUNTS: i64 = 20;
+const POSTS_PER_ACCOUNT: i64 = 5;
+const RUNS: u32 = 20;
+
+const SHAPE: &str = "select Account {
+    username,
+    created_at,
+    posts := (select .<author[is Post] { title, body } order by .title),
+}
+filter .username like <str>$0 ++ '%'
+order by .username";
+
+#[derive(Debug, Deserialize, Queryable, PartialEq)]
+pub struct PostText {
+    pub title: String,
+    pub body: String,
+}
+
+#[derive(Debug, Queryable)]
+pub struct Author {
+    pub username: String,
+    pub created_at: Datetime,
+    pub posts: Vec<PostText>,
+}
+
+// The json version has to take the datetime as a string
+#[derive(Debug, Deserialize)]
+pub struct JsonAuthor {
+    pub username: String,
+    pub created_at: String,
+    pub posts: Vec<PostText>,
+}
+
+// What the binary encoding of `authors` takes: 4 bytes for an object's number of fields
+// and 8 per field (reserved and length) before its data, and a set header of 20 bytes
+// plus 4 per element
+pub fn binary_size(authors: &[Author]) -> usize {
+    let field = |data: usize| 8 + data;
+    let post = |post: &PostText| 4 + 4 + field(post.title.len()) + field(post.body.len());
+    authors
+        .iter()
+        .map(|author| {
+            4 + field(author.username.len())
+                + field(8)
+                + field(20 + author.posts.iter().map(post).sum::<usize>())
+        })
+        .sum::<usize>()
+        + 20
+        + 4 * authors.len()
+}
+
+async fn timed<T, F: std::future::Future<Output = T>>(f: impl Fn() -> F) -> (T, Duration) {
+    let start = Instant::now();
+    let mut res = f().await;
+    for _ in 1..RUNS {
+        res = f().await;
+    }
+    (res, start.elapsed() / RUNS)
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let admin = admin_client(client);
+    // No underscores, which like would treat as a wildcard
+    let prefix = format!("jsonbinary{}", random_name());
+    admin
+        .execute(
+            "for i in range_unpack(range(0, <int64>$1)) union (
+                with account := (insert Account { username := <str>$0 ++ '-' ++ <str>i })
+                for j in range_unpack(range(0, <int64>$2)) union (
+                    insert Post {
+                        author := account,
+                        title := 'Post ' ++ <str>j,
+                        body := 'Written by ' ++ account.username,
+                    }
+                )
+            )",
+            &(&prefix, ACCOUNTS, POSTS_PER_ACCOUNT),
+        )
+        .await?;
+
+    let res = async {
+        let args = (prefix.as_str(),);
+        let (authors, binary_time) = timed(|| client.query::<Author, _>(SHAPE, &args)).await;
+        let authors = authors?;
+        let (json, json_time) = timed(|| client.query_json(SHAPE, &args)).await;
+        let json = json?;
+        let start = Instant::now();
+        let json_authors: Vec<JsonAuthor> = serde_json::from_str(&json)?;
+        let parse_time = start.elapsed();
+
+        // The same data either way
+        assert_eq!(authors.len(), ACCOUNTS as usize);
+        assert_eq!(json_authors.len(), authors.len());
+        for (binary, from_json) in authors.iter().zip(&json_authors) {
+            assert_eq!(binary.username, from_json.username);
+            assert_eq!(binary.posts, from_json.posts);
+        }
+        assert!(json.len() > binary_size(&authors));
+
+        let mut table = Table::new();
+        table.set_header(vec!["", "bytes", "query", "parse", "created_at"]);
+        table.add_row(vec![
+            "binary (Queryable)".to_string(),
+            format!("~{}", binary_size(&authors)),
+            format!("{binary_time:.2?}"),
+            "(in the query)".to_string(),
+            format!("{:?}", authors[0].created_at),
+        ]);
+        table.add_row(vec![
+            "json (serde)".to_string(),
+            json.len().to_string(),
+            format!("{json_time:.2?}"),
+            format!("{parse_time:.2?}"),
+            format!("{:?}", json_authors[0].created_at),
+        ]);
+        println!("{ACCOUNTS} Accounts with {POSTS_PER_ACCOUNT} Posts each, {RUNS} runs:");
+        println!("{table}");
+        Ok(())
+    }
+    .await;
+
+    admin
+        .execute(
+            "delete Account filter .username like <str>$0 ++ '%'",
+            &(&prefix,),
+        )
+        .await?;
+    res
+}
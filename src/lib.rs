@@ -1,9 +1,149 @@
+use edgedb_derive::Queryable;
 use edgedb_protocol::{
     descriptors::{Descriptor, TypePos},
     errors::DecodeError,
     queryable::{Decoder, DescriptorContext, DescriptorMismatch, Queryable},
     serialization::decode::DecodeTupleLike,
 };
+use serde::Deserialize;
+use uuid::Uuid;
+
+pub mod accounts;
+#[cfg(feature = "ai")]
+pub mod ai;
+pub mod aliases;
+pub mod audit;
+#[cfg(feature = "auth")]
+pub mod auth_ext;
+pub mod backup;
+pub mod backup_schedule;
+#[cfg(feature = "bench")]
+pub mod bench;
+pub mod blocking;
+pub mod bulk_delete;
+pub mod cardinality;
+pub mod connection;
+pub mod constraints;
+pub mod csv_import;
+pub mod ddl;
+pub mod defaults;
+pub mod describe;
+pub mod doctor;
+pub mod embeddings;
+pub mod enum_arrays;
+pub mod etl;
+#[cfg(feature = "bench")]
+pub mod experiment;
+pub mod explain;
+pub mod export;
+pub mod factory;
+pub mod fts;
+pub mod functions;
+pub mod globals;
+pub mod groups;
+pub mod handler;
+pub mod highlight;
+pub mod http;
+pub mod ingest;
+pub mod instances;
+pub mod jobs;
+pub mod json_vs_binary;
+pub mod lookup;
+#[cfg(feature = "bench")]
+pub mod memory;
+pub mod migrate;
+pub mod modules;
+pub mod n_plus_one;
+pub mod optimistic;
+pub mod pagination;
+pub mod param_defaults;
+#[cfg(feature = "parquet")]
+pub mod parquet_export;
+pub mod passwords;
+#[cfg(feature = "postgres")]
+pub mod pg_import;
+pub mod policies;
+#[cfg(feature = "postgis")]
+pub mod postgis;
+pub mod profiling;
+pub mod progress;
+pub mod query_metrics;
+pub mod rate_limit;
+pub mod recording;
+pub mod repository;
+pub mod required_single;
+pub mod rollback;
+pub mod sampling;
+pub mod schema_drift;
+pub mod schema_explorer;
+pub mod search;
+pub mod set_ops;
+pub mod settings;
+pub mod smoke;
+#[cfg(feature = "smol")]
+pub mod smol_bridge;
+pub mod snapshots;
+pub mod soft_delete;
+pub mod stats;
+pub mod streaming;
+pub mod sync;
+pub mod telemetry;
+pub mod tenancy;
+pub mod tree;
+pub mod triggers;
+#[cfg(feature = "trgm")]
+pub mod trgm;
+pub mod values;
+#[cfg(feature = "web")]
+pub mod web;
+#[cfg(feature = "chrono")]
+pub mod with_chrono;
+#[cfg(feature = "time")]
+pub mod with_time;
+
+// Used to add a random suffix to types with exclusive constraints.
+pub fn random_name() -> String {
+    std::iter::repeat_with(fastrand::alphanumeric)
+        .take(8)
+        .collect::<String>()
+}
+
+// The query is highlighted and lined up under "Queried: " by highlight.rs
+pub fn display_result(query: &str, res: &impl std::fmt::Debug) {
+    let query = highlight::format_query(query, "Queried: ".len());
+    println!("Queried: {query}\nResult:  {res:?}\n");
+}
+
+// Represents the Account type in the schema, only implements Deserialize
+#[derive(Debug, Deserialize)]
+pub struct Account {
+    pub username: String,
+    pub id: Uuid,
+}
+
+// Also implements Queryable so is more convenient.
+// Note: Queryable requires query fields to be in the same order as the struct.
+// So `select Account { id, username }` will generate a DescriptorMismatch::WrongField error
+// whereas `select Account { username, id }` will not
+#[derive(Debug, Queryable)]
+pub struct QueryableAccount {
+    pub username: String,
+    pub id: Uuid,
+}
+
+// An edgedb(json) attribute on top of Deserialize and Queryable allows unpacking a struct from json returned from EdgeDB.
+#[derive(Debug, Deserialize, Queryable)]
+#[edgedb(json)]
+pub struct JsonQueryableAccount {
+    pub username: String,
+    pub id: Uuid,
+}
+
+#[derive(Debug, Deserialize, Queryable)]
+pub struct BankCustomer {
+    pub name: String,
+    pub bank_balance: i32,
+}
 
 // The code below shows the code generated from the Queryable macro in a more readable form
 // (with macro-generated qualified paths replaced with use statements).
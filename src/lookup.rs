@@ -0,0 +1,91 @@
+use edgedb_derive::Queryable;
+use edgedb_protocol::QueryResult;
+use edgedb_tokio::Client;
+use uuid::Uuid;
+
+use crate::{accounts::AccountSummary, display_result, factory::AccountFactory};
+
+// get_by_id, for the query every application makes over and over:
+//
+//   select Account { username, id, created_at } filter .id = <uuid>$0 limit 1
+//
+// The struct to decode into says which fields to select, through the Shape trait, so the
+// shape is written once next to the struct instead of in every query. The type name
+// can't be a query argument and goes into the query text, so it has to be one of
+// ALLOWED_TYPES: a type name from a request never reaches the query unchecked.
+//
+// The id is a Uuid rather than a string, so a malformed id is rejected where it's parsed
+// (see AccountKey in accounts.rs) and not by the server. An id that doesn't exist, or
+// one of another type, is Ok(None).
+
+pub const ALLOWED_TYPES: [&str; 3] = ["Account", "Post", "auth::Token"];
+
+pub trait Shape {
+    // The shape to select, with the fields in the order of the struct's
+    const SHAPE: &'static str;
+}
+
+impl Shape for AccountSummary {
+    const SHAPE: &'static str = "{ username, id, created_at }";
+}
+
+#[derive(Debug, Queryable)]
+pub struct PostTitle {
+    pub id: Uuid,
+    pub title: String,
+    pub author: String,
+}
+
+impl Shape for PostTitle {
+    const SHAPE: &'static str = "{ id, title, author := .author.username }";
+}
+
+pub async fn get_by_id<T>(
+    client: &Client,
+    type_name: &str,
+    id: Uuid,
+) -> Result<Option<T>, anyhow::Error>
+where
+    T: QueryResult + Shape,
+{
+    anyhow::ensure!(
+        ALLOWED_TYPES.contains(&type_name),
+        "get_by_id doesn't fetch {type_name:?}, only one of {ALLOWED_TYPES:?}"
+    );
+    let query = format!(
+        "select {type_name} {} filter .id = <uuid>$0 limit 1",
+        T::SHAPE
+    );
+    Ok(client.query_single(&query, &(id,)).await?)
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let created = AccountFactory::new().with_posts(1).create(client).await?;
+
+    let res = async {
+        let account: Option<AccountSummary> = get_by_id(client, "Account", created.id).await?;
+        display_result("get_by_id(Account)", &account);
+        assert_eq!(account.map(|a| a.username), Some(created.username.clone()));
+
+        let post: Option<PostTitle> = get_by_id(client, "Post", created.posts[0]).await?;
+        display_result("get_by_id(Post)", &post);
+        assert_eq!(post.map(|p| p.author), Some(created.username.clone()));
+
+        // The Account's id isn't a Post's
+        let post: Option<PostTitle> = get_by_id(client, "Post", created.id).await?;
+        display_result("get_by_id(Post) with an Account's id", &post);
+        assert!(post.is_none());
+
+        let res =
+            get_by_id::<AccountSummary>(client, "Account { password_hash }", created.id).await;
+        display_result("get_by_id(Account { password_hash })", &res);
+        assert!(res.is_err());
+        Ok(())
+    }
+    .await;
+
+    client
+        .execute("delete Account filter .id = <uuid>$0", &(created.id,))
+        .await?;
+    res
+}
@@ -0,0 +1,119 @@
+use std::time::Instant;
+
+use edgedb_derive::Queryable;
+use edgedb_tokio::Client;
+use futures::TryStreamExt;
+
+use crate::{bench, random_name, streaming::stream_query};
+
+// Why unbounded fetches are dangerous: `query()` decodes the whole result into a Vec before
+// returning, so a query that happens to match a million rows needs memory for a million
+// rows (plus the encoded response being decoded) at once. Nothing fails until the day the
+// table is big enough for the process to run out of memory.
+//
+// This example inserts `rows` small BenchRows and reads them back twice, comparing the peak
+// resident memory of:
+// * a single query() of everything into a Vec
+// * keyset pagination: `filter .n > <last n seen> order by .n limit <chunk>` in a loop
+//   (through stream_query in streaming.rs), processing and dropping each chunk before
+//   fetching the next, so memory stays bounded by the chunk size however many rows there
+//   are.
+//
+// The peak is VmHWM from /proc/self/status, reset between the two runs by writing 5 to
+// /proc/self/clear_refs, so this only reports numbers on Linux. The chunked run goes first
+// because memory freed by the allocator isn't always given back to the OS, which would
+// otherwise count the unbounded run's memory against the chunked one.
+
+#[derive(Queryable)]
+struct Row {
+    n: i64,
+    payload: String,
+}
+
+// Peak resident set size in KiB, if available
+fn peak_rss_kib() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn reset_peak_rss() {
+    // Fails on anything but Linux, in which case peak_rss_kib returns None anyway
+    let _ = std::fs::write("/proc/self/clear_refs", "5");
+}
+
+fn report(label: &str, rows: usize, total_len: usize, start: Instant) {
+    let peak = match peak_rss_kib() {
+        Some(kib) => format!("{:.1} MiB", kib as f64 / 1024.0),
+        None => "unavailable (needs /proc)".to_string(),
+    };
+    println!(
+        "{label}: {rows} rows ({total_len} payload bytes) in {:.2?}, peak RSS {peak}",
+        start.elapsed()
+    );
+}
+
+async fn unbounded(client: &Client, run: &str) -> Result<(), anyhow::Error> {
+    reset_peak_rss();
+    let start = Instant::now();
+    let rows: Vec<Row> = client
+        .query(
+            "select BenchRow { n, payload } filter .run = <str>$0 order by .n",
+            &(run,),
+        )
+        .await?;
+    let total_len = rows.iter().map(|row| row.payload.len()).sum();
+    report("query() into a Vec", rows.len(), total_len, start);
+    Ok(())
+}
+
+async fn chunked(client: &Client, run: &str, chunk_size: i64) -> Result<(), anyhow::Error> {
+    reset_peak_rss();
+    let start = Instant::now();
+    // Each row is dropped as soon as it's been added to the totals, and the next page is
+    // only fetched once the current one has been used up.
+    let (count, total_len) = stream_query(
+        client,
+        "select BenchRow { n, payload }
+        filter .run = <str>$0 and .n > <int64>$1
+        order by .n
+        limit <int64>$2",
+        chunk_size as usize,
+        (run, -1, chunk_size),
+        |row: &Row| (run, row.n, chunk_size),
+    )
+    .try_fold((0, 0), |(count, total_len), row| async move {
+        Ok((count + 1, total_len + row.payload.len()))
+    })
+    .await?;
+    report(
+        &format!("keyset chunks of {chunk_size}"),
+        count,
+        total_len,
+        start,
+    );
+    Ok(())
+}
+
+pub async fn run(client: &Client, rows: usize, chunk_size: i64) -> Result<(), anyhow::Error> {
+    let run = random_name();
+    println!("Inserting {rows} rows...");
+    bench::insert_rows(client, &run, rows, 16, 1000).await?;
+
+    let res = async {
+        chunked(client, &run, chunk_size).await?;
+        unbounded(client, &run).await
+    }
+    .await;
+
+    client
+        .execute("delete BenchRow filter .run = <str>$0", &(&run,))
+        .await?;
+    res
+}
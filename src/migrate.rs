@@ -0,0 +1,140 @@
+use std::{fmt, path::PathBuf, process::Command};
+
+use crate::connection::ConnectOpts;
+
+// Schema migrations from the application binary, for deployments where this binary is the
+// only thing that runs against the database: `migrate create` and `migrate apply` run
+// `edgedb migration create` and `edgedb migration apply` with the same connection options
+// as the client (see connection.rs), like dump and restore do.
+//
+// The CLI's output is meant for people, so it's captured (and passed on to the terminal),
+// then parsed into a MigrationOutcome, or a MigrationError for the failures a deployment
+// script wants to tell apart:
+// * NoChanges: `create` found nothing to do. Usually not a failure at all, which is why
+//   it's its own variant rather than a message to search for.
+// * InvalidSchema: the .esdl files don't compile. Fix the schema, nothing was changed.
+// * Failed: anything else, e.g. the database is unreachable or has migrations the files
+//   don't, with the CLI's output.
+//
+// `create` runs with --non-interactive, so instead of asking about ambiguous changes
+// (was a property renamed, or dropped and another one added?) the CLI fails, and the
+// migration has to be created by hand. Migration files are code: create them on a
+// development machine, review and commit them, and only `apply` in deployments.
+
+#[derive(Debug, Clone, Copy)]
+pub enum Action {
+    Create,
+    Apply,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MigrationOutcome {
+    // The new migration file
+    Created(PathBuf),
+    // The names of the migrations applied, oldest first
+    Applied(Vec<String>),
+    UpToDate,
+}
+
+#[derive(Debug)]
+pub enum MigrationError {
+    // The CLI couldn't be started
+    Cli(std::io::Error),
+    NoChanges,
+    InvalidSchema(String),
+    Failed { code: Option<i32>, output: String },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MigrationError::Cli(e) => write!(f, "couldn't run the CLI, is it on the PATH? {e}"),
+            MigrationError::NoChanges => write!(f, "no schema changes to migrate"),
+            MigrationError::InvalidSchema(errors) => write!(f, "the schema is invalid:\n{errors}"),
+            MigrationError::Failed { code, output } => match code {
+                Some(code) => write!(f, "migration failed with exit code {code}:\n{output}"),
+                None => write!(f, "migration was killed:\n{output}"),
+            },
+        }
+    }
+}
+
+impl std::error::Error for MigrationError {}
+
+// Turns the CLI's combined stdout and stderr into the outcome
+pub fn parse_output(
+    action: Action,
+    code: Option<i32>,
+    output: &str,
+) -> Result<MigrationOutcome, MigrationError> {
+    if output.contains("No schema changes detected") {
+        return Err(MigrationError::NoChanges);
+    }
+    if code != Some(0) {
+        // Compilation errors point at the schema file they're in
+        let errors: Vec<&str> = output
+            .lines()
+            .filter(|line| line.trim_start().starts_with("error"))
+            .collect();
+        if !errors.is_empty() && output.contains(".esdl") {
+            return Err(MigrationError::InvalidSchema(errors.join("\n")));
+        }
+        return Err(MigrationError::Failed {
+            code,
+            output: output.to_string(),
+        });
+    }
+    let unexpected = || MigrationError::Failed {
+        code,
+        output: output.to_string(),
+    };
+    match action {
+        // Created ./dbschema/migrations/00002-m1abc.edgeql, id: m1abc...
+        Action::Create => output
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("Created "))
+            .and_then(|rest| rest.split(',').next())
+            .map(|path| MigrationOutcome::Created(PathBuf::from(path)))
+            .ok_or_else(unexpected),
+        // Applied m1abc... (00002-m1abc.edgeql), once per migration
+        // or: Everything is up to date. Revision m1abc...
+        Action::Apply => {
+            let applied: Vec<String> = output
+                .lines()
+                .filter_map(|line| line.trim().strip_prefix("Applied "))
+                .filter_map(|rest| rest.split_whitespace().next())
+                .map(str::to_string)
+                .collect();
+            if !applied.is_empty() {
+                Ok(MigrationOutcome::Applied(applied))
+            } else if output.contains("up to date") {
+                Ok(MigrationOutcome::UpToDate)
+            } else {
+                Err(unexpected())
+            }
+        }
+    }
+}
+
+pub fn run(
+    cli: &str,
+    connection: &ConnectOpts,
+    action: Action,
+) -> Result<MigrationOutcome, MigrationError> {
+    let args: &[&str] = match action {
+        Action::Create => &["migration", "create", "--non-interactive"],
+        Action::Apply => &["migration", "apply"],
+    };
+    let output = Command::new(cli)
+        .args(connection.cli_args())
+        .args(args)
+        .output()
+        .map_err(MigrationError::Cli)?;
+    let text = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    eprint!("{text}");
+    parse_output(action, output.status.code(), &text)
+}
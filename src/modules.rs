@@ -0,0 +1,83 @@
+use edgedb_errors::InvalidReferenceError;
+use edgedb_tokio::Client;
+use uuid::Uuid;
+
+use crate::{display_result, random_name};
+
+// Querying a schema with more than one module. Tokens live in the auth module
+// (dbschema/auth.esdl), Accounts in default, and a name without a module is looked up in
+// the query's default module first and then in std. Three ways to get at auth::Token:
+// 1. Fully qualified: `auth::Token` works with any client and any default module. The
+//    safest choice for queries that live in code, and the one web/auth.rs uses.
+// 2. `with module auth` at the start of a query makes auth the default module for that
+//    query only. Shorter when a query uses many names from one module, but everything
+//    from default now has to be written `default::Account`.
+// 3. `client.with_default_module(Some("auth"))` does the same for every query made with
+//    the derived client, without touching the query text.
+//
+// Where it bites: a name that isn't found is an InvalidReferenceError, and a name that
+// is found in the wrong module is worse. The test module has an Account type of its
+// own, so `select Account` with test as the default module is a valid query returning
+// different objects (see the configuration part of the tour in main.rs).
+
+const TOKEN: &str = "select (insert auth::Token {
+    token := <str>$0,
+    account := (insert Account { username := <str>$1 }),
+}).id";
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let username = format!("modules{}", random_name());
+    let token = random_name();
+    let id: Uuid = client
+        .query_required_single(TOKEN, &(&token, &username))
+        .await?;
+    println!("Inserted token {token} for {username}\n");
+
+    let res = async {
+        // 1. Fully qualified names
+        let query = "select auth::Token.account.username filter auth::Token.id = <uuid>$0";
+        let res: String = client.query_required_single(query, &(id,)).await?;
+        display_result(query, &res);
+        assert_eq!(res, username);
+
+        // 2. with module, where Account now needs its module
+        let query = "with module auth
+            select count(Token filter .account.username = <str>$0)";
+        let res: i64 = client.query_required_single(query, &(&username,)).await?;
+        display_result(query, &res);
+        assert_eq!(res, 1);
+        let query = "with module auth
+            select count(Token filter .account = (
+                select default::Account filter .username = <str>$0
+            ))";
+        let res: i64 = client.query_required_single(query, &(&username,)).await?;
+        display_result(query, &res);
+        assert_eq!(res, 1);
+        let query = "with module auth select count(Account)";
+        let res: Result<i64, _> = client.query_required_single(query, &()).await;
+        display_result(query, &res);
+        assert!(res.is_err_and(|e| e.is::<InvalidReferenceError>()));
+
+        // 3. A client with auth as its default module
+        let auth_client = client.with_default_module(Some("auth"));
+        let query = "select Token.account.username filter Token.id = <uuid>$0";
+        let res: String = auth_client.query_required_single(query, &(id,)).await?;
+        display_result(query, &res);
+        assert_eq!(res, username);
+        // Fully qualified names work the same on every client
+        let query = "select default::Account.username filter default::Account.username = <str>$0";
+        let res: String = auth_client
+            .query_required_single(query, &(&username,))
+            .await?;
+        display_result(query, &res);
+        assert_eq!(res, username);
+        Ok(())
+    }
+    .await;
+
+    // Deleting the Account deletes its token too (on target delete delete source)
+    client
+        .execute("delete Account filter .username = <str>$0", &(&username,))
+        .await?;
+    res
+}
@@ -0,0 +1,119 @@
+use std::time::{Duration, Instant};
+
+use edgedb_derive::Queryable;
+use edgedb_tokio::Client;
+use uuid::Uuid;
+
+use crate::{policies::admin_client, random_name};
+
+// The classic N+1 problem: load a list of objects with one query, then loop over them
+// running one more query per object to load something related. With 20 Accounts that's 21
+// round trips, and the total grows with every Account added.
+//
+// ORMs usually fix it with eager loading or joins that then have to be regrouped in code.
+// In EdgeQL the fix is just a nested shape: the Posts are selected as part of each Account
+// (through the backlink .<author) and come back already grouped under their Account, in a
+// single query and a single round trip.
+
+const ACCOUNTS: usize = 20;
+const POSTS_PER_ACCOUNT: usize = 5;
+
+#[derive(Debug, Queryable)]
+struct AccountId {
+    id: Uuid,
+}
+
+#[derive(Debug, Queryable)]
+struct PostTitle {
+    title: String,
+}
+
+#[derive(Debug, Queryable)]
+struct AccountWithPosts {
+    username: String,
+    posts: Vec<PostTitle>,
+}
+
+fn report(label: &str, queries: usize, accounts: usize, posts: usize, elapsed: Duration) {
+    println!("{label}: {accounts} Accounts with {posts} Posts in {queries} queries, {elapsed:.2?}");
+}
+
+async fn n_plus_one(client: &Client, prefix: &str) -> Result<usize, anyhow::Error> {
+    let start = Instant::now();
+    let mut queries = 0;
+    let accounts: Vec<AccountId> = client
+        .query(
+            "select Account { id } filter .username like <str>$0 ++ '%'",
+            &(prefix,),
+        )
+        .await?;
+    queries += 1;
+    let mut posts = 0;
+    for account in &accounts {
+        let titles: Vec<String> = client
+            .query(
+                "select Post.title filter Post.author.id = <uuid>$0",
+                &(account.id,),
+            )
+            .await?;
+        queries += 1;
+        posts += titles.len();
+    }
+    report("N+1", queries, accounts.len(), posts, start.elapsed());
+    Ok(queries)
+}
+
+async fn single_shape(client: &Client, prefix: &str) -> Result<usize, anyhow::Error> {
+    let start = Instant::now();
+    let accounts: Vec<AccountWithPosts> = client
+        .query(
+            "select Account {
+                username,
+                posts := .<author[is Post] { title },
+            }
+            filter .username like <str>$0 ++ '%'",
+            &(prefix,),
+        )
+        .await?;
+    let posts = accounts.iter().map(|account| account.posts.len()).sum();
+    report("Single shape", 1, accounts.len(), posts, start.elapsed());
+    Ok(1)
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let admin = admin_client(client);
+    // No underscores, which like would treat as a wildcard
+    let prefix = format!("nplusone{}", random_name());
+    admin
+        .execute(
+            "for i in range_unpack(range(0, <int64>$1)) union (
+                with account := (insert Account { username := <str>$0 ++ '-' ++ <str>i })
+                for j in range_unpack(range(0, <int64>$2)) union (
+                    insert Post {
+                        author := account,
+                        title := 'Post ' ++ <str>j,
+                        body := 'Written by ' ++ account.username,
+                    }
+                )
+            )",
+            &(&prefix, ACCOUNTS as i64, POSTS_PER_ACCOUNT as i64),
+        )
+        .await?;
+
+    let res = async {
+        let n_plus_one_queries = n_plus_one(client, &prefix).await?;
+        let single_shape_queries = single_shape(client, &prefix).await?;
+        assert_eq!(n_plus_one_queries, ACCOUNTS + 1);
+        assert_eq!(single_shape_queries, 1);
+        Ok(())
+    }
+    .await;
+
+    admin
+        .execute(
+            "delete Account filter .username like <str>$0 ++ '%'",
+            &(&prefix,),
+        )
+        .await?;
+    res
+}
@@ -0,0 +1,116 @@
+use std::fmt;
+
+use edgedb_protocol::value::Value;
+use edgedb_tokio::Client;
+use uuid::Uuid;
+
+use crate::{display_result, factory::AccountFactory};
+
+// Optimistic concurrency: two people open the same Post, both edit it, and the second
+// save would silently overwrite the first. With a version on Post (see
+// dbschema/default.esdl) each editor remembers the version it read, and the update only
+// applies if the Post is still at that version:
+//
+//   update Post filter .id = <uuid>$0 and .version = <int64>$1
+//   set { version := .version + 1, ... }
+//
+// If someone saved in between, the version has moved on, the filter matches nothing and
+// the update changes nothing. update_post turns that into StaleVersion, so the caller can
+// reload the Post and show the other edit instead of losing it.
+//
+// Nothing is locked while someone is editing, which is what makes it optimistic: it costs
+// nothing when edits don't collide, and a collision is found at save time. An id that
+// doesn't exist (or a Post the access policies hide) also matches nothing, and shows up as
+// StaleVersion too.
+
+#[derive(Debug)]
+pub enum UpdateError {
+    // The Post isn't at the version the edit started from anymore
+    StaleVersion { id: Uuid, expected: i64 },
+    Database(edgedb_tokio::Error),
+}
+
+impl fmt::Display for UpdateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UpdateError::StaleVersion { id, expected } => {
+                write!(f, "post {id} has changed since version {expected}")
+            }
+            UpdateError::Database(e) => write!(f, "database error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for UpdateError {}
+
+impl From<edgedb_tokio::Error> for UpdateError {
+    fn from(error: edgedb_tokio::Error) -> Self {
+        UpdateError::Database(error)
+    }
+}
+
+// Saves an edit made to `version` of the Post, returning the new version
+pub async fn update_post(
+    client: &Client,
+    id: Uuid,
+    version: i64,
+    title: &str,
+    body: &str,
+) -> Result<i64, UpdateError> {
+    let updated: Option<i64> = client
+        .query_single(
+            "select (
+                update Post filter .id = <uuid>$0 and .version = <int64>$1
+                set {
+                    version := .version + 1,
+                    title := <str>$2,
+                    body := <str>$3,
+                }
+            ).version",
+            &(id, version, title, body),
+        )
+        .await?;
+    updated.ok_or(UpdateError::StaleVersion {
+        id,
+        expected: version,
+    })
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let author = AccountFactory::new().with_posts(1).create(client).await?;
+    let id = author.posts[0];
+    let as_author = client.with_globals_fn(|globals| {
+        globals.set("current_user", Value::Uuid(author.id));
+    });
+
+    let res = async {
+        // Both editors open the Post at the same version
+        let query = "select Post.version filter Post.id = <uuid>$0";
+        let opened: i64 = client.query_required_single(query, &(id,)).await?;
+        display_result(query, &opened);
+
+        let saved = update_post(&as_author, id, opened, "First edit", "Saved first").await;
+        display_result("update_post", &saved);
+        assert_eq!(saved?, opened + 1);
+
+        // The second save started from the same version and is turned away
+        let saved = update_post(&as_author, id, opened, "Second edit", "Saved second").await;
+        display_result("update_post", &saved);
+        assert!(matches!(saved, Err(UpdateError::StaleVersion { .. })));
+
+        // Reloading gets the first edit and the current version, and saving works again
+        let query = "select (Post.title, Post.version) filter Post.id = <uuid>$0";
+        let reloaded: (String, i64) = client.query_required_single(query, &(id,)).await?;
+        display_result(query, &reloaded);
+        assert_eq!(reloaded.0, "First edit");
+        let saved = update_post(&as_author, id, reloaded.1, "Second edit", "Merged").await?;
+        assert_eq!(saved, opened + 2);
+        Ok(())
+    }
+    .await;
+
+    client
+        .execute("delete Account filter .id = <uuid>$0", &(author.id,))
+        .await?;
+    res
+}
@@ -0,0 +1,136 @@
+use edgedb_protocol::{
+    descriptors::{Descriptor, TypePos},
+    errors::DecodeError,
+    queryable::{Decoder, DescriptorContext, DescriptorMismatch, Queryable},
+    serialization::decode::DecodeTupleLike,
+};
+use edgedb_tokio::Client;
+
+use crate::{accounts::AccountSummary, display_result, factory::AccountFactory, random_name};
+
+// A page of results together with the total number of results, from a single query. Most
+// pagination code makes two round trips, one for the page and one for `select count(...)`
+// to show "page 2 of 7", and the two can disagree when something is inserted in between.
+// A free object (an object without a type) can hold both:
+//
+//   select {
+//       total := count(Account),
+//       items := (select Account { ... } order by ... offset <int64>$0 limit <int64>$1),
+//   }
+//
+// and both parts are read in the same query, so from the same snapshot.
+//
+// Page<T> decodes it for any Queryable T. The derive macro doesn't take type parameters,
+// so Queryable is implemented by hand like IsAStruct in lib.rs, with the items decoded
+// as a Vec<T> by T's own implementation. The page is stable as long as the order by ends
+// with something unique (here the id), otherwise rows with equal keys can swap places
+// between pages.
+
+#[derive(Debug)]
+pub struct Page<T> {
+    pub total: i64,
+    pub items: Vec<T>,
+}
+
+impl<T> Page<T> {
+    pub fn pages(&self, page_size: i64) -> i64 {
+        (self.total + page_size - 1) / page_size
+    }
+}
+
+impl<T: Queryable> Queryable for Page<T> {
+    fn decode(decoder: &Decoder, buf: &[u8]) -> Result<Self, DecodeError> {
+        // Free objects don't have the implicit id, __tid__ or __tname__ fields
+        let mut elements = DecodeTupleLike::new_object(buf, 2)?;
+        let total = Queryable::decode_optional(decoder, elements.read()?)?;
+        let items = Queryable::decode_optional(decoder, elements.read()?)?;
+        Ok(Page { total, items })
+    }
+
+    fn check_descriptor(
+        ctx: &DescriptorContext,
+        type_pos: TypePos,
+    ) -> Result<(), DescriptorMismatch> {
+        let desc = ctx.get(type_pos)?;
+        let shape = match desc {
+            Descriptor::ObjectShape(shape) => shape,
+            _ => return Err(ctx.wrong_type(desc, "free object")),
+        };
+        let [total, items] = &shape.elements[..] else {
+            return Err(ctx.field_number(shape.elements.len(), 2));
+        };
+        if total.name != "total" {
+            return Err(ctx.wrong_field("total", &total.name));
+        }
+        <i64 as Queryable>::check_descriptor(ctx, total.type_pos)?;
+        if items.name != "items" {
+            return Err(ctx.wrong_field("items", &items.name));
+        }
+        <Vec<T> as Queryable>::check_descriptor(ctx, items.type_pos)
+    }
+}
+
+pub async fn list_accounts(
+    client: &Client,
+    prefix: &str,
+    offset: i64,
+    limit: i64,
+) -> Result<Page<AccountSummary>, edgedb_tokio::Error> {
+    client
+        .query_required_single(
+            "with accounts := (select Account filter .username like <str>$0 ++ '%')
+            select {
+                total := count(accounts),
+                items := (
+                    select accounts { username, id, created_at }
+                    order by .username then .id
+                    offset <int64>$1
+                    limit <int64>$2
+                ),
+            }",
+            &(prefix, offset, limit),
+        )
+        .await
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let prefix = format!("page{}", random_name());
+    for n in 0..5 {
+        AccountFactory::new()
+            .username(format!("{prefix}-{n}"))
+            .create(client)
+            .await?;
+    }
+
+    let res = async {
+        let page_size = 2;
+        for offset in [0, 2, 4] {
+            let page = list_accounts(client, &prefix, offset, page_size).await?;
+            display_result("list_accounts", &page);
+            let usernames: Vec<&str> = page.items.iter().map(|a| a.username.as_str()).collect();
+            println!(
+                "Page {} of {}: {usernames:?}\n",
+                offset / page_size + 1,
+                page.pages(page_size)
+            );
+            assert_eq!(page.total, 5);
+            assert_eq!(page.pages(page_size), 3);
+        }
+
+        // Past the end the page is empty, and the total is still there
+        let page = list_accounts(client, &prefix, 10, page_size).await?;
+        display_result("list_accounts", &page);
+        assert!(page.items.is_empty());
+        assert_eq!(page.total, 5);
+        Ok(())
+    }
+    .await;
+
+    client
+        .execute(
+            "delete Account filter .username like <str>$0 ++ '%'",
+            &(&prefix,),
+        )
+        .await?;
+    res
+}
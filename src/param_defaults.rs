@@ -0,0 +1,127 @@
+use edgedb_tokio::Client;
+
+use crate::{accounts::AccountSummary, display_result, factory::AccountFactory, random_name};
+
+// Defaults for query parameters, in the query: every knob of the listing below is an
+// optional parameter with `??` giving the value to use when it's empty, so the same query
+// text serves a call site that sets nothing and one that sets everything. The Rust side
+// passes Options straight through, None meaning "the default", and never formats a query.
+//
+// * `<optional int64>$1 ?? 20` is the limit, capped at 100 with min() so no caller can
+//   ask for everything. (`limit <optional int64>$1` on its own would mean no limit at all
+//   when it's empty.)
+// * A sort direction can't be a parameter, asc and desc are keywords. Instead there are
+//   two order by expressions, one descending and one ascending, and the direction picks
+//   which of them has a value: the other is the empty set for every row, so it orders
+//   nothing and the next one decides.
+// * The defaults live in one place, next to the query, instead of in every caller (or in
+//   a Default impl that has to be kept in step with the query).
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ListOptions {
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+    pub newest_first: Option<bool>,
+}
+
+const QUERY: &str = "with
+    prefix := <str>$0,
+    page_limit := min({<optional int64>$1 ?? 20, 100}),
+    page_offset := <optional int64>$2 ?? 0,
+    newest_first := <optional bool>$3 ?? true
+select Account { username, id, created_at }
+filter .username like prefix ++ '%'
+order by
+    (.created_at if newest_first else <datetime>{}) desc
+    then (.created_at if not newest_first else <datetime>{}) asc
+    then .id
+offset page_offset
+limit page_limit";
+
+pub async fn list_accounts(
+    client: &Client,
+    prefix: &str,
+    options: ListOptions,
+) -> Result<Vec<AccountSummary>, edgedb_tokio::Error> {
+    client
+        .query(
+            QUERY,
+            &(prefix, options.limit, options.offset, options.newest_first),
+        )
+        .await
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let prefix = format!("params{}", random_name());
+    // One at a time, so each has its own created_at
+    for n in 1..=5 {
+        AccountFactory::new()
+            .username(format!("{prefix}_{n}"))
+            .create(client)
+            .await?;
+    }
+
+    let res = async {
+        let calls = [
+            (
+                "everything by default",
+                ListOptions::default(),
+                vec![5, 4, 3, 2, 1],
+            ),
+            (
+                "the first page of two",
+                ListOptions {
+                    limit: Some(2),
+                    ..Default::default()
+                },
+                vec![5, 4],
+            ),
+            (
+                "the second page of two",
+                ListOptions {
+                    limit: Some(2),
+                    offset: Some(2),
+                    ..Default::default()
+                },
+                vec![3, 2],
+            ),
+            (
+                "oldest first",
+                ListOptions {
+                    newest_first: Some(false),
+                    ..Default::default()
+                },
+                vec![1, 2, 3, 4, 5],
+            ),
+            (
+                "oldest first, skipping one",
+                ListOptions {
+                    offset: Some(1),
+                    newest_first: Some(false),
+                    limit: Some(1000),
+                },
+                vec![2, 3, 4, 5],
+            ),
+        ];
+        for (what, options, expected) in calls {
+            let accounts = list_accounts(client, &prefix, options).await?;
+            println!("{what}: {options:?}");
+            display_result(QUERY, &accounts);
+            let numbers: Vec<usize> = accounts
+                .iter()
+                .map(|a| a.username[prefix.len() + 1..].parse())
+                .collect::<Result<_, _>>()?;
+            assert_eq!(numbers, expected);
+        }
+        Ok(())
+    }
+    .await;
+
+    client
+        .execute(
+            "delete Account filter .username like <str>$0 ++ '%'",
+            &(&prefix,),
+        )
+        .await?;
+    res
+}
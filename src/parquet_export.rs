@@ -0,0 +1,281 @@
+use std::{
+    fs::File,
+    path::Path,
+    pin::pin,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use arrow::{
+    array::{
+        ArrayRef, BooleanArray, Float32Array, Float64Array, Int16Array, Int32Array, Int64Array,
+        StringArray, TimestampMicrosecondArray,
+    },
+    datatypes::{DataType, Field, Schema, TimeUnit},
+    record_batch::RecordBatch,
+};
+use edgedb_derive::Queryable;
+use edgedb_protocol::value::Value;
+use edgedb_tokio::Client;
+use futures::TryStreamExt;
+use parquet::{arrow::ArrowWriter, basic::Compression, file::properties::WriterProperties};
+use uuid::Uuid;
+
+use crate::{
+    export::{id_of, valid_type_name, SECRET_PROPERTIES},
+    progress,
+    streaming::stream_query,
+    values,
+};
+
+// Parquet files of a type's objects, for pandas, polars, DuckDB, Spark and the rest of the
+// analytics world. Works like the export command (see export.rs): the objects are fetched
+// a page at a time, and each page becomes an arrow RecordBatch written to the file as a
+// row group, so memory stays bounded however big the table is.
+//
+// Parquet files have a fixed schema, which can't be guessed from the first page (a column
+// that's empty there would have no type), so it comes from the type's properties in the
+// schema module instead. EdgeDB scalars map to arrow types like this:
+//
+//   str, json, uuid, enums and anything else  Utf8
+//   bool                                      Boolean
+//   int16, int32, int64                       Int16, Int32, Int64
+//   float32, float64                          Float32, Float64
+//   datetime                                  Timestamp(Microsecond, "UTC")
+//
+// Uuids are written as text rather than 16 byte binary because that's what most tools
+// show and join on without extra work. Multi properties are written as json arrays.
+
+#[derive(Queryable)]
+struct Property {
+    name: String,
+    type_name: String,
+    multi: bool,
+}
+
+#[derive(Clone, Copy)]
+enum Column {
+    Text,
+    Bool,
+    Int16,
+    Int32,
+    Int64,
+    Float32,
+    Float64,
+    Datetime,
+}
+
+impl Column {
+    fn new(property: &Property) -> Self {
+        if property.multi {
+            return Column::Text;
+        }
+        match property.type_name.as_str() {
+            "std::bool" => Column::Bool,
+            "std::int16" => Column::Int16,
+            "std::int32" => Column::Int32,
+            "std::int64" => Column::Int64,
+            "std::float32" => Column::Float32,
+            "std::float64" => Column::Float64,
+            "std::datetime" => Column::Datetime,
+            _ => Column::Text,
+        }
+    }
+
+    fn data_type(self) -> DataType {
+        match self {
+            Column::Text => DataType::Utf8,
+            Column::Bool => DataType::Boolean,
+            Column::Int16 => DataType::Int16,
+            Column::Int32 => DataType::Int32,
+            Column::Int64 => DataType::Int64,
+            Column::Float32 => DataType::Float32,
+            Column::Float64 => DataType::Float64,
+            Column::Datetime => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+        }
+    }
+
+    fn array(self, name: &str, cells: &[Option<&Value>]) -> Result<ArrayRef, anyhow::Error> {
+        // Empty sets are nulls, anything else that doesn't match the column type is a bug
+        fn collect<T>(
+            name: &str,
+            cells: &[Option<&Value>],
+            get: impl Fn(&Value) -> Option<T>,
+        ) -> Result<Vec<Option<T>>, anyhow::Error> {
+            cells
+                .iter()
+                .map(|cell| match cell {
+                    None | Some(Value::Nothing) => Ok(None),
+                    Some(value) => get(*value)
+                        .map(Some)
+                        .ok_or_else(|| anyhow::anyhow!("unexpected value in {name}: {value:?}")),
+                })
+                .collect()
+        }
+        Ok(match self {
+            Column::Text => Arc::new(StringArray::from(collect(name, cells, text)?)),
+            Column::Bool => Arc::new(BooleanArray::from(collect(name, cells, |v| match v {
+                Value::Bool(b) => Some(*b),
+                _ => None,
+            })?)),
+            Column::Int16 => Arc::new(Int16Array::from(collect(name, cells, |v| match v {
+                Value::Int16(n) => Some(*n),
+                _ => None,
+            })?)),
+            Column::Int32 => Arc::new(Int32Array::from(collect(name, cells, |v| match v {
+                Value::Int32(n) => Some(*n),
+                _ => None,
+            })?)),
+            Column::Int64 => Arc::new(Int64Array::from(collect(name, cells, |v| match v {
+                Value::Int64(n) => Some(*n),
+                _ => None,
+            })?)),
+            Column::Float32 => Arc::new(Float32Array::from(collect(name, cells, |v| match v {
+                Value::Float32(n) => Some(*n),
+                _ => None,
+            })?)),
+            Column::Float64 => Arc::new(Float64Array::from(collect(name, cells, |v| match v {
+                Value::Float64(n) => Some(*n),
+                _ => None,
+            })?)),
+            Column::Datetime => Arc::new(
+                TimestampMicrosecondArray::from(collect(name, cells, |v| match v {
+                    Value::Datetime(dt) => unix_micros(dt),
+                    _ => None,
+                })?)
+                .with_timezone("UTC"),
+            ),
+        })
+    }
+}
+
+fn text(value: &Value) -> Option<String> {
+    Some(match value {
+        Value::Str(s) => s.clone(),
+        Value::Uuid(id) => id.to_string(),
+        Value::Json(json) => json.to_string(),
+        Value::Enum(e) => e.to_string(),
+        other => match values::to_json(other).ok()? {
+            serde_json::Value::String(s) => s,
+            json => json.to_string(),
+        },
+    })
+}
+
+// Datetimes before 1970 are negative
+fn unix_micros(dt: &edgedb_protocol::model::Datetime) -> Option<i64> {
+    let time = SystemTime::try_from(*dt).ok()?;
+    Some(match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_micros() as i64,
+        Err(before) => -(before.duration().as_micros() as i64),
+    })
+}
+
+fn to_batch(
+    schema: &Arc<Schema>,
+    columns: &[(String, Column)],
+    objects: &[Value],
+) -> Result<RecordBatch, anyhow::Error> {
+    let arrays = columns
+        .iter()
+        .enumerate()
+        .map(|(i, (name, column))| {
+            let cells: Vec<Option<&Value>> = objects
+                .iter()
+                .map(|object| match object {
+                    // The query selects the properties in the same order as the columns
+                    Value::Object { fields, .. } => fields.get(i).and_then(Option::as_ref),
+                    _ => None,
+                })
+                .collect();
+            column.array(name, &cells)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(RecordBatch::try_new(schema.clone(), arrays)?)
+}
+
+// Writes every object of `type_name` to a parquet file at `path` and returns how many
+// there were
+pub async fn run(
+    client: &Client,
+    type_name: &str,
+    path: &Path,
+    page_size: usize,
+) -> Result<usize, anyhow::Error> {
+    anyhow::ensure!(
+        valid_type_name(type_name),
+        "invalid type name {type_name:?}"
+    );
+    anyhow::ensure!(page_size > 0, "page size has to be at least 1");
+    // The schema module only knows fully qualified names
+    let type_name = if type_name.contains("::") {
+        type_name.to_string()
+    } else {
+        format!("default::{type_name}")
+    };
+    let properties: Vec<Property> = client
+        .query(
+            "select (select schema::ObjectType filter .name = <str>$0).properties {
+                name,
+                type_name := .target.name,
+                multi := .cardinality ?= schema::Cardinality.Many,
+            }
+            order by .name",
+            &(&type_name,),
+        )
+        .await?;
+    anyhow::ensure!(!properties.is_empty(), "no type called {type_name}");
+
+    // Secrets stay out of here too, see export.rs
+    let columns: Vec<(String, Column)> = properties
+        .iter()
+        .filter(|p| !SECRET_PROPERTIES.contains(&p.name.as_str()))
+        .map(|p| (p.name.clone(), Column::new(p)))
+        .collect();
+    let schema = Arc::new(Schema::new(
+        columns
+            .iter()
+            .map(|(name, column)| Field::new(name, column.data_type(), true))
+            .collect::<Vec<_>>(),
+    ));
+    // Backquoted so property names that are also keywords still work
+    let shape = columns
+        .iter()
+        .map(|(name, _)| format!("`{name}`"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let query = format!(
+        "select {type_name} {{ {shape} }} filter .id > <uuid>$0 order by .id limit <int64>$1"
+    );
+
+    let total: i64 = client
+        .query_required_single(&format!("select count({type_name})"), &())
+        .await?;
+    let bar = progress::bar(total as u64, "objects");
+    let limit = page_size as i64;
+    let mut pages = pin!(stream_query(
+        client,
+        &query,
+        page_size,
+        (Uuid::nil(), limit),
+        move |last: &Value| {
+            let id = id_of(last).expect("every object has an id");
+            (id, limit)
+        },
+    )
+    .try_chunks(page_size));
+
+    let properties = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut writer = ArrowWriter::try_new(File::create(path)?, schema.clone(), Some(properties))?;
+    let mut count = 0;
+    while let Some(page) = pages.try_next().await.map_err(|e| e.1)? {
+        writer.write(&to_batch(&schema, &columns, &page)?)?;
+        count += page.len();
+        bar.inc(page.len() as u64);
+    }
+    writer.close()?;
+    bar.finish();
+    Ok(count)
+}
@@ -0,0 +1,183 @@
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use edgedb_derive::Queryable;
+use edgedb_tokio::Client;
+use uuid::Uuid;
+
+use crate::random_name;
+
+// Two places to hash passwords for Account.password_hash, each with a sign up and a log in.
+//
+// In the database, with the pgcrypto extension (`using extension pgcrypto;` in the schema):
+//   ext::pgcrypto::crypt(password, ext::pgcrypto::gen_salt('bf')) hashes with bcrypt when
+//   the Account is inserted, and logging in compares crypt(password, stored hash) with the
+//   stored hash (the salt and cost are part of the hash), all inside one query. Nothing to
+//   add to the Rust side, and any client of the database hashes the same way.
+//
+// In Rust, with the argon2 crate:
+//   Argon2id, the current recommendation for passwords, hashes in the application and only
+//   the hash (a PHC string with the salt and parameters in it) is sent to the database.
+//   Logging in fetches the hash and verifies it in Rust.
+//
+// Which to pick:
+// * With pgcrypto the plain password is a query argument, so it travels to the database
+//   and anything that logs query arguments can see it. With argon2 it never leaves the
+//   application.
+// * Hashing is deliberately slow. With pgcrypto that CPU time is spent on the database
+//   server, which is usually the hardest part of a system to scale. With argon2 it's spent
+//   on the application servers, on a blocking thread so the async runtime isn't stalled.
+// * Argon2 is memory-hard as well as slow, which makes guessing with GPUs much more
+//   expensive than it is against bcrypt.
+// For a Rust application the argon2 route is the better default, pgcrypto is handy when
+// several applications (or plain EdgeQL scripts) need to create and check passwords.
+//
+// Either way the hash is stored in an ordinary property, readable by anyone who can read
+// the Account, so a real schema would restrict who can select it with an access policy.
+
+#[derive(Queryable)]
+struct StoredHash {
+    id: Uuid,
+    password_hash: Option<String>,
+}
+
+pub async fn sign_up_pgcrypto(
+    client: &Client,
+    username: &str,
+    password: &str,
+) -> Result<Uuid, edgedb_tokio::Error> {
+    client
+        .query_required_single(
+            "select (
+                insert Account {
+                    username := <str>$0,
+                    password_hash := ext::pgcrypto::crypt(
+                        <str>$1, ext::pgcrypto::gen_salt('bf')
+                    ),
+                }
+            ).id",
+            &(username, password),
+        )
+        .await
+}
+
+// The id of the Account if the password is right
+pub async fn log_in_pgcrypto(
+    client: &Client,
+    username: &str,
+    password: &str,
+) -> Result<Option<Uuid>, edgedb_tokio::Error> {
+    client
+        .query_single(
+            "select Account.id
+            filter Account.username = <str>$0
+            and Account.password_hash = ext::pgcrypto::crypt(<str>$1, Account.password_hash)",
+            &(username, password),
+        )
+        .await
+}
+
+pub async fn sign_up_argon2(
+    client: &Client,
+    username: &str,
+    password: &str,
+) -> Result<Uuid, anyhow::Error> {
+    let password = password.to_string();
+    let hash = tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+    })
+    .await?
+    .map_err(|e| anyhow::anyhow!("hashing failed: {e}"))?;
+    let id = client
+        .query_required_single(
+            "select (insert Account { username := <str>$0, password_hash := <str>$1 }).id",
+            &(username, hash),
+        )
+        .await?;
+    Ok(id)
+}
+
+// The id of the Account if the password is right
+pub async fn log_in_argon2(
+    client: &Client,
+    username: &str,
+    password: &str,
+) -> Result<Option<Uuid>, anyhow::Error> {
+    let account: Option<StoredHash> = client
+        .query_single(
+            "select Account { id, password_hash } filter .username = <str>$0",
+            &(username,),
+        )
+        .await?;
+    // No such Account, or one without a password
+    let Some(StoredHash {
+        id,
+        password_hash: Some(hash),
+    }) = account
+    else {
+        return Ok(None);
+    };
+    let password = password.to_string();
+    let verified = tokio::task::spawn_blocking(move || {
+        let hash =
+            PasswordHash::new(&hash).map_err(|e| anyhow::anyhow!("invalid password hash: {e}"))?;
+        Ok::<_, anyhow::Error>(
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok(),
+        )
+    })
+    .await??;
+    Ok(verified.then_some(id))
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let server_user = format!("pgcrypto{}", random_name());
+    let rust_user = format!("argon2{}", random_name());
+    let password = random_name();
+
+    let res = async {
+        let id = sign_up_pgcrypto(client, &server_user, &password).await?;
+        assert_eq!(
+            log_in_pgcrypto(client, &server_user, &password).await?,
+            Some(id)
+        );
+        assert_eq!(log_in_pgcrypto(client, &server_user, "wrong").await?, None);
+        println!("pgcrypto: signed up {server_user} and logged in");
+
+        let id = sign_up_argon2(client, &rust_user, &password).await?;
+        assert_eq!(
+            log_in_argon2(client, &rust_user, &password).await?,
+            Some(id)
+        );
+        assert_eq!(log_in_argon2(client, &rust_user, "wrong").await?, None);
+        println!("argon2: signed up {rust_user} and logged in");
+
+        let hashes: Vec<String> = client
+            .query(
+                "select Account.password_hash
+                filter Account.username in {<str>$0, <str>$1}
+                order by Account.username desc",
+                &(&server_user, &rust_user),
+            )
+            .await?;
+        // bcrypt hashes start with $2a$, argon2 ones with $argon2id$
+        for hash in &hashes {
+            println!("  {hash}");
+        }
+        Ok(())
+    }
+    .await;
+
+    client
+        .execute(
+            "delete Account filter .username in {<str>$0, <str>$1}",
+            &(&server_user, &rust_user),
+        )
+        .await?;
+    res
+}
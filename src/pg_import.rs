@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+
+use edgedb_derive::Queryable;
+use edgedb_protocol::model::Json;
+use edgedb_tokio::Client;
+use serde::Serialize;
+use tokio_postgres::NoTls;
+use uuid::Uuid;
+
+use crate::{policies::admin_client, progress};
+
+// Moving an existing Postgres database onto the EdgeDB schema. The source tables are
+// assumed to look like this:
+//
+//   create table users (id bigint primary key, username text not null unique);
+//   create table posts (
+//       id bigint primary key,
+//       user_id bigint not null references users (id),
+//       title text not null,
+//       body text not null
+//   );
+//
+// Users become Accounts and posts become Posts. Foreign keys don't carry over as they are:
+// EdgeDB links point at objects, so each users.id is mapped to the id of the Account it
+// became, and posts.user_id is looked up in that map to set Post.author.
+//
+// Rows are read in keyset-paginated batches and each batch goes over as a single json
+// argument, unpacked and inserted by one `for` query. That's one round trip per batch
+// instead of per row.
+
+#[derive(Serialize)]
+struct UserRow {
+    pg_id: i64,
+    username: String,
+}
+
+#[derive(Serialize)]
+struct PostRow {
+    pg_id: i64,
+    author: Uuid,
+    title: String,
+    body: String,
+}
+
+#[derive(Queryable)]
+struct ImportedAccount {
+    pg_id: i64,
+    id: Uuid,
+}
+
+fn json_arg<T: Serialize>(value: &T) -> Result<Json, serde_json::Error> {
+    Ok(Json::new_unchecked(serde_json::to_string(value)?))
+}
+
+pub async fn run(
+    client: &Client,
+    postgres_url: &str,
+    batch_size: i64,
+) -> Result<(), anyhow::Error> {
+    let (pg, connection) = tokio_postgres::connect(postgres_url, NoTls).await?;
+    tokio::spawn(async move {
+        if let Err(e) = connection.await {
+            eprintln!("Postgres connection error: {e}");
+        }
+    });
+
+    // Inserts Posts for any author, like any other admin tool would
+    let client = admin_client(client);
+
+    let total_users: i64 = pg
+        .query_one("select count(*) from users", &[])
+        .await?
+        .get(0);
+    let bar = progress::bar(total_users as u64, "Accounts");
+    let mut account_ids: HashMap<i64, Uuid> = HashMap::new();
+    let mut last_id = i64::MIN;
+    loop {
+        let rows = pg
+            .query(
+                "select id, username from users where id > $1 order by id limit $2",
+                &[&last_id, &batch_size],
+            )
+            .await?;
+        let Some(last) = rows.last() else { break };
+        last_id = last.get("id");
+        let batch: Vec<UserRow> = rows
+            .iter()
+            .map(|row| UserRow {
+                pg_id: row.get("id"),
+                username: row.get("username"),
+            })
+            .collect();
+        // Re-running the import picks up the Accounts created the first time
+        let imported: Vec<ImportedAccount> = client
+            .query(
+                "for user in json_array_unpack(<json>$0) union (
+                    select (
+                        insert Account { username := <str>user['username'] }
+                        unless conflict on .username
+                        else (select Account)
+                    ) { pg_id := <int64>user['pg_id'], id }
+                )",
+                &(json_arg(&batch)?,),
+            )
+            .await?;
+        account_ids.extend(imported.into_iter().map(|a| (a.pg_id, a.id)));
+        bar.set_position(account_ids.len() as u64);
+    }
+    bar.finish();
+
+    let total_posts: i64 = pg
+        .query_one("select count(*) from posts", &[])
+        .await?
+        .get(0);
+    let bar = progress::bar(total_posts as u64, "Posts");
+    let mut imported_posts = 0;
+    let mut last_id = i64::MIN;
+    loop {
+        let rows = pg
+            .query(
+                "select id, user_id, title, body from posts where id > $1 order by id limit $2",
+                &[&last_id, &batch_size],
+            )
+            .await?;
+        let Some(last) = rows.last() else { break };
+        last_id = last.get("id");
+        let mut batch = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let user_id: i64 = row.get("user_id");
+            // The foreign key guarantees the user exists, so it was imported above
+            let Some(&author) = account_ids.get(&user_id) else {
+                anyhow::bail!(
+                    "post {} refers to unknown user {user_id}",
+                    row.get::<_, i64>("id")
+                );
+            };
+            batch.push(PostRow {
+                pg_id: row.get("id"),
+                author,
+                title: row.get("title"),
+                body: row.get("body"),
+            });
+        }
+        // Posts keep the id they had in Postgres as pg_id, so like the Accounts a re-run
+        // picks up the ones inserted the first time instead of duplicating them
+        let imported: Vec<Uuid> = client
+            .query(
+                "for post in json_array_unpack(<json>$0) union (
+                    select (
+                        insert Post {
+                            pg_id := <int64>post['pg_id'],
+                            author := (select Account filter .id = <uuid>post['author']),
+                            title := <str>post['title'],
+                            body := <str>post['body'],
+                        }
+                        unless conflict on .pg_id
+                        else (select Post)
+                    ).id
+                )",
+                &(json_arg(&batch)?,),
+            )
+            .await?;
+        imported_posts += imported.len();
+        bar.set_position(imported_posts as u64);
+    }
+    bar.finish();
+
+    println!(
+        "Imported {} Accounts and {imported_posts} Posts",
+        account_ids.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,93 @@
+use edgedb_errors::AccessPolicyError;
+use edgedb_protocol::value::Value;
+use edgedb_tokio::Client;
+use uuid::Uuid;
+
+use crate::{display_result, random_name};
+
+// The access policies on Account (see dbschema/default.esdl) in action:
+// * Anybody can select and insert Accounts.
+// * With `global current_user` set, only that user's own Account can be updated or deleted.
+//   Queries without the global come from trusted backend code (like the tour), which can
+//   update any Account.
+// * No update may leave a username starting with "admin".
+//
+// A policy that fails on the way in (update read) filters the object out silently, just
+// like a select would. A policy that fails on the way out (update write) is an error.
+
+// A client that ignores every access policy, like admin tools and migrations need (only
+// superusers may turn them off). The examples use it to set up and clean up data the policies
+// wouldn't let them: Posts can only be inserted by their author (author_has_full_access on
+// Post), so examples making Posts for Accounts of their own insert them through this
+// client, and delete them again by deleting those Accounts, whose Posts go with them.
+pub fn admin_client(client: &Client) -> Client {
+    client.with_config_fn(|config| {
+        config.set("apply_access_policies", Value::Bool(false));
+    })
+}
+
+const RENAME: &str = "select (
+        update Account filter .id = <uuid>$0 set { username := <str>$1 }
+    ).username";
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let insert = "select (insert Account { username := <str>$0 }).id";
+    let alice: Uuid = client
+        .query_required_single(insert, &(format!("alice_{}", random_name()),))
+        .await?;
+    let bob: Uuid = client
+        .query_required_single(insert, &(format!("bob_{}", random_name()),))
+        .await?;
+
+    let res = async {
+        let alice_client = client.with_globals_fn(|globals| {
+            globals.set("current_user", Value::Uuid(alice));
+        });
+
+        // Permitted: Alice renames herself
+        let new_name = format!("alice_{}", random_name());
+        let res: Option<String> = alice_client
+            .query_single(RENAME, &(alice, &new_name))
+            .await?;
+        display_result(RENAME, &res);
+        assert_eq!(res.as_ref(), Some(&new_name));
+
+        // Denied, silently: Bob's Account isn't visible to Alice's update at all,
+        // so nothing is updated and nothing is returned
+        let res: Option<String> = alice_client
+            .query_single(RENAME, &(bob, "hacked_by_alice"))
+            .await?;
+        display_result(RENAME, &res);
+        assert_eq!(res, None);
+
+        // Denied, loudly: Alice may update her own Account, but the result fails the
+        // no_reserved_usernames policy. The error has the AccessPolicyError kind (and the
+        // policy's errmessage), so it can be told apart from other failures.
+        let res: Result<Option<String>, _> = alice_client
+            .query_single(RENAME, &(alice, format!("admin_{}", random_name())))
+            .await;
+        display_result(RENAME, &res);
+        assert!(res.unwrap_err().is::<AccessPolicyError>());
+
+        // The escape hatch, admin_client: with apply_access_policies configured to false
+        // the client ignores every policy, even with the global set
+        let admin_name = format!("admin_{}", random_name());
+        let res: Option<String> = admin_client(&alice_client)
+            .query_single(RENAME, &(bob, &admin_name))
+            .await?;
+        display_result(RENAME, &res);
+        assert_eq!(res.as_ref(), Some(&admin_name));
+        Ok(())
+    }
+    .await;
+
+    // Bob now has a reserved username, which would trip the policy on any later update to
+    // him (like the tour's or a sync's), so both are deleted again
+    admin_client(client)
+        .execute(
+            "delete Account filter .id in {<uuid>$0, <uuid>$1}",
+            &(alice, bob),
+        )
+        .await?;
+    res
+}
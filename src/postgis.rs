@@ -0,0 +1,180 @@
+use edgedb_derive::Queryable;
+use edgedb_tokio::Client;
+
+use crate::random_name;
+
+// Geographic queries with the PostGIS extension (ext::postgis, EdgeDB 6+): finding the
+// Places within some distance of a point, nearest first.
+//
+// ext::postgis isn't bundled with the server. It's an extension package that has to be
+// installed first (`edgedb extension install postgis`), and this example doesn't want to
+// make that a requirement for the whole repo, so Place and the `using extension postgis`
+// line are in dbschema/extensions/postgis.esdl, to copy into dbschema/ when wanted. Then:
+// * A Place's location is an ext::postgis::geometry point, built in the insert from the
+//   longitude and latitude (in that order, x then y) with makepoint, and setsrid 4326, the
+//   lat/lng coordinate system GPS uses.
+// * Cast to ext::postgis::geography, distance and dwithin work in meters on the globe
+//   rather than in degrees. The gist index on Place is on that cast, so dwithin can use it.
+// * Geometries go back to Rust as WKT text ("POINT(2.35 48.85)") from astext, and are
+//   parsed back into LatLng here.
+// * run checks whether the extension is enabled first, and skips the example (with
+//   instructions) when it isn't.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatLng {
+    pub lat: f64,
+    pub lng: f64,
+}
+
+impl LatLng {
+    // Parses a WKT point, e.g. POINT(2.35 48.85)
+    pub fn from_wkt(wkt: &str) -> Option<Self> {
+        let coords = wkt.strip_prefix("POINT(")?.strip_suffix(')')?;
+        let (lng, lat) = coords.split_once(' ')?;
+        Some(LatLng {
+            lat: lat.parse().ok()?,
+            lng: lng.parse().ok()?,
+        })
+    }
+}
+
+#[derive(Queryable)]
+struct PlaceRow {
+    name: String,
+    distance_m: f64,
+    wkt: String,
+}
+
+#[derive(Debug)]
+pub struct Nearby {
+    pub name: String,
+    pub distance_m: f64,
+    pub location: LatLng,
+}
+
+pub async fn available(client: &Client) -> Result<bool, edgedb_tokio::Error> {
+    client
+        .query_required_single(
+            "select exists (select schema::Extension filter .name = 'postgis')",
+            &(),
+        )
+        .await
+}
+
+pub async fn insert(
+    client: &Client,
+    collection: &str,
+    name: &str,
+    at: LatLng,
+) -> Result<(), edgedb_tokio::Error> {
+    client
+        .execute(
+            "insert Place {
+                collection := <str>$0,
+                name := <str>$1,
+                location := ext::postgis::setsrid(
+                    ext::postgis::makepoint(<float64>$3, <float64>$2), 4326
+                ),
+            }",
+            &(collection, name, at.lat, at.lng),
+        )
+        .await
+}
+
+// The Places in `collection` at most `meters` from `center`, nearest first
+pub async fn within(
+    client: &Client,
+    collection: &str,
+    center: LatLng,
+    meters: f64,
+) -> Result<Vec<Nearby>, anyhow::Error> {
+    let rows: Vec<PlaceRow> = client
+        .query(
+            "with center := <ext::postgis::geography>ext::postgis::setsrid(
+                ext::postgis::makepoint(<float64>$2, <float64>$1), 4326
+            )
+            select Place {
+                name,
+                distance_m := ext::postgis::distance(
+                    <ext::postgis::geography>.location, center
+                ),
+                wkt := ext::postgis::astext(.location),
+            }
+            filter .collection = <str>$0
+            and ext::postgis::dwithin(
+                <ext::postgis::geography>.location, center, <float64>$3
+            )
+            order by .distance_m",
+            &(collection, center.lat, center.lng, meters),
+        )
+        .await?;
+    rows.into_iter()
+        .map(|row| {
+            let location = LatLng::from_wkt(&row.wkt)
+                .ok_or_else(|| anyhow::anyhow!("unexpected geometry {}", row.wkt))?;
+            Ok(Nearby {
+                name: row.name,
+                distance_m: row.distance_m,
+                location,
+            })
+        })
+        .collect()
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    if !available(client).await? {
+        println!(
+            "The postgis extension isn't enabled, skipping. To enable it, run `edgedb \
+            extension install postgis`, copy dbschema/extensions/postgis.esdl into \
+            dbschema/ and migrate."
+        );
+        return Ok(());
+    }
+
+    let collection = format!("postgis{}", random_name());
+    let places = [
+        ("Eiffel Tower", 48.8584, 2.2945),
+        ("Louvre", 48.8606, 2.3376),
+        ("Notre-Dame", 48.8530, 2.3499),
+        ("Palace of Versailles", 48.8049, 2.1204),
+        ("Big Ben", 51.5007, -0.1246),
+    ];
+    for (name, lat, lng) in places {
+        insert(client, &collection, name, LatLng { lat, lng }).await?;
+    }
+
+    let res = async {
+        let center = LatLng {
+            lat: 48.8566,
+            lng: 2.3522,
+        };
+        for km in [5.0, 20.0, 500.0] {
+            println!("Within {km} km of the centre of Paris:");
+            for place in within(client, &collection, center, km * 1000.0).await? {
+                println!("  {:>8.0} m  {}", place.distance_m, place.name);
+            }
+        }
+
+        let nearby = within(client, &collection, center, 5_000.0).await?;
+        let names: Vec<&str> = nearby.iter().map(|place| place.name.as_str()).collect();
+        assert_eq!(names, ["Notre-Dame", "Louvre", "Eiffel Tower"]);
+        // The points survive the round trip through WKT
+        let louvre = LatLng {
+            lat: 48.8606,
+            lng: 2.3376,
+        };
+        assert_eq!(nearby[1].location, louvre);
+        // London is about 340 km away
+        let all = within(client, &collection, center, 500_000.0).await?;
+        let london = all.last().unwrap();
+        assert_eq!(london.name, "Big Ben");
+        assert!((330_000.0..350_000.0).contains(&london.distance_m));
+        Ok(())
+    }
+    .await;
+
+    client
+        .execute("delete Place filter .collection = <str>$0", &(&collection,))
+        .await?;
+    res
+}
@@ -0,0 +1,48 @@
+use std::future::Future;
+
+// CPU profiles of the benchmark commands (bench-writes, experiment and memory). With the
+// profiling feature each run is sampled with pprof and written as a flamegraph to
+// profiles/<command>-<unix time>.svg, e.g.
+//
+// cargo run --release --features profiling -- bench-writes --concurrency 16
+//
+// The widest towers show where the time goes: decoding in edgedb_protocol, the network
+// and tls in tokio and rustls, serde for json results, or the example's own code. Time
+// spent waiting on the server doesn't show up at all, since a task waiting for a response
+// isn't using the CPU. Release builds give the most realistic picture, and adding
+// `debug = true` under [profile.release] in Cargo.toml keeps the function names readable.
+//
+// Without the feature `profiled` just runs the future.
+pub async fn profiled<T, F>(name: &str, fut: F) -> Result<T, anyhow::Error>
+where
+    F: Future<Output = Result<T, anyhow::Error>>,
+{
+    #[cfg(feature = "profiling")]
+    {
+        let guard = pprof::ProfilerGuardBuilder::default()
+            .frequency(1000)
+            .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+            .build()?;
+        let res = fut.await;
+        write_flamegraph(name, &guard)?;
+        res
+    }
+    #[cfg(not(feature = "profiling"))]
+    {
+        let _ = name;
+        fut.await
+    }
+}
+
+#[cfg(feature = "profiling")]
+fn write_flamegraph(name: &str, guard: &pprof::ProfilerGuard) -> Result<(), anyhow::Error> {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let report = guard.report().build()?;
+    std::fs::create_dir_all("profiles")?;
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let path = format!("profiles/{name}-{secs}.svg");
+    report.flamegraph(std::fs::File::create(&path)?)?;
+    println!("Wrote a flamegraph to {path}");
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+use indicatif::{ProgressBar, ProgressStyle};
+
+// The progress bars of the bulk commands (seed, import-csv, ingest, import-postgres,
+// export, export-parquet and prune-audit), all with the same layout: how far along, the
+// rate, and the time left when there's a total to go towards, e.g.
+//
+// ████████████░░░░░░░░░░░░░░░░░░░░░░░░░░░░ 3000/10000 rows  812/s  eta 9s  in 6 batches
+//
+// The batching loops drive them, moving a bar once per batch, as that's when the rows are
+// actually written (export moves its bar per object written out instead). The rate and
+// eta are worked out by indicatif from how fast the position moves, so they settle after
+// a few batches.
+//
+// Bars are drawn on stderr, out of the way of anything written to stdout (export can write
+// there), and indicatif doesn't draw them at all when stderr isn't a terminal, so logs in
+// CI and the tests don't fill up with them. Lines printed while a bar is showing go
+// through ProgressBar::suspend so the bar doesn't draw over them.
+
+// For a known number of things, e.g. "rows"
+pub fn bar(len: u64, unit: &str) -> ProgressBar {
+    let template = format!("{{bar:40}} {{pos}}/{{len}} {unit}  {{per_sec}}  eta {{eta}}  {{msg}}");
+    ProgressBar::new(len).with_style(style(&template))
+}
+
+// For a known number of bytes, e.g. the size of a file being read
+pub fn bytes_bar(len: u64) -> ProgressBar {
+    let template = "{bar:40} {bytes}/{total_bytes}  {bytes_per_sec}  eta {eta}  {msg}";
+    ProgressBar::new(len).with_style(style(template))
+}
+
+// For input with no known end, e.g. lines from stdin
+pub fn counter(unit: &str) -> ProgressBar {
+    let template = format!("{{spinner}} {{pos}} {unit}  {{per_sec}}  {{elapsed}}  {{msg}}");
+    let counter = ProgressBar::new_spinner().with_style(style(&template));
+    counter.enable_steady_tick(Duration::from_millis(100));
+    counter
+}
+
+fn style(template: &str) -> ProgressStyle {
+    ProgressStyle::with_template(template).expect("the templates above are valid")
+}
@@ -0,0 +1,79 @@
+use std::{sync::LazyLock, time::Duration};
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder,
+};
+
+// Prometheus metrics for the queries made through the functions in telemetry.rs:
+// * edgedb_queries_total{query}: every query, by its name
+// * edgedb_query_errors_total{query, kind}: failed queries, by name and error kind
+//   (e.g. AccessPolicyError, ConstraintViolationError)
+// * edgedb_query_duration_seconds{query}: a latency histogram
+// The web example serves them at /metrics for Prometheus to scrape. The query name is
+// the only label that comes from the application, so keep names to a fixed set and never
+// build them from user input, or the number of series grows without bound.
+
+pub struct QueryMetrics {
+    registry: Registry,
+    queries: IntCounterVec,
+    errors: IntCounterVec,
+    latency: HistogramVec,
+}
+
+impl QueryMetrics {
+    fn new() -> Result<Self, prometheus::Error> {
+        let queries = IntCounterVec::new(
+            Opts::new("edgedb_queries_total", "Queries made, by query name"),
+            &["query"],
+        )?;
+        let errors = IntCounterVec::new(
+            Opts::new(
+                "edgedb_query_errors_total",
+                "Failed queries, by query name and error kind",
+            ),
+            &["query", "kind"],
+        )?;
+        // Most queries against a nearby instance take a few milliseconds, so the buckets
+        // start at 1ms
+        let latency = HistogramVec::new(
+            HistogramOpts::new("edgedb_query_duration_seconds", "Query latency")
+                .buckets(prometheus::exponential_buckets(0.001, 2.0, 14)?),
+            &["query"],
+        )?;
+        let registry = Registry::new();
+        registry.register(Box::new(queries.clone()))?;
+        registry.register(Box::new(errors.clone()))?;
+        registry.register(Box::new(latency.clone()))?;
+        Ok(Self {
+            registry,
+            queries,
+            errors,
+            latency,
+        })
+    }
+
+    // Called by telemetry::traced once a query has finished
+    pub fn observe(&self, name: &str, elapsed: Duration, error: Option<&edgedb_tokio::Error>) {
+        self.queries.with_label_values(&[name]).inc();
+        self.latency
+            .with_label_values(&[name])
+            .observe(elapsed.as_secs_f64());
+        if let Some(error) = error {
+            self.errors
+                .with_label_values(&[name, error.kind_name()])
+                .inc();
+        }
+    }
+
+    // The metrics in Prometheus' text format
+    pub fn render(&self) -> Result<String, prometheus::Error> {
+        let mut buf = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buf)?;
+        Ok(String::from_utf8_lossy(&buf).into_owned())
+    }
+}
+
+// A single set of metrics for the whole process. The metric definitions above are fixed,
+// so creating them can only fail on a programming error.
+pub static METRICS: LazyLock<QueryMetrics> =
+    LazyLock::new(|| QueryMetrics::new().expect("invalid metric definitions"));
@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use edgedb_derive::Queryable;
+use edgedb_tokio::Client;
+
+// A fixed-window rate limiter that keeps its counters in EdgeDB, so every instance of a
+// service shares the same limits.
+//
+// Each check reads the key's window and then either starts a new window, bumps the count
+// or refuses, all inside one transaction. Two requests for the same key at the same time
+// both read the same count, but EdgeDB's serializable isolation only lets one of them
+// commit. The other fails with a serialization error, the client retries the whole
+// transaction, and the retry sees the updated count. So the limit holds exactly, without
+// locks and without the lost updates a read-then-write outside a transaction would have.
+
+#[derive(Debug, Queryable)]
+struct Window {
+    count: i64,
+    expired: bool,
+    retry_after_secs: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decision {
+    Allowed { remaining: i64 },
+    Limited { retry_after_secs: f64 },
+}
+
+#[derive(Clone)]
+pub struct RateLimiter {
+    client: Client,
+    limit: i64,
+    window: edgedb_protocol::model::Duration,
+}
+
+impl RateLimiter {
+    // Allows `limit` checks per key in every `window`
+    pub fn new(client: Client, limit: i64, window: Duration) -> Self {
+        Self {
+            client,
+            limit,
+            window: edgedb_protocol::model::Duration::from_micros(window.as_micros() as i64),
+        }
+    }
+
+    pub async fn check(&self, key: &str) -> Result<Decision, edgedb_tokio::Error> {
+        let (limit, window) = (self.limit, self.window);
+        self.client
+            .transaction(|mut tx| async move {
+                let current: Option<Window> = tx
+                    .query_single(
+                        "with now := datetime_of_statement(),
+                        select RateLimitWindow {
+                            count,
+                            expired := .window_start + <duration>$1 <= now,
+                            retry_after_secs := duration_get(
+                                .window_start + <duration>$1 - now, 'totalseconds'
+                            ),
+                        } filter .key = <str>$0",
+                        &(key, window),
+                    )
+                    .await?;
+                match current {
+                    None => {
+                        tx.execute(
+                            "insert RateLimitWindow {
+                                key := <str>$0,
+                                window_start := datetime_of_statement(),
+                                count := 1,
+                            }",
+                            &(key,),
+                        )
+                        .await?;
+                        Ok(Decision::Allowed {
+                            remaining: limit - 1,
+                        })
+                    }
+                    Some(current) if current.expired => {
+                        tx.execute(
+                            "update RateLimitWindow filter .key = <str>$0 set {
+                                window_start := datetime_of_statement(),
+                                count := 1,
+                            }",
+                            &(key,),
+                        )
+                        .await?;
+                        Ok(Decision::Allowed {
+                            remaining: limit - 1,
+                        })
+                    }
+                    Some(current) if current.count < limit => {
+                        tx.execute(
+                            "update RateLimitWindow filter .key = <str>$0 set {
+                                count := .count + 1,
+                            }",
+                            &(key,),
+                        )
+                        .await?;
+                        Ok(Decision::Allowed {
+                            remaining: limit - current.count - 1,
+                        })
+                    }
+                    Some(current) => Ok(Decision::Limited {
+                        retry_after_secs: current.retry_after_secs,
+                    }),
+                }
+            })
+            .await
+    }
+}
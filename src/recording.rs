@@ -0,0 +1,451 @@
+use std::{
+    fmt::Debug,
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+    path::Path,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use anyhow::Context;
+use bytes::Bytes;
+use edgedb_protocol::{
+    descriptors::TypePos,
+    errors::DecodeError,
+    model::Json,
+    query_arg::QueryArgs,
+    queryable::{DescriptorContext, DescriptorMismatch},
+    value::Value,
+    QueryResult,
+};
+use edgedb_tokio::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    rollback::in_rollback,
+    telemetry::{count_params, sanitize_args},
+    values,
+};
+
+// Recording the queries of a run to reproduce it somewhere else, e.g. for a bug report:
+//
+// cargo run -- --record session.jsonl account list
+// cargo run -- --instance other replay session.jsonl
+//
+// With --record every query made through the functions in telemetry.rs (the same ones
+// --log-queries logs) is written to the file as a line of json: its name and text, the
+// arguments redacted like in the query log, the output descriptor and the decoded result.
+// Queries the examples make on the Client directly aren't recorded.
+//
+// The result is decoded twice from the same data: as the type the caller asked for, and as
+// a Value, which needs no Rust type and so can be written out as json (see values.rs). That
+// happens in Recorded, a QueryResult that wraps the caller's type, so recording doesn't run
+// anything twice. The descriptor is the Debug output of the codec the client built from the
+// server's description of the result, which shows the names, types and cardinalities it
+// decodes, and so what a Queryable struct would have to match.
+//
+// `replay` runs the recorded queries again, each in a transaction that is rolled back (see
+// rollback.rs) so the other instance isn't changed, and compares the descriptors, results
+// and errors with the recording. Only queries without parameters can be replayed: the
+// arguments are only recorded in their redacted form, so the others are skipped.
+//
+// Results are recorded as they are, unlike arguments, so have a look at the file before
+// sharing it.
+
+// The Value a query's output decodes into, and the codec it was decoded with
+#[derive(Debug)]
+pub struct Described {
+    pub value: Value,
+    pub descriptor: Arc<str>,
+}
+
+impl QueryResult for Described {
+    type State = (<Value as QueryResult>::State, Arc<str>);
+
+    fn prepare(
+        ctx: &DescriptorContext,
+        root_pos: TypePos,
+    ) -> Result<Self::State, DescriptorMismatch> {
+        let codec = <Value as QueryResult>::prepare(ctx, root_pos)?;
+        let descriptor = format!("{codec:?}").into();
+        Ok((codec, descriptor))
+    }
+
+    fn decode(state: &mut Self::State, msg: &Bytes) -> Result<Self, DecodeError> {
+        let (codec, descriptor) = state;
+        Ok(Described {
+            value: <Value as QueryResult>::decode(codec, msg)?,
+            descriptor: descriptor.clone(),
+        })
+    }
+}
+
+// A result decoded as R and also as a Value for the recording
+pub struct Recorded<R> {
+    pub value: R,
+    pub described: Described,
+}
+
+impl<R: QueryResult> QueryResult for Recorded<R> {
+    type State = (R::State, <Described as QueryResult>::State);
+
+    fn prepare(
+        ctx: &DescriptorContext,
+        root_pos: TypePos,
+    ) -> Result<Self::State, DescriptorMismatch> {
+        Ok((
+            R::prepare(ctx, root_pos)?,
+            Described::prepare(ctx, root_pos)?,
+        ))
+    }
+
+    fn decode(state: &mut Self::State, msg: &Bytes) -> Result<Self, DecodeError> {
+        Ok(Recorded {
+            value: R::decode(&mut state.0, msg)?,
+            described: Described::decode(&mut state.1, msg)?,
+        })
+    }
+}
+
+// One line of a recording
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub name: String,
+    // The Client method, e.g. query_single
+    pub method: String,
+    pub query: String,
+    // See telemetry::sanitize_args
+    pub args: String,
+    pub params: usize,
+    // None for execute and the json methods, and when the query failed
+    pub descriptor: Option<String>,
+    // The rows as a json array (a single row or none for query_single), the json itself for
+    // the json methods, null for execute
+    pub result: serde_json::Value,
+    // The error kind, e.g. InvalidReferenceError
+    pub error: Option<String>,
+    pub message: Option<String>,
+}
+
+impl Entry {
+    fn new(name: &str, method: &str, query: &str, args: &dyn Debug) -> Entry {
+        Entry {
+            name: name.to_string(),
+            method: method.to_string(),
+            query: query.to_string(),
+            args: sanitize_args(&args),
+            params: count_params(query),
+            descriptor: None,
+            result: serde_json::Value::Null,
+            error: None,
+            message: None,
+        }
+    }
+
+    fn rows<'a>(mut self, rows: impl IntoIterator<Item = &'a Described>) -> Entry {
+        let mut results = Vec::new();
+        for row in rows {
+            self.descriptor = Some(row.descriptor.to_string());
+            let result = values::to_json(&row.value)
+                .unwrap_or_else(|e| json!(format!("<not recorded: {e}>")));
+            results.push(result);
+        }
+        self.result = serde_json::Value::Array(results);
+        self
+    }
+
+    fn json(mut self, json: Option<&Json>) -> Entry {
+        self.result = json
+            .and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_default();
+        self
+    }
+
+    fn error(mut self, error: &edgedb_tokio::Error) -> Entry {
+        self.error = Some(error.kind_name().to_string());
+        self.message = Some(error.to_string());
+        self
+    }
+
+    // What's different about `replayed`, compared to this entry
+    pub fn differences(&self, replayed: &Entry) -> Vec<String> {
+        let mut differences = Vec::new();
+        if self.error != replayed.error {
+            let error = |entry: &Entry| match (&entry.error, &entry.message) {
+                (Some(kind), Some(message)) => format!("{kind}: {message}"),
+                _ => "no error".to_string(),
+            };
+            differences.push(format!("error: {} was {}", error(replayed), error(self)));
+            return differences;
+        }
+        // No rows means no descriptor was seen
+        if self.descriptor.is_some()
+            && replayed.descriptor.is_some()
+            && self.descriptor != replayed.descriptor
+        {
+            differences.push(format!(
+                "descriptor: {} was {}",
+                replayed.descriptor.as_deref().unwrap_or_default(),
+                self.descriptor.as_deref().unwrap_or_default()
+            ));
+        }
+        if self.result != replayed.result {
+            differences.push(format!("result: {} was {}", replayed.result, self.result));
+        }
+        differences
+    }
+}
+
+static RECORDER: OnceLock<Mutex<File>> = OnceLock::new();
+
+// Starts recording to `path`, replacing what's there
+pub fn start(path: &Path) -> io::Result<()> {
+    let file = File::create(path)?;
+    RECORDER
+        .set(Mutex::new(file))
+        .map_err(|_| io::Error::other("already recording"))
+}
+
+fn recording() -> bool {
+    RECORDER.get().is_some()
+}
+
+fn write(entry: &Entry) {
+    let Some(file) = RECORDER.get() else {
+        return;
+    };
+    let line = serde_json::to_string(entry).expect("entries are plain json");
+    let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+    // A run shouldn't fail because its recording did
+    if let Err(e) = writeln!(file, "{line}") {
+        tracing::warn!(error = %e, "couldn't write to the recording");
+    }
+}
+
+// The Client methods, recording when --record is given. Called by the ones in telemetry.rs.
+
+pub async fn query<R, A>(
+    client: &Client,
+    name: &str,
+    query: &str,
+    args: &A,
+) -> Result<Vec<R>, edgedb_tokio::Error>
+where
+    R: QueryResult,
+    A: QueryArgs + Debug,
+{
+    if !recording() {
+        return client.query(query, args).await;
+    }
+    let entry = Entry::new(name, "query", query, args);
+    match client.query::<Recorded<R>, _>(query, args).await {
+        Ok(rows) => {
+            write(&entry.rows(rows.iter().map(|row| &row.described)));
+            Ok(rows.into_iter().map(|row| row.value).collect())
+        }
+        Err(e) => {
+            write(&entry.error(&e));
+            Err(e)
+        }
+    }
+}
+
+pub async fn query_single<R, A>(
+    client: &Client,
+    name: &str,
+    query: &str,
+    args: &A,
+) -> Result<Option<R>, edgedb_tokio::Error>
+where
+    R: QueryResult,
+    A: QueryArgs + Debug,
+{
+    if !recording() {
+        return client.query_single(query, args).await;
+    }
+    let entry = Entry::new(name, "query_single", query, args);
+    match client.query_single::<Recorded<R>, _>(query, args).await {
+        Ok(row) => {
+            write(&entry.rows(row.iter().map(|row| &row.described)));
+            Ok(row.map(|row| row.value))
+        }
+        Err(e) => {
+            write(&entry.error(&e));
+            Err(e)
+        }
+    }
+}
+
+pub async fn query_required_single<R, A>(
+    client: &Client,
+    name: &str,
+    query: &str,
+    args: &A,
+) -> Result<R, edgedb_tokio::Error>
+where
+    R: QueryResult,
+    A: QueryArgs + Debug,
+{
+    if !recording() {
+        return client.query_required_single(query, args).await;
+    }
+    let entry = Entry::new(name, "query_required_single", query, args);
+    match client
+        .query_required_single::<Recorded<R>, _>(query, args)
+        .await
+    {
+        Ok(row) => {
+            write(&entry.rows([&row.described]));
+            Ok(row.value)
+        }
+        Err(e) => {
+            write(&entry.error(&e));
+            Err(e)
+        }
+    }
+}
+
+pub async fn execute<A>(
+    client: &Client,
+    name: &str,
+    query: &str,
+    args: &A,
+) -> Result<(), edgedb_tokio::Error>
+where
+    A: QueryArgs + Debug,
+{
+    let res = client.execute(query, args).await;
+    if recording() {
+        let entry = Entry::new(name, "execute", query, args);
+        match &res {
+            Ok(()) => write(&entry),
+            Err(e) => write(&entry.error(e)),
+        }
+    }
+    res
+}
+
+pub async fn query_json<A>(
+    client: &Client,
+    name: &str,
+    query: &str,
+    args: &A,
+) -> Result<Json, edgedb_tokio::Error>
+where
+    A: QueryArgs + Debug,
+{
+    let res = client.query_json(query, args).await;
+    if recording() {
+        let entry = Entry::new(name, "query_json", query, args);
+        match &res {
+            Ok(json) => write(&entry.json(Some(json))),
+            Err(e) => write(&entry.error(e)),
+        }
+    }
+    res
+}
+
+pub async fn query_single_json<A>(
+    client: &Client,
+    name: &str,
+    query: &str,
+    args: &A,
+) -> Result<Option<Json>, edgedb_tokio::Error>
+where
+    A: QueryArgs + Debug,
+{
+    let res = client.query_single_json(query, args).await;
+    if recording() {
+        let entry = Entry::new(name, "query_single_json", query, args);
+        match &res {
+            Ok(json) => write(&entry.json(json.as_ref())),
+            Err(e) => write(&entry.error(e)),
+        }
+    }
+    res
+}
+
+pub fn read(path: &Path) -> Result<Vec<Entry>, anyhow::Error> {
+    let file = File::open(path)?;
+    let mut entries = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: Entry = serde_json::from_str(&line)
+            .with_context(|| format!("line {} of {}", i + 1, path.display()))?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+// Runs a recorded query again, without committing anything, and records what it returns
+pub async fn replay_entry(client: &Client, recorded: &Entry) -> Entry {
+    let entry = Entry::new(&recorded.name, &recorded.method, &recorded.query, &());
+    let res = in_rollback(client, |mut tx| {
+        let query = recorded.query.clone();
+        let method = recorded.method.clone();
+        let entry = entry.clone();
+        async move {
+            Ok(match method.as_str() {
+                "execute" => {
+                    tx.execute(&query, &()).await?;
+                    entry
+                }
+                "query_json" => entry.json(Some(&tx.query_json(&query, &()).await?)),
+                "query_single_json" => {
+                    entry.json(tx.query_single_json(&query, &()).await?.as_ref())
+                }
+                "query_single" => {
+                    entry.rows(tx.query_single::<Described, _>(&query, &()).await?.iter())
+                }
+                // Fails with a NoDataError again if there's no row any more
+                "query_required_single" => {
+                    let row = tx.query_required_single::<Described, _>(&query, &());
+                    entry.rows([&row.await?])
+                }
+                _ => entry.rows(&tx.query::<Described, _>(&query, &()).await?),
+            })
+        }
+    })
+    .await;
+    res.unwrap_or_else(|e| entry.error(&e))
+}
+
+#[derive(Debug)]
+pub struct Summary {
+    pub same: usize,
+    pub different: usize,
+    pub skipped: usize,
+}
+
+// Replays the recording in `path`, printing what's different
+pub async fn replay(client: &Client, path: &Path) -> Result<Summary, anyhow::Error> {
+    let mut summary = Summary {
+        same: 0,
+        different: 0,
+        skipped: 0,
+    };
+    for (i, recorded) in read(path)?.iter().enumerate() {
+        let label = format!("{} {}", i + 1, recorded.name);
+        if recorded.params > 0 {
+            println!("skip  {label}: takes arguments, which are only recorded redacted");
+            summary.skipped += 1;
+            continue;
+        }
+        let replayed = replay_entry(client, recorded).await;
+        let differences = recorded.differences(&replayed);
+        if differences.is_empty() {
+            println!("same  {label}");
+            summary.same += 1;
+        } else {
+            println!("diff  {label}");
+            for difference in differences {
+                println!("      {difference}");
+            }
+            summary.different += 1;
+        }
+    }
+    Ok(summary)
+}
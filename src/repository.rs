@@ -0,0 +1,146 @@
+use std::{fmt, sync::Mutex, time::SystemTime};
+
+use async_trait::async_trait;
+use edgedb_errors::ConstraintViolationError;
+use edgedb_protocol::model::Datetime;
+use edgedb_tokio::Client;
+use uuid::Uuid;
+
+use crate::accounts::{self, AccountKey, AccountSummary};
+
+// Code that takes a Client can only be tested against a running instance. To test the
+// code around the queries (handlers, validation, status codes) on its own, the queries go
+// behind a trait:
+// * EdgeDbAccounts implements it with the functions in accounts.rs
+// * InMemoryAccounts keeps the accounts in a Vec, for tests
+// Handlers take an Arc<dyn AccountRepository>, so the web app hands them the EdgeDB one
+// and the tests in tests/web.rs hand them the fake, without a server or a database.
+//
+// The fake is only as good as its imitation of the database: it has to enforce the same
+// exclusive constraint on usernames and return the same ordering, or tests pass against
+// it that would fail for real. Keep the integration tests for the queries themselves.
+
+#[derive(Debug)]
+pub enum RepositoryError {
+    // The exclusive constraint on Account.username
+    UsernameTaken,
+    Database(edgedb_tokio::Error),
+}
+
+impl fmt::Display for RepositoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RepositoryError::UsernameTaken => write!(f, "username is already taken"),
+            RepositoryError::Database(e) => write!(f, "database error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for RepositoryError {}
+
+impl From<edgedb_tokio::Error> for RepositoryError {
+    fn from(error: edgedb_tokio::Error) -> Self {
+        if error.is::<ConstraintViolationError>() {
+            RepositoryError::UsernameTaken
+        } else {
+            RepositoryError::Database(error)
+        }
+    }
+}
+
+// async fn in traits needs the async_trait macro to be usable as a trait object
+#[async_trait]
+pub trait AccountRepository: Send + Sync {
+    async fn create(&self, username: &str) -> Result<AccountSummary, RepositoryError>;
+    async fn get(&self, key: &AccountKey) -> Result<Option<AccountSummary>, RepositoryError>;
+    // Newest first
+    async fn list(&self, offset: i64, limit: i64) -> Result<Vec<AccountSummary>, RepositoryError>;
+    async fn delete(&self, id: Uuid) -> Result<Option<Uuid>, RepositoryError>;
+}
+
+pub struct EdgeDbAccounts {
+    client: Client,
+}
+
+impl EdgeDbAccounts {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl AccountRepository for EdgeDbAccounts {
+    async fn create(&self, username: &str) -> Result<AccountSummary, RepositoryError> {
+        Ok(accounts::create(&self.client, username).await?)
+    }
+
+    async fn get(&self, key: &AccountKey) -> Result<Option<AccountSummary>, RepositoryError> {
+        Ok(accounts::get(&self.client, key).await?)
+    }
+
+    async fn list(&self, offset: i64, limit: i64) -> Result<Vec<AccountSummary>, RepositoryError> {
+        Ok(accounts::list(&self.client, offset, limit).await?)
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<Option<Uuid>, RepositoryError> {
+        Ok(accounts::delete(&self.client, id).await?)
+    }
+}
+
+// Accounts in insertion order. A std Mutex is fine as it's never held across an await.
+#[derive(Default)]
+pub struct InMemoryAccounts {
+    accounts: Mutex<Vec<AccountSummary>>,
+}
+
+impl InMemoryAccounts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl AccountRepository for InMemoryAccounts {
+    async fn create(&self, username: &str) -> Result<AccountSummary, RepositoryError> {
+        let mut accounts = self.accounts.lock().unwrap();
+        if accounts.iter().any(|a| a.username == username) {
+            return Err(RepositoryError::UsernameTaken);
+        }
+        let account = AccountSummary {
+            username: username.to_string(),
+            id: Uuid::from_u128(fastrand::u128(..)),
+            created_at: Datetime::try_from(SystemTime::now()).expect("clock is in range"),
+        };
+        accounts.push(account.clone());
+        Ok(account)
+    }
+
+    async fn get(&self, key: &AccountKey) -> Result<Option<AccountSummary>, RepositoryError> {
+        let accounts = self.accounts.lock().unwrap();
+        Ok(accounts
+            .iter()
+            .find(|a| match key {
+                AccountKey::Id(id) => a.id == *id,
+                AccountKey::Username(username) => a.username == *username,
+            })
+            .cloned())
+    }
+
+    async fn list(&self, offset: i64, limit: i64) -> Result<Vec<AccountSummary>, RepositoryError> {
+        let accounts = self.accounts.lock().unwrap();
+        Ok(accounts
+            .iter()
+            .rev()
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn delete(&self, id: Uuid) -> Result<Option<Uuid>, RepositoryError> {
+        let mut accounts = self.accounts.lock().unwrap();
+        let before = accounts.len();
+        accounts.retain(|a| a.id != id);
+        Ok((accounts.len() < before).then_some(id))
+    }
+}
@@ -0,0 +1,83 @@
+use edgedb_errors::{CardinalityViolationError, ResultCardinalityMismatchError};
+use edgedb_tokio::Client;
+
+use crate::{accounts::AccountSummary, display_result, random_name};
+
+// query_required_single (and query_single) never quietly take the first row of several.
+// Each query method tells the server how many results it expects, and the server checks
+// that against the cardinality it infers for the query when compiling it:
+//
+// * A filter that can match more than one object is MANY, whatever the data. The query
+//   fails with a ResultCardinalityMismatchError before it runs, so it fails the same with
+//   two matching Accounts as with one. That's a bug caught on the first call, not when a
+//   second row turns up in production.
+// * A filter on an exclusive property (`.username = ...`, `.id = ...`) or `limit 1` is
+//   AT_MOST_ONE, so it's allowed: the schema or the query guarantees a single row.
+// * assert_single() turns "can be many" into "at most one" at runtime. The query compiles
+//   and a second row fails it with a CardinalityViolationError, for when the data should
+//   guarantee one row but the schema can't say so.
+//
+// To really take the first of many, say which is first: `order by ... limit 1`.
+
+const BY_PREFIX: &str = "select Account { username, id, created_at }
+    filter .username like <str>$0 ++ '%'";
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let prefix = format!("single{}", random_name());
+    client
+        .execute(
+            "for name in {'a', 'b'} union (insert Account { username := <str>$0 ++ name })",
+            &(&prefix,),
+        )
+        .await?;
+
+    let res = async {
+        // Two matches, and an error rather than one of them
+        let res: Result<AccountSummary, _> =
+            client.query_required_single(BY_PREFIX, &(&prefix,)).await;
+        display_result(BY_PREFIX, &res);
+        assert!(res.is_err_and(|e| e.is::<ResultCardinalityMismatchError>()));
+        let res: Result<Option<AccountSummary>, _> =
+            client.query_single(BY_PREFIX, &(&prefix,)).await;
+        assert!(res.is_err_and(|e| e.is::<ResultCardinalityMismatchError>()));
+
+        // One match fails just the same, as it's the query that's MANY
+        let only_a = format!("{prefix}a");
+        let res: Result<AccountSummary, _> =
+            client.query_required_single(BY_PREFIX, &(&only_a,)).await;
+        display_result(BY_PREFIX, &res);
+        assert!(res.is_err_and(|e| e.is::<ResultCardinalityMismatchError>()));
+
+        // An exclusive property makes it AT_MOST_ONE
+        let query = "select Account { username, id, created_at } filter .username = <str>$0";
+        let account: AccountSummary = client.query_required_single(query, &(&only_a,)).await?;
+        display_result(query, &account);
+        assert_eq!(account.username, only_a);
+
+        // As does choosing the first
+        let query = format!("{BY_PREFIX} order by .username limit 1");
+        let first: AccountSummary = client.query_required_single(&query, &(&prefix,)).await?;
+        display_result(&query, &first);
+        assert_eq!(first.username, only_a);
+
+        // assert_single moves the check to when the query runs
+        let query = "select assert_single((
+            select Account filter .username like <str>$0 ++ '%'
+        )) { username, id, created_at }";
+        let account: AccountSummary = client.query_required_single(query, &(&only_a,)).await?;
+        display_result(query, &account);
+        let res: Result<AccountSummary, _> = client.query_required_single(query, &(&prefix,)).await;
+        display_result(query, &res);
+        assert!(res.is_err_and(|e| e.is::<CardinalityViolationError>()));
+        Ok(())
+    }
+    .await;
+
+    client
+        .execute(
+            "delete Account filter .username like <str>$0 ++ '%'",
+            &(&prefix,),
+        )
+        .await?;
+    res
+}
@@ -0,0 +1,41 @@
+use std::{cell::RefCell, future::Future};
+
+use edgedb_errors::{ErrorKind, UserError};
+use edgedb_tokio::{Client, Transaction};
+
+// Runs `body` in a transaction that is always rolled back, and returns what `body` returned.
+// Nothing `body` writes through the transaction is ever committed.
+//
+// The client commits a transaction whenever its closure returns Ok, so once `body` is done
+// the closure returns a sentinel error instead, which makes the client roll back. The
+// sentinel is then recognized and turned back into `body`'s result. Any other error is
+// returned as is.
+//
+// Used by the smoke command and by the integration tests (tests/common/mod.rs has more on
+// when this works and when it doesn't). Like any transaction closure, `body` is run again
+// if the transaction hits a retryable error such as a serialization conflict.
+pub async fn in_rollback<T, B, F>(client: &Client, mut body: B) -> Result<T, edgedb_tokio::Error>
+where
+    B: FnMut(Transaction) -> F,
+    F: Future<Output = Result<T, edgedb_tokio::Error>>,
+{
+    const ROLLBACK: &str = "rolled back by in_rollback";
+    let output = RefCell::new(None);
+    let res: Result<(), _> = client
+        .transaction(|tx| {
+            let fut = body(tx);
+            let output = &output;
+            async move {
+                *output.borrow_mut() = Some(fut.await?);
+                Err(UserError::with_message(ROLLBACK))
+            }
+        })
+        .await;
+    match res {
+        Err(e) if e.is::<UserError>() && e.to_string().contains(ROLLBACK) => Ok(output
+            .into_inner()
+            .expect("the body finished before the rollback")),
+        Err(e) => Err(e),
+        Ok(()) => unreachable!("in_rollback transactions never commit"),
+    }
+}
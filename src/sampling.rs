@@ -0,0 +1,102 @@
+use edgedb_tokio::Client;
+
+use crate::{accounts::AccountSummary, display_result, random_name};
+
+// Random samples of objects, for previews and QA tooling on production-sized data. Both
+// queries decode into the usual structs, only the filtering differs.
+//
+// `order by random() limit n` is the obvious way, and exactly n objects come back. But the
+// database has to give every object a random number and sort all of them to return n, so
+// it gets slower as the table grows, and a second run gives a different sample.
+//
+// For big tables, keep each object with a fixed probability instead, decided by a hash of
+// its id:
+//
+//   filter to_int32(b'\x00\x00' ++ ext::pgcrypto::digest(<str>.id ++ seed, 'md5')[0:2],
+//                   Endian.Big) < threshold
+//
+// The first two bytes of the hash are a number from 0 to 65535 that's spread evenly over
+// the objects, so a threshold of 655 keeps about 1%. There's no sort, and the same seed
+// always picks the same objects (handy to come back to a QA sample), while a new seed gives
+// a new sample. The size is only approximately the fraction asked for. The digest comes
+// from the pgcrypto extension, already in the schema for the passwords example.
+
+pub async fn order_by_random(
+    client: &Client,
+    prefix: &str,
+    n: i64,
+) -> Result<Vec<AccountSummary>, edgedb_tokio::Error> {
+    client
+        .query(
+            "select Account { username, id, created_at }
+            filter .username like <str>$0 ++ '%'
+            order by random()
+            limit <int64>$1",
+            &(prefix, n),
+        )
+        .await
+}
+
+// About `fraction` of the Accounts, always the same ones for the same seed
+pub async fn by_id_hash(
+    client: &Client,
+    prefix: &str,
+    fraction: f64,
+    seed: &str,
+) -> Result<Vec<AccountSummary>, edgedb_tokio::Error> {
+    let threshold = (fraction.clamp(0.0, 1.0) * 65536.0) as i64;
+    client
+        .query(
+            "select Account { username, id, created_at }
+            filter .username like <str>$0 ++ '%'
+            and to_int32(
+                b'\\x00\\x00' ++ ext::pgcrypto::digest(<str>.id ++ <str>$2, 'md5')[0:2],
+                Endian.Big
+            ) < <int64>$1
+            order by .username",
+            &(prefix, threshold, seed),
+        )
+        .await
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let prefix = format!("sample{}", random_name());
+    client
+        .execute(
+            "for n in range_unpack(range(0, 200)) union (
+                insert Account { username := <str>$0 ++ '-' ++ <str>n }
+            )",
+            &(&prefix,),
+        )
+        .await?;
+
+    let res = async {
+        let sample = order_by_random(client, &prefix, 5).await?;
+        display_result("order by random() limit 5", &sample);
+        assert_eq!(sample.len(), 5);
+
+        let sample = by_id_hash(client, &prefix, 0.1, "qa-1").await?;
+        let usernames: Vec<&str> = sample.iter().map(|a| a.username.as_str()).collect();
+        println!(
+            "About 10% by id hash: {} of 200, {usernames:?}\n",
+            sample.len()
+        );
+        assert!(sample.len() < 60);
+
+        // The same seed picks the same Accounts
+        let again = by_id_hash(client, &prefix, 0.1, "qa-1").await?;
+        let ids = |sample: &[AccountSummary]| sample.iter().map(|a| a.id).collect::<Vec<_>>();
+        assert_eq!(ids(&sample), ids(&again));
+        assert_eq!(by_id_hash(client, &prefix, 1.0, "qa-1").await?.len(), 200);
+        Ok(())
+    }
+    .await;
+
+    client
+        .execute(
+            "delete Account filter .username like <str>$0 ++ '%'",
+            &(&prefix,),
+        )
+        .await?;
+    res
+}
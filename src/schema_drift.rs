@@ -0,0 +1,269 @@
+use std::{collections::BTreeSet, fs, path::Path};
+
+use edgedb_derive::Queryable;
+use edgedb_tokio::Client;
+
+// A preflight check for deployments: is the database's schema the one in dbschema/?
+//
+// `describe schema as sdl` gives the database's schema as SDL, which is written to a file
+// for reference (or for diffing by hand). It can't be compared with the files in dbschema/
+// line by line though: the server prints its own normalized SDL, with fully qualified
+// names, different syntax for the same declarations and everything in its own order.
+//
+// So the check compares what's declared instead. The .esdl files are read with a small
+// tokenizer that picks out the object types, their properties and links, the scalar
+// types and the globals of each module, and the same names are queried from the schema
+// module. Anything on one side only is drift:
+// * only in the database: DDL that was run outside of migrations (see the ddl example),
+//   or a migration applied from somewhere else
+// * only in dbschema/: changes that haven't been made into a migration and applied yet
+//
+// It's a check of names, not definitions: a property whose type or constraints changed
+// still has the same name. `edgedb migration status` does the full comparison, and needs
+// the CLI and the migration files. Aliases and functions aren't compared.
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Declared {
+    pub modules: BTreeSet<String>,
+    // e.g. default::Account and default::Account.username
+    pub items: BTreeSet<String>,
+}
+
+#[derive(Debug)]
+pub struct Drift {
+    pub only_in_database: Vec<String>,
+    pub only_in_files: Vec<String>,
+}
+
+impl Drift {
+    pub fn is_empty(&self) -> bool {
+        self.only_in_database.is_empty() && self.only_in_files.is_empty()
+    }
+}
+
+fn tokenize(sdl: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = sdl.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '#' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            // String literals only matter as something that isn't a brace or a semicolon
+            '\'' | '"' => {
+                while let Some(next) = chars.next() {
+                    if next == '\\' {
+                        chars.next();
+                    } else if next == c {
+                        break;
+                    }
+                }
+                tokens.push("''".to_string());
+            }
+            '`' => {
+                let name: String = chars.by_ref().take_while(|&c| c != '`').collect();
+                tokens.push(name);
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut word = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if next.is_alphanumeric() || next == '_' || next == ':' {
+                        word.push(next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                // A trailing : is the shorthand `name: type`, not part of the name
+                if let Some(name) = word.strip_suffix(':').filter(|w| !w.ends_with(':')) {
+                    tokens.push(name.to_string());
+                    tokens.push(":".to_string());
+                } else {
+                    tokens.push(word);
+                }
+            }
+            c => tokens.push(c.to_string()),
+        }
+    }
+    tokens
+}
+
+const QUALIFIERS: [&str; 6] = [
+    "required",
+    "optional",
+    "single",
+    "multi",
+    "overloaded",
+    "abstract",
+];
+
+// The name a statement inside a type body declares, if it's a property or link
+fn pointer_name(statement: &[String]) -> Option<&str> {
+    let mut words = statement
+        .iter()
+        .map(String::as_str)
+        .skip_while(|word| QUALIFIERS.contains(word));
+    match words.next()? {
+        "property" | "link" => words.next(),
+        "index" | "deferred" | "constraint" | "access" | "trigger" | "rewrite" | "annotation" => {
+            None
+        }
+        name => match words.next()? {
+            ":" | "-" => Some(name),
+            _ => None,
+        },
+    }
+}
+
+enum Block {
+    Module(String),
+    Type(String),
+    Other,
+}
+
+// The modules, types, pointers, scalars and globals declared in some SDL
+pub fn parse_sdl(sdl: &str) -> Declared {
+    let mut declared = Declared::default();
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut statement: Vec<String> = Vec::new();
+    let mut parens = 0;
+    for token in tokenize(sdl) {
+        match token.as_str() {
+            "(" | "[" => parens += 1,
+            ")" | "]" => parens -= 1,
+            "{" | ";" if parens == 0 => {
+                let opens = token == "{";
+                let words: Vec<&str> = statement
+                    .iter()
+                    .map(String::as_str)
+                    .skip_while(|word| QUALIFIERS.contains(word))
+                    .collect();
+                let block = match (blocks.last(), words.as_slice()) {
+                    (None | Some(Block::Module(_)), ["module", name, ..]) => {
+                        let module = match blocks.last() {
+                            Some(Block::Module(outer)) => format!("{outer}::{name}"),
+                            _ => name.to_string(),
+                        };
+                        declared.modules.insert(module.clone());
+                        Block::Module(module)
+                    }
+                    (Some(Block::Module(module)), ["type", name, ..]) => {
+                        let name = format!("{module}::{name}");
+                        declared.items.insert(name.clone());
+                        Block::Type(name)
+                    }
+                    (Some(Block::Module(module)), ["scalar", "type", name, ..])
+                    | (Some(Block::Module(module)), ["global", name, ..]) => {
+                        declared.items.insert(format!("{module}::{name}"));
+                        Block::Other
+                    }
+                    (Some(Block::Type(type_name)), _) => {
+                        if let Some(name) = pointer_name(&statement) {
+                            declared.items.insert(format!("{type_name}.{name}"));
+                        }
+                        Block::Other
+                    }
+                    _ => Block::Other,
+                };
+                if opens {
+                    blocks.push(block);
+                }
+                statement.clear();
+                continue;
+            }
+            "}" if parens == 0 => {
+                blocks.pop();
+                statement.clear();
+                continue;
+            }
+            _ => {}
+        }
+        statement.push(token);
+    }
+    declared
+}
+
+pub fn read_schema_dir(dir: &Path) -> Result<Declared, anyhow::Error> {
+    let mut declared = Declared::default();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "esdl") {
+            let file = parse_sdl(&fs::read_to_string(&path)?);
+            declared.modules.extend(file.modules);
+            declared.items.extend(file.items);
+        }
+    }
+    Ok(declared)
+}
+
+#[derive(Queryable)]
+struct ObjectType {
+    name: String,
+    pointers: Vec<String>,
+}
+
+async fn database_items(
+    client: &Client,
+    modules: &BTreeSet<String>,
+) -> Result<BTreeSet<String>, edgedb_tokio::Error> {
+    let modules: Vec<String> = modules.iter().cloned().collect();
+    // Only pointers declared on the type itself (@owned), not inherited ones or id
+    let types: Vec<ObjectType> = client
+        .query(
+            "with module_names := array_unpack(<array<str>>$0)
+            select schema::ObjectType {
+                name,
+                pointers := (select .pointers filter @owned).name,
+            }
+            filter any(.name like module_names ++ '::%')
+            # Aliases and the types like `Movie | Show` the schema makes for itself
+            and not .from_alias
+            and not .compound_type",
+            &(modules.clone(),),
+        )
+        .await?;
+    let others: Vec<String> = client
+        .query(
+            "with module_names := array_unpack(<array<str>>$0)
+            select (
+                select {schema::ScalarType, schema::Global}
+                filter any(.name like module_names ++ '::%')
+            ).name",
+            &(modules,),
+        )
+        .await?;
+    let mut items: BTreeSet<String> = others.into_iter().collect();
+    for object_type in types {
+        for pointer in object_type.pointers {
+            items.insert(format!("{}.{pointer}", object_type.name));
+        }
+        items.insert(object_type.name);
+    }
+    Ok(items)
+}
+
+// Writes the database's schema as SDL to `out`, if given, and compares it with the files
+// in `schema_dir`
+pub async fn run(
+    client: &Client,
+    schema_dir: &Path,
+    out: Option<&Path>,
+) -> Result<Drift, anyhow::Error> {
+    if let Some(out) = out {
+        let sdl: String = client
+            .query_required_single("describe schema as sdl", &())
+            .await?;
+        fs::write(out, sdl)?;
+    }
+    let files = read_schema_dir(schema_dir)?;
+    let database = database_items(client, &files.modules).await?;
+    Ok(Drift {
+        only_in_database: database.difference(&files.items).cloned().collect(),
+        only_in_files: files.items.difference(&database).cloned().collect(),
+    })
+}
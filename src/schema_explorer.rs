@@ -0,0 +1,230 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io::{self, IsTerminal},
+};
+
+use dialoguer::{theme::ColorfulTheme, Select};
+use edgedb_derive::Queryable;
+use edgedb_tokio::Client;
+
+// Browsing the schema from the terminal: `cargo run -- schema` lists the modules, then
+// the object types of the module picked, then the properties and links of the type picked,
+// each with its target type, cardinality and constraints, written the way SDL would:
+//
+// required property username -> str  constraint exclusive
+// multi link posts -> default::Post  computed
+//
+// Picking a link goes on to its target type, and Esc goes back a level (or quits from the
+// list of modules). Nothing is ever written, it only reads the schema module.
+//
+// The whole schema is read with one introspection query at the start, and browsing is
+// done on what came back, so moving around doesn't make any more round trips. The lists
+// are dialoguer Selects like the instance picker in instances.rs, so it needs a terminal;
+// `cargo run -- describe <type>` prints the same kind of thing without one.
+//
+// Only object types are listed, not the server's own (std, schema, sys and the like),
+// aliases or the types like `Movie | Show` the schema makes for itself.
+
+#[derive(Debug, Clone, PartialEq, Queryable)]
+pub struct Pointer {
+    pub name: String,
+    pub is_link: bool,
+    pub target: String,
+    pub multi: bool,
+    pub required: bool,
+    pub readonly: bool,
+    pub computed: bool,
+    // Declared on this type rather than inherited
+    pub owned: bool,
+    pub constraints: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Queryable)]
+pub struct ObjectType {
+    pub name: String,
+    pub is_abstract: bool,
+    pub bases: Vec<String>,
+    pub pointers: Vec<Pointer>,
+}
+
+// The server's own types go without their module, like in SDL
+fn short(name: &str) -> &str {
+    name.strip_prefix("std::").unwrap_or(name)
+}
+
+impl fmt::Display for Pointer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.required {
+            write!(f, "required ")?;
+        }
+        if self.multi {
+            write!(f, "multi ")?;
+        }
+        let kind = if self.is_link { "link" } else { "property" };
+        write!(f, "{kind} {} -> {}", self.name, short(&self.target))?;
+        if !self.constraints.is_empty() {
+            let constraints: Vec<&str> = self.constraints.iter().map(|c| short(c)).collect();
+            write!(f, "  constraint {}", constraints.join(", "))?;
+        }
+        for (flag, set) in [
+            ("computed", self.computed),
+            ("readonly", self.readonly),
+            ("inherited", !self.owned),
+        ] {
+            if set {
+                write!(f, "  {flag}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ObjectType {
+    // Module and name, split at the last ::
+    pub fn split_name(&self) -> (&str, &str) {
+        self.name.rsplit_once("::").unwrap_or(("", &self.name))
+    }
+}
+
+impl fmt::Display for ObjectType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_abstract {
+            write!(f, "abstract ")?;
+        }
+        write!(f, "type {}", self.split_name().1)?;
+        let bases: Vec<&str> = self
+            .bases
+            .iter()
+            .map(|base| short(base))
+            .filter(|&base| base != "BaseObject" && base != "Object")
+            .collect();
+        if !bases.is_empty() {
+            write!(f, " extending {}", bases.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+pub async fn read_schema(client: &Client) -> Result<Vec<ObjectType>, edgedb_tokio::Error> {
+    client
+        .query(
+            "select schema::ObjectType {
+                name,
+                is_abstract := .abstract ?? false,
+                bases := .bases.name,
+                pointers := (
+                    select .pointers {
+                        name,
+                        is_link := .__type__.name = 'schema::Link',
+                        target := .target.name,
+                        multi := .cardinality ?= schema::Cardinality.Many,
+                        required := .required ?? false,
+                        readonly := .readonly ?? false,
+                        computed := exists .expr,
+                        owned := @owned,
+                        constraints := .constraints.name,
+                    }
+                    # id and __type__ are on every object
+                    filter .name not in {'id', '__type__'}
+                    order by .name
+                ),
+            }
+            filter not .builtin
+            and not .from_alias
+            and not .compound_type
+            order by .name",
+            &(),
+        )
+        .await
+}
+
+// The types by module, in order
+pub fn modules(types: &[ObjectType]) -> BTreeMap<&str, Vec<&ObjectType>> {
+    let mut modules: BTreeMap<&str, Vec<&ObjectType>> = BTreeMap::new();
+    for object_type in types {
+        let (module, _) = object_type.split_name();
+        modules.entry(module).or_default().push(object_type);
+    }
+    modules
+}
+
+enum View<'a> {
+    Modules,
+    Module(&'a str),
+    Type(&'a ObjectType),
+}
+
+fn select<T: ToString>(prompt: &str, items: &[T]) -> Result<Option<usize>, anyhow::Error> {
+    Ok(Select::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .items(items)
+        .default(0)
+        .max_length(20)
+        .interact_opt()?)
+}
+
+// Lets the user move around `types` until they press Esc on the list of modules
+pub fn browse(types: &[ObjectType]) -> Result<(), anyhow::Error> {
+    let modules = modules(types);
+    let by_name: BTreeMap<&str, &ObjectType> = types
+        .iter()
+        .map(|object_type| (object_type.name.as_str(), object_type))
+        .collect();
+    // Where we've been, with where we are last
+    let mut views = vec![View::Modules];
+    while let Some(view) = views.last() {
+        let next = match view {
+            View::Modules => {
+                let names: Vec<&str> = modules.keys().copied().collect();
+                let items: Vec<String> = modules
+                    .iter()
+                    .map(|(module, types)| format!("{module} ({} types)", types.len()))
+                    .collect();
+                select("Module (Esc to quit)", &items)?.map(|i| View::Module(names[i]))
+            }
+            View::Module(module) => {
+                let types = &modules[module];
+                select(&format!("module {module}"), types)?.map(|i| View::Type(types[i]))
+            }
+            View::Type(object_type) => {
+                let pointers = &object_type.pointers;
+                let prompt = format!("{object_type} (pick a link to go to its target)");
+                if pointers.is_empty() {
+                    println!("{object_type} has no properties or links");
+                    None
+                } else {
+                    // Properties lead nowhere, so picking one asks again
+                    loop {
+                        let Some(i) = select(&prompt, pointers)? else {
+                            break None;
+                        };
+                        if let Some(&target) = by_name.get(pointers[i].target.as_str()) {
+                            break Some(View::Type(target));
+                        }
+                    }
+                }
+            }
+        };
+        match next {
+            Some(next) => views.push(next),
+            None => {
+                views.pop();
+            }
+        }
+    }
+    Ok(())
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    anyhow::ensure!(
+        io::stdin().is_terminal() && io::stderr().is_terminal(),
+        "the schema explorer needs a terminal, `cargo run -- describe <type>` doesn't"
+    );
+    let types = read_schema(client).await?;
+    anyhow::ensure!(
+        !types.is_empty(),
+        "no object types, run the migrations first"
+    );
+    browse(&types)
+}
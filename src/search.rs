@@ -0,0 +1,210 @@
+use std::{fmt, str::FromStr};
+
+use edgedb_derive::Queryable;
+use edgedb_protocol::model::Datetime;
+use edgedb_tokio::Client;
+use uuid::Uuid;
+
+use crate::{display_result, random_name};
+
+// A search over Accounts with optional criteria, as a search endpoint gets them from a
+// form: any combination of a part of the username, a creation date and a status, in one
+// of a few orders.
+//
+// The query is put together from the criteria that are set, so it only filters on what
+// was asked for, but nothing the user sends ever becomes query text:
+// * Every value is a query argument. All of them are always bound, at the top of the
+//   query, whether or not a filter uses them, so the arguments are the same tuple for
+//   every combination.
+// * The filters and orders are fixed fragments in this file, picked by which fields are
+//   set and by the Sort enum. A username full of quotes and semicolons is just a string
+//   to compare with.
+// So the query text only depends on which fields are set, never on what's in them, which
+// is what tests/search.rs checks.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccountStatus {
+    Active,
+    Suspended,
+    Closed,
+}
+
+impl AccountStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AccountStatus::Active => "Active",
+            AccountStatus::Suspended => "Suspended",
+            AccountStatus::Closed => "Closed",
+        }
+    }
+}
+
+// A label that isn't one of AccountStatus's, e.g. from a newer schema
+#[derive(Debug, PartialEq)]
+pub struct UnknownStatus(pub String);
+
+impl fmt::Display for UnknownStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown account status {:?}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownStatus {}
+
+impl FromStr for AccountStatus {
+    type Err = UnknownStatus;
+
+    fn from_str(label: &str) -> Result<Self, Self::Err> {
+        match label {
+            "Active" => Ok(AccountStatus::Active),
+            "Suspended" => Ok(AccountStatus::Suspended),
+            "Closed" => Ok(AccountStatus::Closed),
+            other => Err(UnknownStatus(other.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Sort {
+    #[default]
+    Newest,
+    Oldest,
+    Username,
+}
+
+impl Sort {
+    fn order_by(self) -> &'static str {
+        match self {
+            Sort::Newest => "order by .created_at desc then .id",
+            Sort::Oldest => "order by .created_at then .id",
+            Sort::Username => "order by .username then .id",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SearchParams {
+    pub username_contains: Option<String>,
+    pub created_after: Option<Datetime>,
+    pub status: Option<AccountStatus>,
+    pub sort: Sort,
+}
+
+#[derive(Debug, Queryable)]
+pub struct SearchResult {
+    pub username: String,
+    pub id: Uuid,
+    pub created_at: Datetime,
+    pub status: String,
+}
+
+// The query text for `params`, which takes the arguments from `arguments`
+pub fn build(params: &SearchParams) -> String {
+    let mut filters = Vec::new();
+    if params.username_contains.is_some() {
+        filters.push("contains(str_lower(.username), str_lower(username_contains))");
+    }
+    if params.created_after.is_some() {
+        filters.push(".created_at > created_after");
+    }
+    if params.status.is_some() {
+        filters.push(".status = <AccountStatus>status");
+    }
+    let filter = if filters.is_empty() {
+        String::new()
+    } else {
+        format!("filter {}", filters.join(" and "))
+    };
+    format!(
+        "with
+            username_contains := <optional str>$0,
+            created_after := <optional datetime>$1,
+            status := <optional str>$2
+        select Account {{ username, id, created_at, status := <str>.status }}
+        {filter}
+        {}
+        limit <int64>$3",
+        params.sort.order_by()
+    )
+}
+
+pub fn arguments(
+    params: &SearchParams,
+    limit: i64,
+) -> (Option<String>, Option<Datetime>, Option<String>, i64) {
+    (
+        params.username_contains.clone(),
+        params.created_after,
+        params.status.map(|status| status.as_str().to_string()),
+        limit,
+    )
+}
+
+pub async fn search(
+    client: &Client,
+    params: &SearchParams,
+    limit: i64,
+) -> Result<Vec<SearchResult>, edgedb_tokio::Error> {
+    client
+        .query(&build(params), &arguments(params, limit))
+        .await
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let prefix = format!("search{}", random_name());
+    client
+        .execute(
+            "for account in {('ada', 'Active'), ('bob', 'Suspended'), ('cy', 'Active')} union (
+                insert Account {
+                    username := <str>$0 ++ '_' ++ account.0,
+                    status := <AccountStatus>account.1,
+                }
+            )",
+            &(&prefix,),
+        )
+        .await?;
+
+    let res = async {
+        let searches = [
+            SearchParams {
+                username_contains: Some(prefix.clone()),
+                sort: Sort::Username,
+                ..Default::default()
+            },
+            SearchParams {
+                username_contains: Some(prefix.clone()),
+                status: Some(AccountStatus::Active),
+                sort: Sort::Username,
+                ..Default::default()
+            },
+            // Quotes and keywords are only something to look for in usernames
+            SearchParams {
+                username_contains: Some("' or true; delete Account; #".to_string()),
+                ..Default::default()
+            },
+        ];
+        let mut found = Vec::new();
+        for params in &searches {
+            let results = search(client, params, 10).await?;
+            display_result(&build(params), &results);
+            let usernames: Vec<String> = results.into_iter().map(|r| r.username).collect();
+            found.push(usernames);
+        }
+        let names = |suffixes: &[&str]| -> Vec<String> {
+            suffixes.iter().map(|s| format!("{prefix}_{s}")).collect()
+        };
+        assert_eq!(found[0], names(&["ada", "bob", "cy"]));
+        assert_eq!(found[1], names(&["ada", "cy"]));
+        assert!(found[2].is_empty());
+        Ok(())
+    }
+    .await;
+
+    client
+        .execute(
+            "delete Account filter .username like <str>$0 ++ '%'",
+            &(&prefix,),
+        )
+        .await?;
+    res
+}
@@ -0,0 +1,122 @@
+use edgedb_tokio::Client;
+
+use crate::{accounts::AccountSummary, display_result, factory::AccountFactory, random_name};
+
+// Combining sets with `union`, `intersect` and `except`, and removing duplicates with
+// `distinct`, on scalars and on objects.
+//
+// Every set in EdgeQL is a multiset, and the operators keep it that way, which is where
+// they differ from SQL:
+// * `union` keeps duplicates, like SQL's UNION ALL. There's no deduplicating union, wrap
+//   it in `distinct` instead. `{1, 2}` is itself shorthand for `1 union 2`.
+// * `intersect` keeps an element as many times as it's in both sides, like INTERSECT ALL,
+//   and `except` takes away one copy per copy on the right, like EXCEPT ALL.
+// * `distinct` compares scalars by value, tuples element by element and objects by
+//   identity, so two Accounts with the same username are still two Accounts.
+// * None of them sort. A set has no order until an `order by` gives it one.
+// Objects from both sides must share a type for the result to have one. Here they're all
+// Accounts, and the result has the same shape as any other select of Account.
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    // Scalars: each query orders its result so it can be compared
+    let queries = [
+        (
+            "with s := {1, 2, 2, 3} union {2, 3, 4} select s order by s",
+            vec![1, 2, 2, 2, 3, 3, 4],
+        ),
+        (
+            "with s := distinct ({1, 2, 2, 3} union {2, 3, 4}) select s order by s",
+            vec![1, 2, 3, 4],
+        ),
+        (
+            "with s := {1, 2, 2, 3} intersect {2, 2, 2, 4} select s order by s",
+            vec![2, 2],
+        ),
+        (
+            "with s := {1, 2, 2, 3} except {2, 4} select s order by s",
+            vec![1, 2, 3],
+        ),
+    ];
+    for (query, expected) in queries {
+        let res: Vec<i64> = client.query(query, &()).await?;
+        display_result(query, &res);
+        assert_eq!(res, expected);
+    }
+
+    // Objects: ada and bob have written Posts, bob and cy are Suspended, dan neither
+    let prefix = format!("setops{}", random_name());
+    for (name, posts) in [("ada", 1), ("bob", 1), ("cy", 0), ("dan", 0)] {
+        AccountFactory::new()
+            .username(format!("{prefix}_{name}"))
+            .with_posts(posts)
+            .create(client)
+            .await?;
+    }
+    client
+        .execute(
+            "update Account filter .username in {<str>$0 ++ '_bob', <str>$0 ++ '_cy'}
+            set { status := AccountStatus.Suspended }",
+            &(&prefix,),
+        )
+        .await?;
+
+    let res = async {
+        let sets = "with
+            authors := (select Author filter .username like <str>$0 ++ '%'),
+            suspended := (select Account filter .username like <str>$0 ++ '%'
+                and .status = AccountStatus.Suspended)";
+        let cases = [
+            ("authors union suspended", vec!["ada", "bob", "bob", "cy"]),
+            (
+                "distinct (authors union suspended)",
+                vec!["ada", "bob", "cy"],
+            ),
+            ("authors intersect suspended", vec!["bob"]),
+            ("authors except suspended", vec!["ada"]),
+            ("suspended except authors", vec!["cy"]),
+        ];
+        for (set, expected) in cases {
+            let query = format!(
+                "{sets}
+                select ({set}) {{ username, id, created_at }}
+                order by .username"
+            );
+            let accounts: Vec<AccountSummary> = client.query(&query, &(&prefix,)).await?;
+            display_result(set, &accounts);
+            let names: Vec<&str> = accounts
+                .iter()
+                .map(|a| &a.username[prefix.len() + 1..])
+                .collect();
+            assert_eq!(names, expected);
+        }
+
+        // Author is an alias of Account, so its objects are Accounts: the same bob is on
+        // both sides, and distinct sees one object
+        let query = format!(
+            "{sets}
+            select (count(authors union suspended), count(distinct (authors union suspended)))"
+        );
+        let counts: (i64, i64) = client.query_required_single(&query, &(&prefix,)).await?;
+        display_result(&query, &counts);
+        assert_eq!(counts, (4, 3));
+
+        // distinct on a property is distinct on values
+        let query = "select distinct <str>(
+            select Account filter .username like <str>$0 ++ '%'
+        ).status";
+        let mut statuses: Vec<String> = client.query(query, &(&prefix,)).await?;
+        statuses.sort();
+        display_result(query, &statuses);
+        assert_eq!(statuses, ["Active", "Suspended"]);
+        Ok(())
+    }
+    .await;
+
+    client
+        .execute(
+            "delete Account filter .username like <str>$0 ++ '%'",
+            &(&prefix,),
+        )
+        .await?;
+    res
+}
@@ -0,0 +1,206 @@
+use edgedb_errors::{ErrorKind, UserError};
+use edgedb_protocol::model::Json;
+use edgedb_tokio::Client;
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+
+use crate::{display_result, factory::AccountFactory, random_name};
+
+// Partial updates of a json property: Account.settings holds a user's preferences as one
+// json object, and a request changes a few keys of it without sending the rest. There are
+// two places to do the merge.
+//
+// In Rust, inside a transaction (patch_in_transaction): read the settings, apply the patch
+// with serde_json (merge_patch is RFC 7396: objects merge recursively and a null removes
+// the key), write the result back. The merge can be anything Rust can do, including
+// validating the result. Without the transaction this is a lost update waiting to happen:
+// two requests both read the old settings and the second write drops the first one's
+// change. In a transaction, EdgeDB's serializable isolation fails one of the two with a
+// serialization error instead, and `transaction` reruns the closure, which reads the
+// settings again. The cost is two round trips per attempt and retries under contention.
+//
+// In the database (patch_in_database and set_nested): one update computes the new value
+// from the current one with the json operators and functions, `++` for a shallow merge of
+// two objects and json_get/json_object_pack for one level down. The read and the write
+// are one statement, so there's nothing for another request to slip in between, in one
+// round trip. The merge is limited to what EdgeQL can express: `++` replaces nested
+// objects whole and stores a null rather than removing the key.
+
+// Applies `patch` to `target` as a json merge patch (RFC 7396)
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let Value::Object(target) = target else {
+        unreachable!()
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            target.remove(key);
+        } else {
+            merge_patch(target.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+// Returns the new settings, or None if there's no Account with that id
+pub async fn patch_in_transaction(
+    client: &Client,
+    id: Uuid,
+    patch: &Value,
+) -> Result<Option<Value>, anyhow::Error> {
+    let settings = client
+        .transaction(|mut tx| async move {
+            let current: Option<Json> = tx
+                .query_single(
+                    "select (select Account filter .id = <uuid>$0).settings",
+                    &(id,),
+                )
+                .await?;
+            let mut settings = match current {
+                Some(json) => serde_json::from_str(&json).map_err(UserError::with_source)?,
+                None => Value::Object(Map::new()),
+            };
+            merge_patch(&mut settings, patch);
+            let updated: Option<Uuid> = tx
+                .query_single(
+                    "select (update Account filter .id = <uuid>$0 set {
+                        settings := <json>$1
+                    }).id",
+                    &(id, Json::new_unchecked(settings.to_string())),
+                )
+                .await?;
+            Ok(updated.map(|_| settings))
+        })
+        .await?;
+    Ok(settings)
+}
+
+// A shallow merge of `patch` (an object) into the settings, in one statement
+pub async fn patch_in_database(
+    client: &Client,
+    id: Uuid,
+    patch: &Value,
+) -> Result<Option<Value>, anyhow::Error> {
+    let settings: Option<Json> = client
+        .query_single(
+            "select (update Account filter .id = <uuid>$0 set {
+                settings := (.settings ?? to_json('{}')) ++ <json>$1
+            }).settings",
+            &(id, Json::new_unchecked(patch.to_string())),
+        )
+        .await?;
+    Ok(settings
+        .map(|json| serde_json::from_str(&json))
+        .transpose()?)
+}
+
+// Sets settings[section][key] to `value`, keeping the rest of the section
+pub async fn set_nested(
+    client: &Client,
+    id: Uuid,
+    section: &str,
+    key: &str,
+    value: &Value,
+) -> Result<Option<Value>, anyhow::Error> {
+    let settings: Option<Json> = client
+        .query_single(
+            "select (update Account filter .id = <uuid>$0 set {
+                settings := (
+                    with
+                        current := .settings ?? to_json('{}'),
+                        section := json_get(current, <str>$1) ?? to_json('{}'),
+                    select current ++ json_object_pack({
+                        (<str>$1, section ++ json_object_pack({(<str>$2, <json>$3)}))
+                    })
+                )
+            }).settings",
+            &(id, section, key, Json::new_unchecked(value.to_string())),
+        )
+        .await?;
+    Ok(settings
+        .map(|json| serde_json::from_str(&json))
+        .transpose()?)
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let account = AccountFactory::new()
+        .username(format!("settings{}", random_name()))
+        .create(client)
+        .await?;
+    let id = account.id;
+
+    let res = async {
+        let patch = json!({ "theme": "dark", "notifications": { "email": true, "sms": false } });
+        let settings = patch_in_transaction(client, id, &patch).await?;
+        display_result("patch_in_transaction", &settings);
+        assert_eq!(settings, Some(patch));
+
+        // Nested objects merge, and null removes a key
+        let patch = json!({ "notifications": { "sms": null, "push": true }, "language": "fr" });
+        let settings = patch_in_transaction(client, id, &patch).await?;
+        display_result("patch_in_transaction", &settings);
+        let expected = json!({
+            "theme": "dark",
+            "language": "fr",
+            "notifications": { "email": true, "push": true },
+        });
+        assert_eq!(settings.as_ref(), Some(&expected));
+
+        // Two patches at once: the transactions conflict and one is retried, so neither
+        // change is lost
+        let (a, b) = tokio::join!(
+            patch_in_transaction(client, id, &json!({ "a": 1 })),
+            patch_in_transaction(client, id, &json!({ "b": 2 })),
+        );
+        a?;
+        b?;
+        let current: Option<Json> = client
+            .query_single(
+                "select (select Account filter .id = <uuid>$0).settings",
+                &(id,),
+            )
+            .await?;
+        let current: Value = serde_json::from_str(&current.expect("settings are set"))?;
+        assert_eq!((&current["a"], &current["b"]), (&json!(1), &json!(2)));
+
+        // `++` is shallow: the whole notifications object is replaced, and the null stays
+        let patch = json!({ "notifications": { "email": false }, "language": null });
+        let settings = patch_in_database(client, id, &patch)
+            .await?
+            .expect("the Account exists");
+        display_result("patch_in_database", &settings);
+        assert_eq!(settings["notifications"], json!({ "email": false }));
+        assert_eq!(settings.get("language"), Some(&Value::Null));
+        assert_eq!(settings["theme"], "dark");
+
+        // One key a level down, keeping its neighbours
+        let settings = set_nested(client, id, "notifications", "push", &json!(true))
+            .await?
+            .expect("the Account exists");
+        display_result("set_nested", &settings);
+        assert_eq!(
+            settings["notifications"],
+            json!({ "email": false, "push": true })
+        );
+
+        // Nothing to update
+        let missing = Uuid::from_u128(0);
+        assert_eq!(
+            patch_in_transaction(client, missing, &json!({})).await?,
+            None
+        );
+        assert_eq!(patch_in_database(client, missing, &json!({})).await?, None);
+        Ok(())
+    }
+    .await;
+
+    client
+        .execute("delete Account filter .id = <uuid>$0", &(id,))
+        .await?;
+    res
+}
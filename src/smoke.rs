@@ -0,0 +1,108 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{ensure, Context};
+use edgedb_tokio::{Builder, Client};
+use uuid::Uuid;
+
+use crate::{random_name, rollback::in_rollback, QueryableAccount};
+
+// A quick end-to-end check that the database is reachable and usable, for CI or as a
+// readiness probe: `cargo run -- smoke` exits with 0 when everything works, and otherwise
+// prints which step failed and why and exits with 1.
+//
+// The steps:
+// 1. connect, giving up after `timeout` instead of the default 30 seconds
+// 2. a scalar query
+// 3. an insert, inside a transaction that is rolled back so the probe leaves nothing behind
+// 4. decoding an object into a Queryable struct, which catches a client and server that
+//    disagree about the protocol
+//
+// None of the steps depend on existing data.
+
+async fn connect(timeout: Duration) -> Result<Client, anyhow::Error> {
+    let config = Builder::new()
+        .wait_until_available(timeout)
+        .connect_timeout(timeout)
+        .max_concurrency(1)
+        .build_env()
+        .await?;
+    let client = Client::new(&config);
+    client.ensure_connected().await?;
+    Ok(client)
+}
+
+async fn scalar(client: &Client) -> Result<(), anyhow::Error> {
+    let res: i64 = client.query_required_single("select 1 + 1", &()).await?;
+    ensure!(res == 2, "expected 2, got {res}");
+    Ok(())
+}
+
+async fn rolled_back_insert(client: &Client) -> Result<(), anyhow::Error> {
+    let username = format!("smoke_{}", random_name());
+    let inserted: i64 = in_rollback(client, |mut tx| {
+        let username = username.clone();
+        async move {
+            tx.execute("insert Account { username := <str>$0 }", &(&username,))
+                .await?;
+            tx.query_required_single(
+                "select count(Account filter .username = <str>$0)",
+                &(&username,),
+            )
+            .await
+        }
+    })
+    .await?;
+    ensure!(
+        inserted == 1,
+        "the insert wasn't visible in its transaction"
+    );
+    let left_behind: i64 = client
+        .query_required_single(
+            "select count(Account filter .username = <str>$0)",
+            &(&username,),
+        )
+        .await?;
+    ensure!(left_behind == 0, "the insert wasn't rolled back");
+    Ok(())
+}
+
+async fn queryable(client: &Client) -> Result<(), anyhow::Error> {
+    let id = Uuid::from_u128(1);
+    let res: QueryableAccount = client
+        .query_required_single("select { username := 'smoke', id := <uuid>$0 }", &(id,))
+        .await?;
+    ensure!(
+        res.username == "smoke" && res.id == id,
+        "decoded the wrong values: {res:?}"
+    );
+    Ok(())
+}
+
+pub async fn run(timeout: Duration) -> Result<(), anyhow::Error> {
+    let start = Instant::now();
+    let client = connect(timeout)
+        .await
+        .context("smoke check failed: connect")?;
+    println!("ok  connect ({:.0?})", start.elapsed());
+
+    let step = Instant::now();
+    scalar(&client)
+        .await
+        .context("smoke check failed: scalar query")?;
+    println!("ok  scalar query ({:.0?})", step.elapsed());
+
+    let step = Instant::now();
+    rolled_back_insert(&client)
+        .await
+        .context("smoke check failed: rolled back insert")?;
+    println!("ok  rolled back insert ({:.0?})", step.elapsed());
+
+    let step = Instant::now();
+    queryable(&client)
+        .await
+        .context("smoke check failed: Queryable decode")?;
+    println!("ok  Queryable decode ({:.0?})", step.elapsed());
+
+    println!("All smoke checks passed in {:.0?}", start.elapsed());
+    Ok(())
+}
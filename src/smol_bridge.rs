@@ -0,0 +1,189 @@
+use std::thread::{self, JoinHandle};
+
+use anyhow::anyhow;
+use edgedb_protocol::{query_arg::QueryArgs, QueryResult};
+use edgedb_tokio::Client;
+use futures::{
+    future::{self, BoxFuture},
+    Future, FutureExt,
+};
+use tokio::{
+    runtime,
+    sync::{mpsc, oneshot},
+};
+use uuid::Uuid;
+
+use crate::{connection::ConnectOpts, random_name};
+
+// The client from an application on another executor, here smol (async-std works the
+// same). edgedb-tokio's connections are tokio sockets and timers, which only work while a
+// tokio runtime drives them, and a smol task isn't running on one.
+//
+// TokioBridge keeps a tokio runtime on a thread of its own, with the Client living there.
+// Requests cross over as closures on an mpsc channel: the tokio thread spawns each one as
+// a task with a clone of the Client, and the result comes back on a oneshot channel. The
+// channels from tokio::sync don't need a tokio runtime to be awaited on, so the smol side
+// just awaits the reply, without blocking its executor, and any number of requests run
+// at once on the tokio side.
+//
+// * Arguments and results cross threads, so they have to be Send and 'static: the query
+//   text and arguments are moved into the request rather than borrowed.
+// * Dropping the bridge closes the channel, which stops the tokio thread. Requests still
+//   in flight are cancelled, and their callers get an error.
+// * The alternative, running everything on tokio, is simpler when it's an option. The
+//   bridge is for when the rest of the application is already built on another executor.
+
+type Request = Box<dyn FnOnce(Client) -> BoxFuture<'static, ()> + Send>;
+
+pub struct TokioBridge {
+    requests: Option<mpsc::UnboundedSender<Request>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl TokioBridge {
+    // Starts the tokio thread and connects on it
+    pub fn start(connection: ConnectOpts) -> Result<Self, anyhow::Error> {
+        let runtime = runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let (requests, mut receiver) = mpsc::unbounded_channel::<Request>();
+        let (ready, started) = std::sync::mpsc::channel();
+        let thread = thread::spawn(move || {
+            runtime.block_on(async move {
+                let client = match connection.client().await {
+                    Ok(client) => client,
+                    Err(e) => {
+                        let _ = ready.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = ready.send(Ok(()));
+                while let Some(request) = receiver.recv().await {
+                    tokio::spawn(request(client.clone()));
+                }
+            })
+        });
+        started.recv()??;
+        Ok(TokioBridge {
+            requests: Some(requests),
+            thread: Some(thread),
+        })
+    }
+
+    // Runs `f` with the Client on the tokio thread, and waits for its result on whatever
+    // executor the caller is on
+    pub async fn call<T, F, Fut>(&self, f: F) -> Result<T, anyhow::Error>
+    where
+        F: FnOnce(Client) -> Fut + Send + 'static,
+        Fut: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let (reply, response) = oneshot::channel();
+        let request: Request = Box::new(move |client| {
+            async move {
+                // The caller may have stopped waiting
+                let _ = reply.send(f(client).await);
+            }
+            .boxed()
+        });
+        let closed = || anyhow!("the tokio thread has stopped");
+        self.requests
+            .as_ref()
+            .expect("only taken on drop")
+            .send(request)
+            .map_err(|_| closed())?;
+        response.await.map_err(|_| closed())
+    }
+
+    pub async fn query<R, A>(&self, query: &str, arguments: A) -> Result<Vec<R>, anyhow::Error>
+    where
+        R: QueryResult + Send + 'static,
+        A: QueryArgs + Send + 'static,
+    {
+        let query = query.to_string();
+        let rows = self
+            .call(move |client| async move { client.query(&query, &arguments).await })
+            .await??;
+        Ok(rows)
+    }
+
+    pub async fn query_required_single<R, A>(
+        &self,
+        query: &str,
+        arguments: A,
+    ) -> Result<R, anyhow::Error>
+    where
+        R: QueryResult + Send + 'static,
+        A: QueryArgs + Send + 'static,
+    {
+        let query = query.to_string();
+        let row = self
+            .call(
+                move |client| async move { client.query_required_single(&query, &arguments).await },
+            )
+            .await??;
+        Ok(row)
+    }
+
+    pub async fn execute<A>(&self, query: &str, arguments: A) -> Result<(), anyhow::Error>
+    where
+        A: QueryArgs + Send + 'static,
+    {
+        let query = query.to_string();
+        self.call(move |client| async move { client.execute(&query, &arguments).await })
+            .await??;
+        Ok(())
+    }
+}
+
+impl Drop for TokioBridge {
+    fn drop(&mut self) {
+        // Closing the channel ends the loop on the tokio thread
+        drop(self.requests.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// Runs a smol executor on the calling thread, so call it from a thread that isn't running
+// async code
+pub fn run(connection: &ConnectOpts) -> Result<(), anyhow::Error> {
+    let bridge = TokioBridge::start(connection.clone())?;
+    smol::block_on(async {
+        let greeting: String = bridge
+            .query_required_single("select 'Hello from ' ++ <str>$0", ("smol".to_string(),))
+            .await?;
+        println!("{greeting}\n");
+
+        // Several requests at once, all awaited on smol while tokio runs them
+        let prefix = format!("smol{}", random_name());
+        let inserts = (0..5_i64).map(|n| {
+            bridge.query_required_single::<Uuid, _>(
+                "select (insert Account { username := <str>$0 ++ '_' ++ <str><int64>$1 }).id",
+                (prefix.clone(), n),
+            )
+        });
+        let res = async {
+            let ids = future::try_join_all(inserts).await?;
+            let count: i64 = bridge
+                .query_required_single(
+                    "select count(Account filter .username like <str>$0 ++ '%')",
+                    (prefix.clone(),),
+                )
+                .await?;
+            println!("{} inserts from smol, {count} Accounts", ids.len());
+            assert_eq!(count, 5);
+            Ok(())
+        }
+        .await;
+
+        bridge
+            .execute(
+                "delete Account filter .username like <str>$0 ++ '%'",
+                (prefix,),
+            )
+            .await?;
+        res
+    })
+}
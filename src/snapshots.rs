@@ -0,0 +1,69 @@
+use std::{sync::Arc, time::Duration};
+
+use edgedb_derive::Queryable;
+use edgedb_protocol::model::Datetime;
+use edgedb_tokio::Client;
+use tokio::{sync::Mutex, time::MissedTickBehavior};
+
+use crate::telemetry;
+
+// Cron-like maintenance inside a Rust service: every `every` a snapshot of some aggregates
+// is computed and stored as an AccountSnapshot object.
+//
+// Two things keep a slow run from piling up work:
+// * Each run is spawned so the ticker stays on schedule, but it first has to take a lock.
+//   If the previous run still holds it, the tick is skipped rather than queued.
+// * MissedTickBehavior::Skip stops the interval from firing a burst of ticks to catch up
+//   after the runtime was busy.
+
+#[derive(Debug, Queryable)]
+pub struct AccountSnapshot {
+    pub taken_at: Datetime,
+    pub account_count: i64,
+    pub new_accounts: i64,
+}
+
+pub async fn take_snapshot(client: &Client) -> Result<AccountSnapshot, edgedb_tokio::Error> {
+    telemetry::query_required_single(
+        client,
+        "snapshots.take",
+        "with
+            last := (select AccountSnapshot order by .taken_at desc limit 1),
+            since := last.taken_at ?? <datetime>'1970-01-01T00:00:00Z',
+        select (
+            insert AccountSnapshot {
+                account_count := count(Account),
+                new_accounts := count((select Account filter .created_at > since)),
+            }
+        ) { taken_at, account_count, new_accounts }",
+        &(),
+    )
+    .await
+}
+
+// Takes a snapshot every `every`, stopping after `runs` ticks if given.
+pub async fn run(client: Client, every: Duration, runs: Option<usize>) {
+    let running = Arc::new(Mutex::new(()));
+    let mut interval = tokio::time::interval(every);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    let mut ticks = 0;
+    while runs.is_none_or(|runs| ticks < runs) {
+        interval.tick().await;
+        ticks += 1;
+        let Ok(guard) = running.clone().try_lock_owned() else {
+            println!("Previous snapshot is still running, skipping this one");
+            continue;
+        };
+        let client = client.clone();
+        tokio::spawn(async move {
+            match take_snapshot(&client).await {
+                Ok(snapshot) => println!("Took snapshot: {snapshot:?}"),
+                Err(e) => eprintln!("Snapshot failed: {e}"),
+            }
+            drop(guard);
+        });
+    }
+    // Let the last run finish before returning
+    drop(running.lock().await);
+}
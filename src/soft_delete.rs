@@ -0,0 +1,123 @@
+use std::time::Duration;
+
+use edgedb_tokio::Client;
+use uuid::Uuid;
+
+use crate::{display_result, factory::AccountFactory, policies::admin_client};
+
+// Soft deletes: deleting a Comment sets its deleted_at instead of removing it, so it can
+// be restored, and a purge job removes the ones deleted long enough ago for good.
+//
+// Every query has to leave the deleted Comments out. Writing `filter not exists
+// .deleted_at` in each of them works until one query forgets, so the schema does it
+// instead, two ways (see Comment in dbschema/default.esdl):
+// * The access policy live_comments_only only allows selecting, updating and deleting
+//   Comments without a deleted_at. For ordinary clients deleted Comments don't exist: they
+//   aren't in selects, counts or backlinks, and updating them changes nothing.
+// * Restoring and purging have to see deleted Comments, so they run through
+//   policies::admin_client, like any admin tool. The LiveComment alias gives such code
+//   the live Comments when it needs them, without repeating the filter.
+//
+// Things to keep in mind: exclusive constraints still see deleted rows (a deleted
+// username is still taken), and links to a soft deleted object still point at it, so
+// code reading links with policies off has to check deleted_at itself.
+
+// The id of the Comment, if there was a live one to delete
+pub async fn soft_delete(client: &Client, id: Uuid) -> Result<Option<Uuid>, edgedb_tokio::Error> {
+    client
+        .query_single(
+            "select (
+                update Comment filter .id = <uuid>$0
+                set { deleted_at := datetime_of_statement() }
+            ).id",
+            &(id,),
+        )
+        .await
+}
+
+pub async fn restore(client: &Client, id: Uuid) -> Result<Option<Uuid>, edgedb_tokio::Error> {
+    admin_client(client)
+        .query_single(
+            "select (
+                update Comment filter .id = <uuid>$0 and exists .deleted_at
+                set { deleted_at := {} }
+            ).id",
+            &(id,),
+        )
+        .await
+}
+
+// Removes the Comments deleted more than `older_than` ago, returning how many
+pub async fn purge(client: &Client, older_than: Duration) -> Result<i64, anyhow::Error> {
+    let older_than =
+        edgedb_protocol::model::Duration::from_micros(older_than.as_micros().try_into()?);
+    Ok(admin_client(client)
+        .query_required_single(
+            "select count((
+                delete Comment
+                filter .deleted_at < datetime_of_statement() - <duration>$0
+            ))",
+            &(older_than,),
+        )
+        .await?)
+}
+
+async fn bodies(client: &Client, query: &str, post: Uuid) -> Result<Vec<String>, anyhow::Error> {
+    let bodies: Vec<String> = client.query(query, &(post,)).await?;
+    display_result(query, &bodies);
+    Ok(bodies)
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let author = AccountFactory::new().with_posts(1).create(client).await?;
+    let post = author.posts[0];
+
+    let res = async {
+        let ids: Vec<Uuid> = client
+            .query(
+                "for body in {'first', 'second'} union (
+                    select (insert Comment {
+                        post := (select Post filter .id = <uuid>$0),
+                        body := body,
+                    }).id
+                )",
+                &(post,),
+            )
+            .await?;
+        let first = ids[0];
+        let comments =
+            "select Comment.body filter Comment.post.id = <uuid>$0 order by Comment.body";
+
+        // Gone for ordinary queries, and deleting it again finds nothing
+        assert_eq!(soft_delete(client, first).await?, Some(first));
+        assert_eq!(bodies(client, comments, post).await?, ["second"]);
+        assert_eq!(soft_delete(client, first).await?, None);
+
+        // Still there with policies off, where the alias leaves it out
+        let admin = admin_client(client);
+        assert_eq!(bodies(&admin, comments, post).await?, ["first", "second"]);
+        let live = "select LiveComment.body filter LiveComment.post.id = <uuid>$0";
+        assert_eq!(bodies(&admin, live, post).await?, ["second"]);
+
+        assert_eq!(restore(client, first).await?, Some(first));
+        assert_eq!(bodies(client, comments, post).await?, ["first", "second"]);
+
+        // Purged for good once it's been deleted for long enough
+        soft_delete(client, first).await?;
+        let purged = purge(client, Duration::from_secs(3600)).await?;
+        println!("Purged {purged} Comments deleted over an hour ago\n");
+        assert_eq!(bodies(&admin, comments, post).await?, ["first", "second"]);
+        let purged = purge(client, Duration::ZERO).await?;
+        println!("Purged {purged} Comments\n");
+        assert_eq!(bodies(&admin, comments, post).await?, ["second"]);
+        Ok(())
+    }
+    .await;
+
+    // Deleting the Account deletes its Post and the Post's Comments too, including any
+    // deleted ones if the example failed halfway
+    admin_client(client)
+        .execute("delete Account filter .id = <uuid>$0", &(author.id,))
+        .await?;
+    res
+}
@@ -0,0 +1,166 @@
+use edgedb_derive::Queryable;
+use edgedb_tokio::Client;
+use indicatif::HumanBytes;
+
+// An overview of what's in the database, e.g. after `cargo run -- seed`:
+//
+// type               objects  properties  links  ~size
+// default::Account      1000           6      2  112.35 KiB
+// default::Post         3000           4      1  1.02 MiB
+// total                 4000                       1.13 MiB
+//
+// The object types come from the schema module, and a count query is then generated for
+// each one, as EdgeQL can't select from a type whose name is only known at runtime. The
+// names are backquoted in the generated queries, so types named after keywords still work.
+//
+// * objects counts the objects of exactly that type: `count(Account)` would also count the
+//   objects of types extending Account, and every object would be counted once per
+//   ancestor. Access policies apply as usual, so objects hidden from the client aren't
+//   counted.
+// * properties and links are what the type has, inherited ones included, apart from id
+//   and __type__.
+// * ~size is an estimate of the data volume: the length of each object's properties as
+//   json, added up. It's not the size on disk (there's no EdgeQL for that), and leaves out
+//   links, indexes and storage overhead, but it's in proportion to it and it's enough to
+//   see where the data is. Computed properties are left out, as they're not stored.
+//
+// Abstract types, aliases and the types the server and extensions bring along aren't
+// listed. Every object is read to work out its size, so this is for seeing what the
+// examples created rather than for running against a big production database.
+
+#[derive(Debug, Queryable)]
+struct ObjectType {
+    name: String,
+    // Stored properties, which the size is worked out from
+    properties: Vec<String>,
+    computed: i64,
+    links: i64,
+}
+
+#[derive(Debug, Queryable)]
+struct Counts {
+    objects: i64,
+    json_length: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeStats {
+    pub name: String,
+    pub objects: i64,
+    pub properties: i64,
+    pub links: i64,
+    // Bytes of json, see above
+    pub size: u64,
+}
+
+fn quote_name(name: &str) -> String {
+    name.split("::")
+        .map(|part| format!("`{part}`"))
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+pub async fn collect(client: &Client) -> Result<Vec<TypeStats>, anyhow::Error> {
+    let types: Vec<ObjectType> = client
+        .query(
+            "select schema::ObjectType {
+                name,
+                properties := (
+                    select .properties filter .name != 'id' and not exists .expr
+                ).name,
+                computed := count((select .properties filter exists .expr)),
+                links := count((select .links filter .name != '__type__')),
+            }
+            filter not .builtin
+            and not .abstract
+            and not .from_alias
+            and not .compound_type
+            and not .name like 'ext::%'
+            order by .name",
+            &(),
+        )
+        .await?;
+    let mut stats = Vec::with_capacity(types.len());
+    for object_type in types {
+        let shape = object_type
+            .properties
+            .iter()
+            .map(|property| format!("`{property}`"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        // A shape can't be empty
+        let json_length = if shape.is_empty() {
+            "0".to_string()
+        } else {
+            format!("sum(len(to_str(<json>(objects {{ {shape} }}))))")
+        };
+        let query = format!(
+            "with objects := (select {} filter .__type__.name = <str>$0)
+            select {{
+                objects := count(objects),
+                json_length := {json_length},
+            }}",
+            quote_name(&object_type.name)
+        );
+        let counts: Counts = client
+            .query_required_single(&query, &(&object_type.name,))
+            .await?;
+        stats.push(TypeStats {
+            name: object_type.name,
+            objects: counts.objects,
+            properties: object_type.properties.len() as i64 + object_type.computed,
+            links: object_type.links,
+            size: counts.json_length as u64,
+        });
+    }
+    Ok(stats)
+}
+
+// The stats lined up in columns, with a total at the bottom
+pub fn table(stats: &[TypeStats]) -> String {
+    let width = stats
+        .iter()
+        .map(|type_stats| type_stats.name.len())
+        .chain(["total".len()])
+        .max()
+        .unwrap_or(0);
+    let row = |name: &str, objects: String, properties: String, links: String, size: String| {
+        format!("{name:<width$}  {objects:>8}  {properties:>10}  {links:>5}  {size}\n")
+    };
+    let mut out = row(
+        "type",
+        "objects".to_string(),
+        "properties".to_string(),
+        "links".to_string(),
+        "~size".to_string(),
+    );
+    for type_stats in stats {
+        out.push_str(&row(
+            &type_stats.name,
+            type_stats.objects.to_string(),
+            type_stats.properties.to_string(),
+            type_stats.links.to_string(),
+            HumanBytes(type_stats.size).to_string(),
+        ));
+    }
+    let objects: i64 = stats.iter().map(|type_stats| type_stats.objects).sum();
+    let size: u64 = stats.iter().map(|type_stats| type_stats.size).sum();
+    out.push_str(&row(
+        "total",
+        objects.to_string(),
+        String::new(),
+        String::new(),
+        HumanBytes(size).to_string(),
+    ));
+    out
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let stats = collect(client).await?;
+    anyhow::ensure!(
+        !stats.is_empty(),
+        "no object types, run the migrations first"
+    );
+    print!("{}", table(&stats));
+    Ok(())
+}
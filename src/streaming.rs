@@ -0,0 +1,50 @@
+use edgedb_protocol::{query_arg::QueryArgs, QueryResult};
+use edgedb_tokio::Client;
+use futures::{stream, Stream, TryStreamExt};
+
+// The client has no streaming API yet: every query method decodes the whole result before
+// returning. stream_query fills the gap by fetching a large result one page at a time with
+// keyset pagination and handing out the rows as a Stream, so a consumer that processes rows
+// as they come only ever holds one page in memory.
+//
+// The query must order by a unique key, filter to rows after the key it's given and limit
+// to `page_size` rows, e.g.
+//
+//   select Account { username, id }
+//   filter .username > <str>$0
+//   order by .username
+//   limit <int64>$1
+//
+// `first` are the arguments for the first page and `next` builds the arguments for the
+// following page from the last row of the previous one. Compound keys work the same way:
+// `filter (.created_at, .id) > (<datetime>$0, <uuid>$1)` with both taken from the last row.
+// The stream ends after the first page with fewer than `page_size` rows.
+//
+// Unlike a single query, the pages aren't read in one transaction, so rows inserted or
+// updated while the stream is being read may or may not show up. For exports and batch jobs
+// that's usually fine, as each row is still seen at most once.
+pub fn stream_query<'a, T, A>(
+    client: &'a Client,
+    query: &'a str,
+    page_size: usize,
+    first: A,
+    next: impl Fn(&T) -> A + 'a,
+) -> impl Stream<Item = Result<T, edgedb_tokio::Error>> + 'a
+where
+    T: QueryResult + 'a,
+    A: QueryArgs + 'a,
+{
+    stream::try_unfold(Some((first, next)), move |state| async move {
+        let Some((args, next)) = state else {
+            return Ok(None);
+        };
+        let page: Vec<T> = client.query(query, &args).await?;
+        let state = match page.last() {
+            Some(last) if page.len() >= page_size => Some((next(last), next)),
+            _ => None,
+        };
+        Ok(Some((page, state)))
+    })
+    .map_ok(|page| stream::iter(page.into_iter().map(Ok)))
+    .try_flatten()
+}
@@ -0,0 +1,161 @@
+use std::{pin::pin, time::UNIX_EPOCH};
+
+use edgedb_derive::Queryable;
+use edgedb_protocol::model::{Datetime, Duration, Json};
+use edgedb_tokio::Client;
+use futures::TryStreamExt;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::streaming::stream_query;
+
+// One-way replication of Accounts from a source instance to a target instance with the
+// same schema, the smallest version of an ETL job between two databases:
+//
+// 1. The target remembers how far it has got as a watermark: the updated_at of the last
+//    Account it copied, in a SyncWatermark object named after the job.
+// 2. The source is read for Accounts changed since then, oldest first, with keyset
+//    pagination on (updated_at, id) through stream_query. updated_at is maintained by a
+//    rewrite in the schema, so every insert and update moves an Account to the end.
+// 3. Each page is upserted into the target by username, the natural key both sides
+//    agree on (ids are different in every instance). An Account that exists in the
+//    target already is overwritten: the source always wins.
+// 4. The new watermark is stored in the same transaction as the page, so a sync that's
+//    interrupted carries on from the last page that made it, and never skips one.
+//
+// updated_at is the time the statement started, not the time its transaction committed, so
+// a slow transaction on the source can commit an Account with an updated_at older than the
+// watermark a concurrent sync has already stored. Each sync therefore starts `overlap`
+// before the watermark. Reading some Accounts twice is harmless as the upserts are
+// idempotent.
+//
+// The source and the target have to be different databases: syncing a database into
+// itself updates every Account it reads, which moves it past the end of the sync again.
+//
+// Deletes aren't replicated: an Account deleted in the source has nothing left to find.
+// Replicating them needs soft deletes (a deleted_at that goes through updated_at like any
+// other change) or a table of tombstones.
+
+#[derive(Queryable)]
+struct ChangedAccount {
+    id: Uuid,
+    username: String,
+    some_json: Option<Json>,
+    updated_at: Datetime,
+}
+
+#[derive(Serialize)]
+struct Upsert {
+    username: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    some_json: Option<serde_json::Value>,
+}
+
+pub struct SyncReport {
+    pub synced: usize,
+    pub watermark: Option<Datetime>,
+}
+
+async fn watermark(target: &Client, name: &str) -> Result<Option<Datetime>, edgedb_tokio::Error> {
+    target
+        .query_single(
+            "select SyncWatermark.updated_at filter SyncWatermark.name = <str>$0",
+            &(name,),
+        )
+        .await
+}
+
+async fn upsert_page(
+    target: &Client,
+    name: &str,
+    page: &[ChangedAccount],
+) -> Result<(), anyhow::Error> {
+    let Some(last) = page.last() else {
+        return Ok(());
+    };
+    let upserts = page
+        .iter()
+        .map(|account| {
+            let some_json = match &account.some_json {
+                Some(json) => Some(serde_json::from_str(&json.to_string())?),
+                None => None,
+            };
+            Ok(Upsert {
+                username: account.username.clone(),
+                some_json,
+            })
+        })
+        .collect::<Result<Vec<_>, serde_json::Error>>()?;
+    let upserts = Json::new_unchecked(serde_json::to_string(&upserts)?);
+    let updated_at = last.updated_at;
+    target
+        .transaction(|mut tx| {
+            let upserts = upserts.clone();
+            async move {
+                tx.execute(
+                    "for account in json_array_unpack(<json>$0) union (
+                        insert Account {
+                            username := <str>account['username'],
+                            some_json := json_get(account, 'some_json'),
+                        }
+                        unless conflict on .username
+                        else (
+                            update Account set { some_json := json_get(account, 'some_json') }
+                        )
+                    )",
+                    &(upserts,),
+                )
+                .await?;
+                tx.execute(
+                    "insert SyncWatermark { name := <str>$0, updated_at := <datetime>$1 }
+                    unless conflict on .name
+                    else (update SyncWatermark set { updated_at := <datetime>$1 })",
+                    &(name, updated_at),
+                )
+                .await
+            }
+        })
+        .await?;
+    Ok(())
+}
+
+pub async fn run(
+    source: &Client,
+    target: &Client,
+    name: &str,
+    page_size: usize,
+    overlap: std::time::Duration,
+) -> Result<SyncReport, anyhow::Error> {
+    anyhow::ensure!(page_size > 0, "page size has to be at least 1");
+    let start = match watermark(target, name).await? {
+        Some(watermark) => watermark,
+        None => Datetime::try_from(UNIX_EPOCH)?,
+    };
+    let overlap = Duration::from_micros(overlap.as_micros().try_into()?);
+    let no_overlap = Duration::from_micros(0);
+
+    let limit = page_size as i64;
+    let mut pages = pin!(stream_query(
+        source,
+        "select Account { id, username, some_json, updated_at }
+        filter (.updated_at, .id) > (<datetime>$0 - <duration>$1, <uuid>$2)
+        order by .updated_at then .id
+        limit <int64>$3",
+        page_size,
+        (start, overlap, Uuid::nil(), limit),
+        move |last: &ChangedAccount| (last.updated_at, no_overlap, last.id, limit),
+    )
+    .try_chunks(page_size));
+
+    let mut report = SyncReport {
+        synced: 0,
+        watermark: None,
+    };
+    while let Some(page) = pages.try_next().await.map_err(|e| e.1)? {
+        upsert_page(target, name, &page).await?;
+        report.synced += page.len();
+        report.watermark = page.last().map(|account| account.updated_at);
+        println!("Synced {} Accounts", report.synced);
+    }
+    Ok(report)
+}
@@ -0,0 +1,335 @@
+use std::{collections::BTreeSet, fmt::Debug, future::Future, time::Instant};
+
+use edgedb_protocol::{model::Json, query_arg::QueryArgs, QueryResult};
+use edgedb_tokio::Client;
+use tracing::{field::Empty, Instrument, Span};
+use tracing_subscriber::{
+    filter::filter_fn, fmt::format::FmtSpan, layer::SubscriberExt, util::SubscriberInitExt,
+    EnvFilter, Layer,
+};
+
+use crate::{query_metrics::METRICS, recording};
+
+// Tracing for queries. The functions below mirror the Client methods of the same name but
+// take a short name for the query as well, and run it inside a `query` span recording:
+// * query.name and query.text
+// * query.args: the number of distinct parameters ($0, $name...) in the query text
+// * query.rows: how many objects came back (unknown for json results)
+// * query.latency_ms
+// The examples call these instead of the Client methods wherever a query is worth seeing
+// in traces. The same name is used to label the query's metrics (see query_metrics.rs),
+// and the queries are what --record writes to a file for replaying (see recording.rs).
+
+// Logs to stderr, filtered by RUST_LOG (warnings only by default). Spans are logged when
+// they close so their fields are complete, e.g.
+// RUST_LOG=edgedb_client_example=info cargo run -- account list
+//
+// With `log_queries` every query is also logged as a line of json on stderr, see
+// log_query below.
+//
+// With the otel feature spans are also exported over OTLP (to localhost:4317 unless
+// OTEL_EXPORTER_OTLP_ENDPOINT says otherwise), so they show up in Jaeger, Tempo or any
+// other OpenTelemetry backend. Must be called from inside the tokio runtime.
+pub fn init(log_queries: bool) -> Result<(), anyhow::Error> {
+    let mut filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn"));
+    if log_queries {
+        filter = filter.add_directive(format!("{QUERY_LOG}=info").parse()?);
+    }
+    let registry = tracing_subscriber::registry()
+        .with(filter)
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_span_events(FmtSpan::CLOSE)
+                .with_writer(std::io::stderr)
+                .with_filter(filter_fn(|meta| meta.target() != QUERY_LOG)),
+        )
+        .with(log_queries.then(|| {
+            tracing_subscriber::fmt::layer()
+                .json()
+                .flatten_event(true)
+                .with_current_span(false)
+                .with_span_list(false)
+                .with_writer(std::io::stderr)
+                .with_filter(filter_fn(|meta| meta.target() == QUERY_LOG))
+        }));
+    #[cfg(feature = "otel")]
+    let registry = registry.with(otel::layer()?);
+    registry.init();
+    Ok(())
+}
+
+// Flushes spans that haven't been exported yet. Call before exiting.
+pub fn shutdown() {
+    #[cfg(feature = "otel")]
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use opentelemetry::{
+        sdk::{trace, Resource},
+        KeyValue,
+    };
+    use tracing::Subscriber;
+    use tracing_opentelemetry::OpenTelemetryLayer;
+    use tracing_subscriber::registry::LookupSpan;
+
+    pub fn layer<S>() -> Result<OpenTelemetryLayer<S, trace::Tracer>, anyhow::Error>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        let tracer =
+            opentelemetry_otlp::new_pipeline()
+                .tracing()
+                .with_exporter(opentelemetry_otlp::new_exporter().tonic())
+                .with_trace_config(trace::config().with_resource(Resource::new(vec![
+                    KeyValue::new("service.name", "edgedb-client-example"),
+                ])))
+                .install_batch(opentelemetry::runtime::Tokio)?;
+        Ok(tracing_opentelemetry::layer().with_tracer(tracer))
+    }
+}
+
+pub(crate) fn count_params(query: &str) -> usize {
+    let mut params = BTreeSet::new();
+    let mut rest = query;
+    while let Some(pos) = rest.find('$') {
+        let after = &rest[pos + 1..];
+        let len = after
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(after.len());
+        if len > 0 {
+            params.insert(&after[..len]);
+        }
+        rest = &after[len..];
+    }
+    params.len()
+}
+
+// The target of the query log events, so they can be enabled and formatted separately
+const QUERY_LOG: &str = "query_log";
+// Longest rendering of a query's arguments that is logged before it's cut off
+const MAX_ARGS_LEN: usize = 200;
+
+// Query arguments often hold personal data (usernames, emails, tokens), so they are never
+// logged as is. Every string argument is replaced by its length, which is usually enough to
+// tell an empty or unexpectedly long value from a normal one, while numbers, uuids,
+// booleans and the like are kept since they are what's needed to look a row up again. Long
+// argument lists are truncated.
+//
+// This works on the Debug rendering of the arguments, where strings are the only values in
+// double quotes. Anything that might be sensitive but isn't a string (say a Json argument,
+// whose Debug output is a quoted string too and so is redacted) should be checked before
+// relying on this.
+pub fn sanitize_args(args: &impl Debug) -> String {
+    let rendered = format!("{args:?}");
+    let mut out = String::with_capacity(rendered.len());
+    let mut chars = rendered.chars();
+    while let Some(c) = chars.next() {
+        if c != '"' {
+            out.push(c);
+            continue;
+        }
+        let mut len = 0;
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => {
+                    chars.next();
+                }
+                _ => {}
+            }
+            len += 1;
+        }
+        out.push_str(&format!("<redacted str, {len} chars>"));
+    }
+    if let Some((cut, _)) = out.char_indices().nth(MAX_ARGS_LEN) {
+        out.truncate(cut);
+        out.push_str("...");
+    }
+    out
+}
+
+// One json line per query when --log-queries is given, e.g.
+// {"timestamp":"...","level":"INFO","message":"query","query.name":"account.get_by_username",
+//  "query.args":"(<redacted str, 4 chars>,)","query.latency_ms":1.2,"query.ok":true,...}
+// The arguments are only rendered when the event is enabled.
+fn log_query(
+    name: &str,
+    query: &str,
+    args: &dyn Debug,
+    latency_ms: f64,
+    error: Option<&edgedb_tokio::Error>,
+) {
+    tracing::info!(
+        target: QUERY_LOG,
+        query.name = name,
+        query.text = query,
+        query.args = sanitize_args(&args),
+        query.latency_ms = latency_ms,
+        query.ok = error.is_none(),
+        query.error_kind = error.map(|e| e.kind_name()),
+        "query",
+    );
+}
+
+fn query_span(name: &str, query: &str) -> Span {
+    tracing::info_span!(
+        "query",
+        query.name = name,
+        query.text = query,
+        query.args = count_params(query),
+        query.rows = Empty,
+        query.latency_ms = Empty,
+    )
+}
+
+// Runs `fut` in a span for the query, recording the row count given by `rows` and the latency,
+// and logs it when query logging is on.
+async fn traced<T, F>(
+    name: &str,
+    query: &str,
+    args: &dyn Debug,
+    rows: impl FnOnce(&T) -> Option<usize>,
+    fut: F,
+) -> Result<T, edgedb_tokio::Error>
+where
+    F: Future<Output = Result<T, edgedb_tokio::Error>>,
+{
+    let span = query_span(name, query);
+    let start = Instant::now();
+    let res = fut.instrument(span.clone()).await;
+    let elapsed = start.elapsed();
+    let latency_ms = elapsed.as_secs_f64() * 1000.0;
+    span.record("query.latency_ms", latency_ms);
+    METRICS.observe(name, elapsed, res.as_ref().err());
+    span.in_scope(|| log_query(name, query, args, latency_ms, res.as_ref().err()));
+    match &res {
+        Ok(value) => {
+            if let Some(rows) = rows(value) {
+                span.record("query.rows", rows);
+            }
+        }
+        Err(e) => {
+            span.in_scope(|| tracing::warn!(error = %e, "query failed"));
+        }
+    }
+    res
+}
+
+pub async fn query<R, A>(
+    client: &Client,
+    name: &str,
+    query: &str,
+    args: &A,
+) -> Result<Vec<R>, edgedb_tokio::Error>
+where
+    R: QueryResult,
+    A: QueryArgs + Debug,
+{
+    traced(
+        name,
+        query,
+        args,
+        |res| Some(res.len()),
+        recording::query(client, name, query, args),
+    )
+    .await
+}
+
+pub async fn query_single<R, A>(
+    client: &Client,
+    name: &str,
+    query: &str,
+    args: &A,
+) -> Result<Option<R>, edgedb_tokio::Error>
+where
+    R: QueryResult,
+    A: QueryArgs + Debug,
+{
+    traced(
+        name,
+        query,
+        args,
+        |res: &Option<R>| Some(res.is_some() as usize),
+        recording::query_single(client, name, query, args),
+    )
+    .await
+}
+
+pub async fn query_required_single<R, A>(
+    client: &Client,
+    name: &str,
+    query: &str,
+    args: &A,
+) -> Result<R, edgedb_tokio::Error>
+where
+    R: QueryResult,
+    A: QueryArgs + Debug,
+{
+    traced(
+        name,
+        query,
+        args,
+        |_| Some(1),
+        recording::query_required_single(client, name, query, args),
+    )
+    .await
+}
+
+pub async fn execute<A>(
+    client: &Client,
+    name: &str,
+    query: &str,
+    args: &A,
+) -> Result<(), edgedb_tokio::Error>
+where
+    A: QueryArgs + Debug,
+{
+    traced(
+        name,
+        query,
+        args,
+        |_| None,
+        recording::execute(client, name, query, args),
+    )
+    .await
+}
+
+pub async fn query_json<A>(
+    client: &Client,
+    name: &str,
+    query: &str,
+    args: &A,
+) -> Result<Json, edgedb_tokio::Error>
+where
+    A: QueryArgs + Debug,
+{
+    traced(
+        name,
+        query,
+        args,
+        |_| None,
+        recording::query_json(client, name, query, args),
+    )
+    .await
+}
+
+pub async fn query_single_json<A>(
+    client: &Client,
+    name: &str,
+    query: &str,
+    args: &A,
+) -> Result<Option<Json>, edgedb_tokio::Error>
+where
+    A: QueryArgs + Debug,
+{
+    traced(
+        name,
+        query,
+        args,
+        |res: &Option<Json>| Some(res.is_some() as usize),
+        recording::query_single_json(client, name, query, args),
+    )
+    .await
+}
@@ -0,0 +1,92 @@
+use edgedb_errors::AccessPolicyError;
+use edgedb_protocol::value::Value;
+use edgedb_tokio::Client;
+use uuid::Uuid;
+
+use crate::{display_result, policies::admin_client, random_name};
+
+// Multi-tenancy with a global. TenantNote has an access policy that only lets through
+// notes belonging to `global current_tenant`, so a client derived with that global set
+// can only ever read or write its own tenant's data, no matter what the query says.
+//
+// current_tenant is a required global with a default of the nil uuid, which matches no
+// Tenant. A client that forgets to set it sees nothing instead of everything.
+
+fn tenant_client(client: &Client, tenant: Uuid) -> Client {
+    client.with_globals_fn(|globals| {
+        globals.set("current_tenant", Value::Uuid(tenant));
+    })
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    // Tenants themselves are not protected, so the plain client can create them
+    let query = "select (insert Tenant { name := <str>$0 }).id";
+    let tenant_a: Uuid = client
+        .query_required_single(query, &(format!("Tenant_{}", random_name()),))
+        .await?;
+    let tenant_b: Uuid = client
+        .query_required_single(query, &(format!("Tenant_{}", random_name()),))
+        .await?;
+
+    let res = async {
+        let client_a = tenant_client(client, tenant_a);
+        let client_b = tenant_client(client, tenant_b);
+
+        // The tenant link defaults to the current tenant, so inserts look the same for everybody
+        let insert = "insert TenantNote { text := <str>$0 }";
+        client_a.execute(insert, &("A's first note",)).await?;
+        client_a.execute(insert, &("A's second note",)).await?;
+        client_b.execute(insert, &("B's only note",)).await?;
+
+        // Exactly the same query returns different data for each client
+        let query = "select TenantNote.text order by TenantNote.text";
+        let notes_a: Vec<String> = client_a.query(query, &()).await?;
+        display_result(query, &notes_a);
+        assert_eq!(notes_a, vec!["A's first note", "A's second note"]);
+
+        let notes_b: Vec<String> = client_b.query(query, &()).await?;
+        display_result(query, &notes_b);
+        assert_eq!(notes_b, vec!["B's only note"]);
+
+        // Filtering on the other tenant explicitly doesn't get around the policy
+        let query = "select TenantNote.text filter TenantNote.tenant.id = <uuid>$0";
+        let leaked: Vec<String> = client_a.query(query, &(tenant_b,)).await?;
+        display_result(query, &leaked);
+        assert!(leaked.is_empty());
+
+        // And neither does writing into the other tenant. This time it's an error though:
+        // an insert that fails a policy can't just be filtered out like a select.
+        let query = "insert TenantNote {
+            text := 'sneaky',
+            tenant := (select Tenant filter .id = <uuid>$0)
+        }";
+        let res = client_a.execute(query, &(tenant_b,)).await;
+        display_result(query, &res);
+        assert!(res.unwrap_err().is::<AccessPolicyError>());
+
+        // The original client doesn't have the global set, so it gets the default (nil)
+        // tenant and sees no notes at all
+        let query = "select count(TenantNote)";
+        let count: i64 = client.query_required_single(query, &()).await?;
+        display_result(query, &count);
+        assert_eq!(count, 0);
+        Ok(())
+    }
+    .await;
+
+    // The notes are hidden from the plain client by the policy, so they're deleted through
+    // admin_client before the tenants they link to
+    admin_client(client)
+        .execute(
+            "delete TenantNote filter .tenant.id in {<uuid>$0, <uuid>$1}",
+            &(tenant_a, tenant_b),
+        )
+        .await?;
+    client
+        .execute(
+            "delete Tenant filter .id in {<uuid>$0, <uuid>$1}",
+            &(tenant_a, tenant_b),
+        )
+        .await?;
+    res
+}
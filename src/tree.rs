@@ -0,0 +1,182 @@
+use std::collections::VecDeque;
+
+use edgedb_derive::Queryable;
+use edgedb_tokio::Client;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{display_result, random_name};
+
+// Hierarchical data: Categories that point at their parent (see dbschema/default.esdl),
+// with the children as a computed backlink. Storing only the parent link keeps every
+// change to the tree a single update: moving a subtree is one `set { parent := ... }`.
+//
+// * Inserting: a tree from Rust is inserted top down, a level at a time, as each
+//   Category needs its parent's id.
+// * Reading down: EdgeQL has no recursive queries, but shapes nest, so a shape repeated
+//   `depth` times fetches that many levels in one query. The result is cast to json and
+//   decoded with serde (edgedb(json), see lib.rs) into the recursive CategoryNode, where
+//   the missing children of the last level default to empty.
+// * Reading up: the same trick with the parent link gives the ancestors, up to a maximum
+//   depth, as a chain of Ancestors that's turned into a path like Books / Fiction / Sci-fi.
+// A tree deeper than the depth asked for is cut off, so pick a depth that's more than the
+// tree can have, or store the depth on each Category to know.
+
+#[derive(Debug, Deserialize, Queryable, PartialEq)]
+#[edgedb(json)]
+pub struct CategoryNode {
+    pub name: String,
+    #[serde(default)]
+    pub children: Vec<CategoryNode>,
+}
+
+#[derive(Debug, Deserialize, Queryable)]
+#[edgedb(json)]
+pub struct Ancestor {
+    pub name: String,
+    pub parent: Option<Box<Ancestor>>,
+}
+
+impl Ancestor {
+    // The names from the root down to this Category
+    pub fn path(&self) -> Vec<&str> {
+        let mut path = vec![self.name.as_str()];
+        let mut parent = self.parent.as_deref();
+        while let Some(ancestor) = parent {
+            path.push(&ancestor.name);
+            parent = ancestor.parent.as_deref();
+        }
+        path.reverse();
+        path
+    }
+}
+
+// `{ name, children: { name, children: { name } order by .name } order by .name }` for
+// a depth of 3
+fn nested_shape(link: &str, clauses: &str, depth: usize) -> String {
+    let mut shape = "{ name }".to_string();
+    for _ in 1..depth {
+        shape = format!("{{ name, {link}: {shape} {clauses} }}");
+    }
+    shape
+}
+
+// Inserts `tree` under `parent` (or as a root), returning the id of its top Category
+pub async fn insert_tree(
+    client: &Client,
+    tree: &CategoryNode,
+    parent: Option<Uuid>,
+) -> Result<Uuid, edgedb_tokio::Error> {
+    let insert = "select (insert Category {
+        name := <str>$0,
+        parent := (select Category filter .id = <optional uuid>$1),
+    }).id";
+    let root: Uuid = client
+        .query_required_single(insert, &(&tree.name, parent))
+        .await?;
+    let mut queue = VecDeque::from([(tree, root)]);
+    while let Some((node, id)) = queue.pop_front() {
+        for child in &node.children {
+            let child_id: Uuid = client
+                .query_required_single(insert, &(&child.name, Some(id)))
+                .await?;
+            queue.push_back((child, child_id));
+        }
+    }
+    Ok(root)
+}
+
+pub async fn subtree(
+    client: &Client,
+    id: Uuid,
+    depth: usize,
+) -> Result<Option<CategoryNode>, edgedb_tokio::Error> {
+    let shape = nested_shape("children", "order by .name", depth.max(1));
+    client
+        .query_single(
+            &format!("select <json>(select Category {shape} filter .id = <uuid>$0)"),
+            &(id,),
+        )
+        .await
+}
+
+pub async fn ancestors(
+    client: &Client,
+    id: Uuid,
+    max_depth: usize,
+) -> Result<Option<Ancestor>, edgedb_tokio::Error> {
+    let shape = nested_shape("parent", "", max_depth.max(1));
+    client
+        .query_single(
+            &format!("select <json>(select Category {shape} filter .id = <uuid>$0)"),
+            &(id,),
+        )
+        .await
+}
+
+fn node(name: &str, children: Vec<CategoryNode>) -> CategoryNode {
+    CategoryNode {
+        name: name.to_string(),
+        children,
+    }
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let root_name = format!("Books {}", random_name());
+    let tree = node(
+        &root_name,
+        vec![
+            node("Cooking", vec![]),
+            node(
+                "Fiction",
+                vec![
+                    node("Crime", vec![]),
+                    node("Sci-fi", vec![node("Space opera", vec![])]),
+                ],
+            ),
+        ],
+    );
+    let root = insert_tree(client, &tree, None).await?;
+    println!("Inserted the tree under {root_name}\n");
+
+    let res = async {
+        // Two levels below the root, so Space opera is left out
+        let top = subtree(client, root, 3).await?.expect("the root exists");
+        display_result("subtree(root, 3)", &top);
+        let fiction = top
+            .children
+            .iter()
+            .find(|c| c.name == "Fiction")
+            .expect("Fiction");
+        let sci_fi = fiction
+            .children
+            .iter()
+            .find(|c| c.name == "Sci-fi")
+            .expect("Sci-fi");
+        assert!(sci_fi.children.is_empty());
+        // Deep enough for the whole tree, which comes back as it went in (children are
+        // ordered by name in both)
+        let whole = subtree(client, root, 10).await?.expect("the root exists");
+        assert_eq!(whole, tree);
+
+        let query = "select (
+            select Category filter .name = 'Space opera' and .parent.parent.parent.name = <str>$0
+        ).id";
+        let leaf: Uuid = client.query_required_single(query, &(&root_name,)).await?;
+        let chain = ancestors(client, leaf, 10).await?.expect("the leaf exists");
+        let path = chain.path();
+        println!("Path: {}\n", path.join(" / "));
+        assert_eq!(
+            path,
+            [root_name.as_str(), "Fiction", "Sci-fi", "Space opera"]
+        );
+        Ok(())
+    }
+    .await;
+
+    // Deleting the root deletes its descendants too (on target delete delete source)
+    client
+        .execute("delete Category filter .id = <uuid>$0", &(root,))
+        .await?;
+    res
+}
@@ -0,0 +1,62 @@
+use edgedb_tokio::Client;
+
+// Fuzzy username search with the pg_trgm extension (`using extension pg_trgm` in
+// dbschema/extensions/trgm.esdl). pg_trgm compares strings by the three-character
+// sequences they share, so "jonh" still finds "john_smith" and a typo doesn't mean an
+// empty result.
+//
+// Two ways of scoring, both from 0 (nothing in common) to 1 (the same trigrams):
+// * ext::pg_trgm::similarity compares the whole username with the whole query
+// * ext::pg_trgm::word_similarity compares the query with the best matching part of the
+//   username, so "smith" scores highly against "john_smith". It's the score behind the
+//   word_similar function, and usually the better fit for search-as-you-type.
+//
+// The boolean forms, similar and word_similar, compare against a threshold from the
+// extension's configuration (ext::pg_trgm::Config) that's set per session. Here the
+// threshold is an argument of the search instead, so the score is compared with it
+// directly. Both kinds of search go through every Account: an `index ext::pg_trgm::gin
+// on (.username)` on Account would spare them that, but it would make the whole schema
+// need the extension, so it's left out here.
+//
+// Both scores are float32 in EdgeDB and are cast to float64 in the query, so each result
+// decodes into a (String, f64) tuple, best match first.
+
+#[derive(Debug, Clone, Copy)]
+pub enum Mode {
+    Similarity,
+    WordSimilarity,
+}
+
+pub async fn search(
+    client: &Client,
+    query: &str,
+    mode: Mode,
+    threshold: f64,
+    limit: usize,
+) -> Result<Vec<(String, f64)>, anyhow::Error> {
+    anyhow::ensure!(
+        (0.0..=1.0).contains(&threshold),
+        "the threshold has to be between 0 and 1"
+    );
+    let score = match mode {
+        Mode::Similarity => "ext::pg_trgm::similarity(account.username, q)",
+        Mode::WordSimilarity => "ext::pg_trgm::word_similarity(q, account.username)",
+    };
+    let matches = client
+        .query(
+            &format!(
+                "with
+                    q := <str>$0,
+                    matches := (
+                        for account in Account union (account.username, <float64>{score})
+                    )
+                select matches
+                filter matches.1 >= <float64>$1
+                order by matches.1 desc then matches.0
+                limit <int64>$2"
+            ),
+            &(query, threshold, limit as i64),
+        )
+        .await?;
+    Ok(matches)
+}
@@ -0,0 +1,115 @@
+use edgedb_derive::Queryable;
+use edgedb_errors::ConstraintViolationError;
+use edgedb_tokio::Client;
+use uuid::Uuid;
+
+use crate::{display_result, highlight::format_query, random_name};
+
+// A trigger (EdgeDB 3+) keeping an audit trail: `trigger log_update after update for each`
+// on Account in dbschema/default.esdl inserts an AuditEntry with the username before and
+// after every update, as json. In the trigger `__old__` is the Account as it was and
+// `__new__` as it is now.
+//
+// Nothing on the client side knows about it. The updates below are plain updates, and the
+// audit trail is checked afterwards with an ordinary query. Things to know:
+// * The trigger runs in the same transaction as the update. An update that fails (here on
+//   the exclusive constraint on username) leaves no AuditEntry behind, and neither does
+//   one in a transaction that's rolled back.
+// * `for each` means once per updated object, whether or not any value changed, so a bulk
+//   update of a thousand Accounts inserts a thousand entries in the same statement.
+// * A trigger can't change the objects the statement is changing, only other ones. To
+//   change the object itself use a rewrite (see the defaults example).
+
+#[derive(Debug, Queryable)]
+pub struct AuditEntry {
+    pub username_before: String,
+    pub username_after: String,
+}
+
+pub async fn audit_trail(
+    client: &Client,
+    id: Uuid,
+) -> Result<Vec<AuditEntry>, edgedb_tokio::Error> {
+    client
+        .query(
+            "select AuditEntry {
+                username_before := <str>.old_value['username'],
+                username_after := <str>.new_value['username'],
+            }
+            filter .object_type = 'Account' and .object_id = <uuid>$0
+            order by .at",
+            &(id,),
+        )
+        .await
+}
+
+const RENAME: &str = "update Account filter .id = <uuid>$0 set { username := <str>$1 }";
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let name = random_name();
+    let (first, second, third) = (
+        format!("audit{name}"),
+        format!("audit{name}_2"),
+        format!("audit{name}_3"),
+    );
+    let other = format!("audit{name}_other");
+    let id: Uuid = client
+        .query_required_single(
+            "select (insert Account { username := <str>$0 }).id",
+            &(&first,),
+        )
+        .await?;
+    client
+        .execute("insert Account { username := <str>$0 }", &(&other,))
+        .await?;
+
+    let res = async {
+        // Inserts aren't audited, only updates
+        assert!(audit_trail(client, id).await?.is_empty());
+
+        let rename = format_query(RENAME, "Executed: ".len());
+        client.execute(RENAME, &(id, &second)).await?;
+        println!("Executed: {rename} with {second}\n");
+        client.execute(RENAME, &(id, &third)).await?;
+        println!("Executed: {rename} with {third}\n");
+
+        // Taking the other Account's username breaks the exclusive constraint, so the
+        // update and the trigger's insert are both undone
+        let res = client.execute(RENAME, &(id, &other)).await;
+        display_result(RENAME, &res);
+        assert!(res.is_err_and(|e| e.is::<ConstraintViolationError>()));
+
+        let trail = audit_trail(client, id).await?;
+        display_result("select AuditEntry ...", &trail);
+        let renames: Vec<(&str, &str)> = trail
+            .iter()
+            .map(|entry| {
+                (
+                    entry.username_before.as_str(),
+                    entry.username_after.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            renames,
+            [
+                (first.as_str(), second.as_str()),
+                (second.as_str(), third.as_str())
+            ]
+        );
+        Ok(())
+    }
+    .await;
+
+    // The AuditEntries outlive their Account, so they're deleted too
+    client
+        .execute(
+            "delete Account filter .id = <uuid>$0 or .username = <str>$1",
+            &(id, &other),
+        )
+        .await?;
+    client
+        .execute("delete AuditEntry filter .object_id = <uuid>$0", &(id,))
+        .await?;
+    res
+}
@@ -0,0 +1,190 @@
+use std::fmt::{self, Write};
+
+use edgedb_protocol::value::Value;
+use serde_json::json;
+
+// Two helpers for working with untyped results (Value) without matching on every variant
+// by hand:
+// * pretty: an indented, human readable rendering that uses the field names from the
+//   object's shape, unlike Value's Debug output which prints the shape separately
+// * to_json: a conversion to serde_json::Value, for handing results to code that speaks
+//   json
+//
+// Both are meant to be copied into other projects, so they have to cope with any Value,
+// including ones that no query would ever return: objects whose shape and fields don't
+// line up, deep nesting, invalid json. Values nested deeper than MAX_DEPTH are cut off by
+// pretty and rejected by to_json rather than recursing until the stack runs out. The fuzz
+// target in fuzz/ checks that neither of them ever panics.
+
+pub const MAX_DEPTH: usize = 64;
+
+#[derive(Debug)]
+pub enum ToJsonError {
+    TooDeep,
+    InvalidJson(serde_json::Error),
+}
+
+impl fmt::Display for ToJsonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ToJsonError::TooDeep => write!(f, "value is nested more than {MAX_DEPTH} levels deep"),
+            ToJsonError::InvalidJson(e) => write!(f, "json value doesn't parse: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ToJsonError {}
+
+pub fn pretty(value: &Value) -> String {
+    let mut out = String::new();
+    write_pretty(&mut out, value, 0);
+    out
+}
+
+fn indent(out: &mut String, depth: usize) {
+    out.extend(std::iter::repeat_n("  ", depth));
+}
+
+// Writes a list of (optionally named) items between `open` and `close`, one per line
+fn write_items<'a>(
+    out: &mut String,
+    open: &str,
+    close: &str,
+    items: impl Iterator<Item = (Option<&'a str>, Option<&'a Value>)>,
+    depth: usize,
+) {
+    out.push_str(open);
+    let mut empty = true;
+    for (name, value) in items {
+        empty = false;
+        out.push('\n');
+        indent(out, depth + 1);
+        if let Some(name) = name {
+            let _ = write!(out, "{name}: ");
+        }
+        match value {
+            Some(value) => write_pretty(out, value, depth + 1),
+            None => out.push_str("{}"),
+        }
+        out.push(',');
+    }
+    if !empty {
+        out.push('\n');
+        indent(out, depth);
+    }
+    out.push_str(close);
+}
+
+fn write_pretty(out: &mut String, value: &Value, depth: usize) {
+    if depth >= MAX_DEPTH {
+        out.push_str("...");
+        return;
+    }
+    match value {
+        Value::Nothing => out.push_str("{}"),
+        Value::Str(s) => {
+            let _ = write!(out, "{s:?}");
+        }
+        Value::Uuid(id) => {
+            let _ = write!(out, "<uuid>'{id}'");
+        }
+        Value::Bool(b) => {
+            let _ = write!(out, "{b}");
+        }
+        Value::Int16(n) => {
+            let _ = write!(out, "{n}");
+        }
+        Value::Int32(n) => {
+            let _ = write!(out, "{n}");
+        }
+        Value::Int64(n) => {
+            let _ = write!(out, "{n}");
+        }
+        Value::Float32(n) => {
+            let _ = write!(out, "{n}");
+        }
+        Value::Float64(n) => {
+            let _ = write!(out, "{n}");
+        }
+        Value::Json(json) => {
+            let _ = write!(out, "<json>{:?}", json.to_string());
+        }
+        // Fields without a matching element in the shape (which the server never sends)
+        // are printed without a name instead of being dropped
+        Value::Object { shape, fields } => {
+            let names = shape
+                .elements
+                .iter()
+                .map(|element| (Some(element.name.as_str()), element.flag_implicit))
+                .chain(std::iter::repeat((None, false)));
+            let items = names
+                .zip(fields)
+                .filter(|((_, implicit), _)| !implicit)
+                .map(|((name, _), value)| (name, value.as_ref()));
+            write_items(out, "{", "}", items, depth);
+        }
+        Value::Set(items) => {
+            write_items(out, "{", "}", items.iter().map(|v| (None, Some(v))), depth)
+        }
+        Value::Array(items) => {
+            write_items(out, "[", "]", items.iter().map(|v| (None, Some(v))), depth)
+        }
+        Value::Tuple(items) => {
+            write_items(out, "(", ")", items.iter().map(|v| (None, Some(v))), depth)
+        }
+        // Everything else (dates and times, durations, big numbers, ranges, enums...) as
+        // its Debug output
+        other => {
+            let _ = write!(out, "{other:?}");
+        }
+    }
+}
+
+pub fn to_json(value: &Value) -> Result<serde_json::Value, ToJsonError> {
+    to_json_at(value, 0)
+}
+
+fn to_json_at(value: &Value, depth: usize) -> Result<serde_json::Value, ToJsonError> {
+    if depth >= MAX_DEPTH {
+        return Err(ToJsonError::TooDeep);
+    }
+    let list = |items: &[Value]| -> Result<serde_json::Value, ToJsonError> {
+        items
+            .iter()
+            .map(|item| to_json_at(item, depth + 1))
+            .collect::<Result<Vec<_>, _>>()
+            .map(serde_json::Value::Array)
+    };
+    Ok(match value {
+        Value::Nothing => serde_json::Value::Null,
+        Value::Str(s) => json!(s),
+        Value::Uuid(id) => json!(id.to_string()),
+        Value::Bool(b) => json!(b),
+        Value::Int16(n) => json!(n),
+        Value::Int32(n) => json!(n),
+        Value::Int64(n) => json!(n),
+        // json has no NaN or infinity, serde_json turns them into null
+        Value::Float32(n) => json!(n),
+        Value::Float64(n) => json!(n),
+        Value::Json(json) => {
+            serde_json::from_str(&json.to_string()).map_err(ToJsonError::InvalidJson)?
+        }
+        Value::Object { shape, fields } => {
+            let mut object = serde_json::Map::new();
+            for (element, field) in shape.elements.iter().zip(fields) {
+                if element.flag_implicit {
+                    continue;
+                }
+                let value = match field {
+                    Some(value) => to_json_at(value, depth + 1)?,
+                    None => serde_json::Value::Null,
+                };
+                object.insert(element.name.clone(), value);
+            }
+            serde_json::Value::Object(object)
+        }
+        Value::Set(items) | Value::Array(items) | Value::Tuple(items) => list(items)?,
+        // Like pretty, everything else becomes a string of its Debug output
+        other => json!(format!("{other:?}")),
+    })
+}
@@ -0,0 +1,110 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    extract::{ConnectInfo, Path, Query, State},
+    http::{
+        header::{CONTENT_TYPE, RETRY_AFTER},
+        StatusCode,
+    },
+    response::{IntoResponse, Response},
+    routing::get,
+    Extension, Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+use super::{layer::RequestClient, ApiError, AppState};
+use crate::{
+    accounts::{self, AccountKey},
+    rate_limit::Decision,
+    repository::AccountRepository,
+    telemetry,
+};
+
+#[derive(Deserialize)]
+pub struct ListParams {
+    #[serde(default = "default_limit")]
+    limit: i64,
+}
+
+fn default_limit() -> i64 {
+    20
+}
+
+// The client comes from the request extensions (see EdgeDbLayer), so it already carries
+// whatever globals the request set. EdgeDB renders the json itself, so the handler
+// passes it through without a round trip through Rust structs.
+pub async fn list(
+    Extension(client): Extension<RequestClient>,
+    Query(params): Query<ListParams>,
+) -> Result<impl IntoResponse, ApiError> {
+    let json = telemetry::query_json(
+        &client,
+        "web.accounts.list",
+        "select Account { username, id } order by .created_at desc limit <int64>$0",
+        &(params.limit,),
+    )
+    .await?;
+    Ok(([(CONTENT_TYPE, "application/json")], json.to_string()))
+}
+
+#[derive(Deserialize)]
+pub struct NewAccount {
+    username: String,
+}
+
+// Account creation is throttled per client ip by the RateLimiter in the app state.
+pub async fn create(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Extension(client): Extension<RequestClient>,
+    Json(new_account): Json<NewAccount>,
+) -> Result<Response, ApiError> {
+    let key = format!("create_account:{}", addr.ip());
+    if let Decision::Limited { retry_after_secs } = state.rate_limiter.check(&key).await? {
+        let retry_after = (retry_after_secs.ceil() as u64).to_string();
+        return Ok((
+            StatusCode::TOO_MANY_REQUESTS,
+            [(RETRY_AFTER, retry_after)],
+            "too many accounts created, try again later",
+        )
+            .into_response());
+    }
+    let account = accounts::create(&client, &new_account.username).await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(json!({ "username": account.username, "id": account.id })),
+    )
+        .into_response())
+}
+
+// Routes whose handlers only talk to an AccountRepository instead of a Client, so they can
+// be tested against repository::InMemoryAccounts (see tests/web.rs). They get the
+// repository as their own state, and with_state turns them into a Router that merges into
+// the app's whatever its state is. Note that they use the repository's client rather than
+// the per-request one from EdgeDbLayer, so they don't see the logged in user.
+pub fn routes<S>(repo: Arc<dyn AccountRepository>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/accounts/:key", get(get_account))
+        .with_state(repo)
+}
+
+// Looks an Account up by id or username, like `cargo run -- account get`
+pub async fn get_account(
+    State(repo): State<Arc<dyn AccountRepository>>,
+    Path(key): Path<String>,
+) -> Result<Response, ApiError> {
+    let key: AccountKey = key.parse().unwrap_or_else(|never| match never {});
+    Ok(match repo.get(&key).await? {
+        Some(account) => Json(json!({
+            "username": account.username,
+            "id": account.id,
+            "created_at": account.created_at.to_string(),
+        }))
+        .into_response(),
+        None => (StatusCode::NOT_FOUND, "no such account").into_response(),
+    })
+}
@@ -0,0 +1,147 @@
+use axum::{
+    extract::State,
+    http::{
+        header::{AUTHORIZATION, CONTENT_TYPE},
+        HeaderMap, Request, StatusCode,
+    },
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use edgedb_protocol::value::Value;
+use edgedb_tokio::Client;
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use super::{layer::RequestClient, ApiError, AppState};
+use crate::telemetry;
+
+// Per-request auth context, end to end:
+// 1. A bearer token in the Authorization header is looked up to find the user's id.
+// 2. `client.with_globals_fn` derives a client for this request with `global current_user`
+//    set to that id, and it replaces the client that EdgeDbLayer put in the extensions.
+// 3. Every query the handler makes goes through that client, so the access policies on
+//    Post (see dbschema/default.esdl) know who is asking. The handlers never filter by user
+//    themselves and can't forget to.
+
+// The id of the logged in user, for handlers that need it directly.
+#[derive(Clone, Copy, Debug)]
+pub struct CurrentUser(pub Uuid);
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+pub async fn user_for_token(
+    client: &Client,
+    token: &str,
+) -> Result<Option<Uuid>, edgedb_tokio::Error> {
+    telemetry::query_single(
+        client,
+        "web.auth.user_for_token",
+        "select (select auth::Token filter .token = <str>$0).account.id",
+        &(token,),
+    )
+    .await
+}
+
+// Requests without a token go through anonymously, with current_user left unset.
+// A token that doesn't match anything is rejected.
+pub async fn resolve_user<B>(
+    State(state): State<AppState>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let Some(token) = bearer_token(request.headers()) else {
+        return next.run(request).await;
+    };
+    let user_id = match user_for_token(&state.client, token).await {
+        Ok(Some(user_id)) => user_id,
+        Ok(None) => return (StatusCode::UNAUTHORIZED, "unknown token").into_response(),
+        Err(e) => return ApiError::from(e).into_response(),
+    };
+    let client = state.client.with_globals_fn(|globals| {
+        globals.set("current_user", Value::Uuid(user_id));
+    });
+    request.extensions_mut().insert(RequestClient(client));
+    request.extensions_mut().insert(CurrentUser(user_id));
+    next.run(request).await
+}
+
+#[derive(Deserialize)]
+pub struct TokenRequest {
+    username: String,
+}
+
+// Hands out a token for any existing username. This is a demo, so there's no password:
+// the point is what happens to the token afterwards. (fastrand isn't a cryptographically
+// secure generator either, use something like rand's OsRng for real tokens.)
+pub async fn create_token(
+    State(state): State<AppState>,
+    Json(request): Json<TokenRequest>,
+) -> Result<Response, ApiError> {
+    let token: String = std::iter::repeat_with(fastrand::alphanumeric)
+        .take(32)
+        .collect();
+    // The insert is in a for over the matching Account, so an unknown username inserts
+    // nothing instead of failing on the required account link
+    let created: Option<String> = telemetry::query_single(
+        &state.client,
+        "web.auth.create_token",
+        "select (
+            for account in (select Account filter .username = <str>$1) union (
+                insert auth::Token { token := <str>$0, account := account }
+            )
+        ).token",
+        &(&token, &request.username),
+    )
+    .await?;
+    Ok(match created {
+        Some(token) => Json(json!({ "token": token })).into_response(),
+        None => (StatusCode::NOT_FOUND, "unknown username").into_response(),
+    })
+}
+
+// Returns whoever `global current_user` points at.
+pub async fn me(Extension(client): Extension<RequestClient>) -> Result<Response, ApiError> {
+    let me = telemetry::query_single_json(
+        &client,
+        "web.auth.me",
+        "select Account { username, id } filter .id = global current_user",
+        &(),
+    )
+    .await?;
+    Ok(match me {
+        Some(me) => ([(CONTENT_TYPE, "application/json")], me.to_string()).into_response(),
+        None => (StatusCode::UNAUTHORIZED, "not logged in").into_response(),
+    })
+}
+
+#[derive(Deserialize)]
+pub struct NewPost {
+    title: String,
+    body: String,
+}
+
+// The author isn't sent by the client or set by the handler: it defaults to the current
+// user in the schema. For anonymous requests the default is empty and the insert fails on
+// the required link, and inserting a Post with somebody else as the author would fail the
+// access policy instead.
+pub async fn create_post(
+    Extension(client): Extension<RequestClient>,
+    Json(post): Json<NewPost>,
+) -> Result<Response, ApiError> {
+    let id: Uuid = telemetry::query_required_single(
+        &client,
+        "web.posts.create",
+        "select (insert Post { title := <str>$0, body := <str>$1 }).id",
+        &(&post.title, &post.body),
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(json!({ "id": id }))).into_response())
+}
@@ -0,0 +1,131 @@
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket},
+        State, WebSocketUpgrade,
+    },
+    response::IntoResponse,
+};
+use edgedb_protocol::model::Datetime;
+use edgedb_tokio::Client;
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+use uuid::Uuid;
+
+use super::AppState;
+use crate::{accounts::AccountSummary, telemetry};
+
+// EdgeDB doesn't push changes to clients, but a change feed is easy to build on top of the
+// client: a single task polls for Accounts created since the last one it saw and publishes
+// them to a broadcast channel, and every WebSocket connection subscribes to that channel.
+// However many subscribers there are, the database only sees one polling query.
+
+// What subscribers receive. Datetime doesn't implement Serialize so it's left out.
+#[derive(Debug, Serialize)]
+pub struct NewAccountMessage<'a> {
+    pub username: &'a str,
+    pub id: Uuid,
+}
+
+// Keyset pagination on (created_at, id): several Accounts inserted by the same statement
+// share a created_at, so the id breaks ties and nothing is skipped or sent twice.
+// Note: created_at is the time the inserting statement started, so an Account inserted by a
+// slow transaction that commits after a poll can carry a timestamp behind the cursor.
+// Polling with some overlap and deduplicating on id is the fix if that matters.
+const NEW_ACCOUNTS_QUERY: &str = "select Account { username, id, created_at }
+    filter .created_at > <datetime>$0
+        or (.created_at = <datetime>$0 and .id > <uuid>$1)
+    order by .created_at then .id
+    limit <int64>$2";
+
+const PAGE_SIZE: i64 = 100;
+
+pub async fn poll_new_accounts(
+    client: Client,
+    new_accounts: broadcast::Sender<AccountSummary>,
+    poll_interval: Duration,
+) {
+    // Start from now so only Accounts created from this point on are pushed.
+    let mut cursor: (Datetime, Uuid) = loop {
+        match client
+            .query_required_single("select datetime_of_statement()", &())
+            .await
+        {
+            Ok(now) => break (now, Uuid::nil()),
+            Err(e) => {
+                eprintln!("Couldn't start the Account feed, retrying: {e}");
+                tokio::time::sleep(poll_interval).await;
+            }
+        }
+    };
+
+    let mut interval = tokio::time::interval(poll_interval);
+    let mut more_waiting = false;
+    loop {
+        // A full page means there could be more waiting, so don't wait for the next tick
+        if !more_waiting {
+            interval.tick().await;
+        }
+        let page: Vec<AccountSummary> = match telemetry::query(
+            &client,
+            "feed.new_accounts",
+            NEW_ACCOUNTS_QUERY,
+            &(cursor.0, cursor.1, PAGE_SIZE),
+        )
+        .await
+        {
+            Ok(page) => page,
+            Err(e) => {
+                eprintln!("Polling for new Accounts failed: {e}");
+                more_waiting = false;
+                continue;
+            }
+        };
+        if let Some(last) = page.last() {
+            cursor = (last.created_at, last.id);
+        }
+        more_waiting = page.len() as i64 == PAGE_SIZE;
+        for account in page {
+            // Sending only fails when nobody is subscribed, which is fine
+            let _ = new_accounts.send(account);
+        }
+    }
+}
+
+pub async fn subscribe(ws: WebSocketUpgrade, State(state): State<AppState>) -> impl IntoResponse {
+    let receiver = state.new_accounts.subscribe();
+    ws.on_upgrade(move |socket| push_new_accounts(socket, receiver))
+}
+
+async fn push_new_accounts(
+    mut socket: WebSocket,
+    mut receiver: broadcast::Receiver<AccountSummary>,
+) {
+    loop {
+        let account = match receiver.recv().await {
+            Ok(account) => account,
+            // A subscriber that can't keep up misses some messages instead of holding up the others
+            Err(RecvError::Lagged(skipped)) => {
+                eprintln!("Subscriber lagged behind, skipped {skipped} new accounts");
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+        let message = NewAccountMessage {
+            username: &account.username,
+            id: account.id,
+        };
+        let text = match serde_json::to_string(&message) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("Couldn't serialize {account:?}: {e}");
+                continue;
+            }
+        };
+        // The subscriber has gone away
+        if socket.send(Message::Text(text)).await.is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,120 @@
+use std::{
+    ops::Deref,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use axum::http::{HeaderMap, HeaderName, Request};
+use edgedb_protocol::value::Value;
+use edgedb_tokio::Client;
+use tower::{Layer, Service};
+use uuid::Uuid;
+
+// A tower Layer that puts an EdgeDB client into the extensions of every request, so
+// handlers can pull it out with `Extension<RequestClient>` instead of reaching into
+// application state. Since it's plain tower it works the same with axum, hyper or tonic.
+//
+// The client is also the natural place for per-request session state. Each request can
+// get its own derived client with globals set from request headers (e.g. the id of the
+// logged in user), and access policies in the schema then apply to everything the handler
+// queries. Deriving a client only copies the session state, the connection pool is shared.
+#[derive(Clone)]
+pub struct EdgeDbLayer {
+    client: Client,
+    global_headers: Arc<Vec<(HeaderName, String)>>,
+}
+
+impl EdgeDbLayer {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            global_headers: Arc::new(Vec::new()),
+        }
+    }
+
+    // Sets the uuid global `global` from the header `header` when the request has one.
+    // A missing or malformed header leaves the global unset, so the schema decides what an
+    // anonymous request can see.
+    pub fn global_from_header(mut self, header: HeaderName, global: impl Into<String>) -> Self {
+        Arc::make_mut(&mut self.global_headers).push((header, global.into()));
+        self
+    }
+
+    // The globals from the request's headers, with their values
+    fn globals_for(&self, headers: &HeaderMap) -> Vec<(String, Uuid)> {
+        self.global_headers
+            .iter()
+            .filter_map(|(header, global)| {
+                let value = headers.get(header)?.to_str().ok()?;
+                Some((global.clone(), Uuid::parse_str(value).ok()?))
+            })
+            .collect()
+    }
+
+    fn client_for(&self, globals: &[(String, Uuid)]) -> Client {
+        if globals.is_empty() {
+            return self.client.clone();
+        }
+        self.client.with_globals_fn(|modifier| {
+            for (global, id) in globals {
+                modifier.set(global.as_str(), Value::Uuid(*id));
+            }
+        })
+    }
+}
+
+impl<S> Layer<S> for EdgeDbLayer {
+    type Service = EdgeDbService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        EdgeDbService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct EdgeDbService<S> {
+    inner: S,
+    layer: EdgeDbLayer,
+}
+
+impl<S, B> Service<Request<B>> for EdgeDbService<S>
+where
+    S: Service<Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<B>) -> Self::Future {
+        let globals = self.layer.globals_for(request.headers());
+        let client = self.layer.client_for(&globals);
+        request.extensions_mut().insert(RequestClient(client));
+        request.extensions_mut().insert(RequestGlobals(globals));
+        self.inner.call(request)
+    }
+}
+
+// The client for the current request, extracted in handlers with `Extension<RequestClient>`.
+#[derive(Clone)]
+pub struct RequestClient(pub Client);
+
+impl Deref for RequestClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.0
+    }
+}
+
+// The globals EdgeDbLayer derived RequestClient with, empty when it handed out the shared
+// client itself. Extracted with `Extension<RequestGlobals>`, e.g. to log who a request is
+// for. Middleware further in (like resolve_user) can still derive the client further.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestGlobals(pub Vec<(String, Uuid)>);
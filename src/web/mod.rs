@@ -0,0 +1,112 @@
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use axum::{
+    http::{header::CONTENT_TYPE, HeaderName, StatusCode},
+    middleware,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Router,
+};
+use edgedb_errors::AccessPolicyError;
+use edgedb_tokio::Client;
+use tokio::sync::broadcast;
+use tower_http::trace::{DefaultMakeSpan, TraceLayer};
+use tracing::Level;
+
+use crate::{
+    accounts::AccountSummary, query_metrics::METRICS, rate_limit::RateLimiter,
+    repository::EdgeDbAccounts,
+};
+
+pub mod accounts;
+pub mod auth;
+pub mod feed;
+pub mod layer;
+
+// Shared state handed to every handler. Client is cheap to clone (it's a handle to a
+// connection pool) so there is no need to wrap it in an Arc.
+#[derive(Clone)]
+pub struct AppState {
+    pub client: Client,
+    pub new_accounts: broadcast::Sender<AccountSummary>,
+    pub rate_limiter: RateLimiter,
+}
+
+// Any error returned from a handler becomes a 500 with the error message as the body,
+// except for access policy violations which are the client's fault.
+pub struct ApiError(anyhow::Error);
+
+impl<E: Into<anyhow::Error>> From<E> for ApiError {
+    fn from(error: E) -> Self {
+        ApiError(error.into())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let status = match self.0.downcast_ref::<edgedb_tokio::Error>() {
+            Some(e) if e.is::<AccessPolicyError>() => StatusCode::FORBIDDEN,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        (status, self.0.to_string()).into_response()
+    }
+}
+
+// Metrics for every query this process has made, for Prometheus to scrape
+async fn metrics() -> Result<impl IntoResponse, ApiError> {
+    Ok(([(CONTENT_TYPE, prometheus::TEXT_FORMAT)], METRICS.render()?))
+}
+
+pub async fn serve(
+    client: Client,
+    addr: SocketAddr,
+    poll_interval: Duration,
+) -> Result<(), anyhow::Error> {
+    let (new_accounts, _) = broadcast::channel(256);
+    tokio::spawn(feed::poll_new_accounts(
+        client.clone(),
+        new_accounts.clone(),
+        poll_interval,
+    ));
+
+    let state = AppState {
+        client: client.clone(),
+        new_accounts,
+        // Five new Accounts per ip per minute
+        rate_limiter: RateLimiter::new(client.clone(), 5, Duration::from_secs(60)),
+    };
+    let repo = Arc::new(EdgeDbAccounts::new(client.clone()));
+    // Every request gets a span, and the query spans from the handlers nest inside it.
+    // With the otel feature they're exported as one trace per request.
+    let trace =
+        TraceLayer::new_for_http().make_span_with(DefaultMakeSpan::new().level(Level::INFO));
+    // Layers added later wrap the ones added before, so EdgeDbLayer runs first and
+    // resolve_user can replace its client with one for the logged in user. EdgeDbLayer
+    // sets current_tenant from an X-Tenant-Id header, so such requests only see that
+    // tenant's notes (see the tenancy example).
+    let app = Router::new()
+        .route("/accounts", get(accounts::list).post(accounts::create))
+        .route("/accounts/feed", get(feed::subscribe))
+        .route("/tokens", post(auth::create_token))
+        .route("/me", get(auth::me))
+        .route("/posts", post(auth::create_post))
+        .route("/metrics", get(metrics))
+        .merge(accounts::routes(repo))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::resolve_user,
+        ))
+        .layer(
+            layer::EdgeDbLayer::new(client)
+                .global_from_header(HeaderName::from_static("x-tenant-id"), "current_tenant"),
+        )
+        .layer(trace)
+        .with_state(state);
+
+    println!("Listening on {addr}, new accounts are pushed to ws://{addr}/accounts/feed");
+    axum::Server::bind(&addr)
+        // The rate limiter needs the client's address
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use std::time::SystemTime;
+
+use chrono::{DateTime, Duration, FixedOffset, TimeZone, Utc};
+use edgedb_protocol::model::Datetime;
+use edgedb_tokio::Client;
+
+use crate::display_result;
+
+// chrono's DateTime in and out of queries. The client decodes a `datetime` into its own
+// Datetime, a point in time counted in microseconds since 2000-01-01 UTC with no calendar
+// or time zone of its own, and takes one as an argument. Both sides convert to and from
+// std's SystemTime, so that's the bridge:
+//
+// * Datetime -> DateTime<Utc>: always works, every Datetime is in chrono's range
+// * DateTime<Tz> -> Datetime: can fail, as chrono's range is wider than EdgeDB's (years
+//   1 to 9999), and drops anything below a microsecond
+//
+// The offset of a DateTime<FixedOffset> isn't stored: a `datetime` is an instant, so
+// 14:00+02:00 and 12:00Z are the same value. Keep the zone in a property of its own if it
+// matters, and use cal::local_datetime for wall-clock times that aren't instants at all.
+
+pub fn to_chrono(datetime: Datetime) -> Result<DateTime<Utc>, anyhow::Error> {
+    Ok(SystemTime::try_from(datetime)?.into())
+}
+
+pub fn from_chrono<Tz: TimeZone>(datetime: DateTime<Tz>) -> Result<Datetime, anyhow::Error> {
+    Ok(Datetime::try_from(SystemTime::from(datetime))?)
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let query = "select <datetime>'2024-01-31T12:00:00.123456Z'";
+    let res: Datetime = client.query_required_single(query, &()).await?;
+    let decoded = to_chrono(res)?;
+    display_result(query, &decoded);
+    let expected =
+        Utc.with_ymd_and_hms(2024, 1, 31, 12, 0, 0).unwrap() + Duration::microseconds(123_456);
+    assert_eq!(decoded, expected);
+
+    // An argument with an offset is the same instant in UTC
+    let berlin = FixedOffset::east_opt(2 * 3600).unwrap();
+    let local = berlin.with_ymd_and_hms(2024, 1, 31, 14, 0, 0).unwrap();
+    let query = "select <datetime>$0 = <datetime>'2024-01-31T12:00:00Z'";
+    let res: bool = client
+        .query_required_single(query, &(from_chrono(local)?,))
+        .await?;
+    display_result(query, &res);
+    assert!(res);
+
+    // Arithmetic in the database agrees with chrono's
+    let query = "select <datetime>$0 + <duration>'90 minutes'";
+    let res: Datetime = client
+        .query_required_single(query, &(from_chrono(expected)?,))
+        .await?;
+    let later = to_chrono(res)?;
+    display_result(query, &later);
+    assert_eq!(later, expected + Duration::minutes(90));
+
+    // Years outside of EdgeDB's range don't convert
+    let far_future = Utc.with_ymd_and_hms(10_000, 1, 1, 0, 0, 0).unwrap();
+    let res = from_chrono(far_future);
+    println!("Converting {far_future}: {res:?}\n");
+    assert!(res.is_err());
+
+    Ok(())
+}
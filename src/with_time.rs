@@ -0,0 +1,61 @@
+use std::time::SystemTime;
+
+use edgedb_protocol::model::Datetime;
+use edgedb_tokio::Client;
+use time::{macros::datetime, Duration, OffsetDateTime};
+
+use crate::display_result;
+
+// The time crate's OffsetDateTime in and out of queries, the same way as the chrono
+// example (see src/with_chrono.rs): through std's SystemTime, which both the client's
+// Datetime and OffsetDateTime convert to and from.
+//
+// * Datetime -> OffsetDateTime: always works, the result is in UTC
+// * OffsetDateTime -> Datetime: fails before year 1, where EdgeDB's range starts (time's
+//   own range goes back to -9999), and drops anything below a microsecond
+//
+// OffsetDateTime compares instants, so a value read back in UTC equals the one sent with
+// an offset even though they print differently.
+
+pub fn to_time(datetime: Datetime) -> Result<OffsetDateTime, anyhow::Error> {
+    Ok(SystemTime::try_from(datetime)?.into())
+}
+
+pub fn from_time(datetime: OffsetDateTime) -> Result<Datetime, anyhow::Error> {
+    Ok(Datetime::try_from(SystemTime::from(datetime))?)
+}
+
+pub async fn run(client: &Client) -> Result<(), anyhow::Error> {
+    let query = "select <datetime>'2024-01-31T12:00:00.123456Z'";
+    let res: Datetime = client.query_required_single(query, &()).await?;
+    let decoded = to_time(res)?;
+    display_result(query, &decoded);
+    let expected = datetime!(2024-01-31 12:00:00.123456 UTC);
+    assert_eq!(decoded, expected);
+
+    // An argument with an offset is the same instant in UTC
+    let local = datetime!(2024-01-31 14:00 +2);
+    let query = "select <datetime>$0 = <datetime>'2024-01-31T12:00:00Z'";
+    let res: bool = client
+        .query_required_single(query, &(from_time(local)?,))
+        .await?;
+    display_result(query, &res);
+    assert!(res);
+
+    // Arithmetic in the database agrees with time's
+    let query = "select <datetime>$0 + <duration>'90 minutes'";
+    let res: Datetime = client
+        .query_required_single(query, &(from_time(expected)?,))
+        .await?;
+    let later = to_time(res)?;
+    display_result(query, &later);
+    assert_eq!(later, expected + Duration::minutes(90));
+
+    // Years before EdgeDB's range don't convert
+    let year_zero = datetime!(0000-01-01 0:00 UTC);
+    let res = from_time(year_zero);
+    println!("Converting {year_zero}: {res:?}\n");
+    assert!(res.is_err());
+
+    Ok(())
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use edgedb_client_example::{backup, backup_schedule, connection::ConnectOpts, random_name};
+
+// The parts of dump/restore that don't need the CLI or an instance, so these always run.
+// The backup schedule records its runs in the database, so that test needs an instance
+// (but still no CLI).
+
+#[test]
+fn cli_args_match_the_client_options() {
+    assert!(ConnectOpts::default().cli_args().is_empty());
+    let opts = ConnectOpts {
+        instance: Some("prod".into()),
+        dsn: None,
+        database: Some("restored".into()),
+    };
+    assert_eq!(
+        opts.cli_args(),
+        ["--instance", "prod", "--database", "restored"]
+    );
+}
+
+#[test]
+fn only_dumps_pass_verification() {
+    let dir = std::env::temp_dir();
+    let dump = dir.join(format!("{}.dump", random_name()));
+    let mut contents = b"\xff\xd8\x00\x00\xd8EDGEDB\x00DUMP\x00".to_vec();
+    contents.extend_from_slice(b"rest of the dump");
+    std::fs::write(&dump, &contents).unwrap();
+    let not_a_dump = dir.join(format!("{}.dump", random_name()));
+    std::fs::write(&not_a_dump, "select 1;").unwrap();
+
+    let res = (backup::verify_dump(&dump), backup::verify_dump(&not_a_dump));
+    std::fs::remove_file(&dump).unwrap();
+    std::fs::remove_file(&not_a_dump).unwrap();
+    assert_eq!(res.0.unwrap(), contents.len() as u64);
+    assert!(res.1.is_err());
+}
+
+#[tokio::test]
+async fn failed_backups_are_recorded() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let dir = std::env::temp_dir().join(random_name());
+    let res = backup_schedule::backup_once(
+        &client,
+        "no-such-edgedb-cli",
+        &ConnectOpts::default(),
+        &dir,
+        3,
+    )
+    .await;
+    assert!(res.is_err());
+
+    let error: Option<String> = client
+        .query_required_single(
+            "select (select BackupRun filter .path like <str>$0 ++ '%').error",
+            &(dir.to_str().unwrap(),),
+        )
+        .await
+        .unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert!(error.unwrap().contains("no-such-edgedb-cli"));
+}
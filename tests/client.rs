@@ -0,0 +1,173 @@
+mod common;
+
+use edgedb_client_example::{random_name, Account, QueryableAccount};
+use edgedb_errors::{ConstraintViolationError, QueryError};
+use edgedb_protocol::{model::Datetime, value::Value};
+use uuid::Uuid;
+
+// The basics from the tour: connecting, scalars, objects, json, transactions and errors.
+
+#[tokio::test]
+async fn connects() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    client.ensure_connected().await.unwrap();
+}
+
+#[tokio::test]
+async fn scalars() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let res: String = client
+        .query_required_single("select 'hello'", &())
+        .await
+        .unwrap();
+    assert_eq!(res, "hello");
+
+    let res: i64 = client
+        .query_required_single("select 9 * 2", &())
+        .await
+        .unwrap();
+    assert_eq!(res, 18);
+
+    let res: Vec<i64> = client.query("select {1, 2, 3}", &()).await.unwrap();
+    assert_eq!(res, vec![1, 2, 3]);
+
+    let res: Option<String> = client.query_single("select <str>{}", &()).await.unwrap();
+    assert_eq!(res, None);
+}
+
+#[tokio::test]
+async fn arguments() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let res: (String, i32, bool) = client
+        .query_required_single(
+            "select (<str>$0, <int32>$1, <bool>$2)",
+            &("text", 10i32, true),
+        )
+        .await
+        .unwrap();
+    assert_eq!(res, ("text".to_string(), 10, true));
+
+    let id = Uuid::from_u128(0x6f8e_4b2a_1c3d_4e5f_8a9b_0c1d_2e3f_4a5b);
+    let res: Uuid = client
+        .query_required_single("select <uuid>$0", &(id,))
+        .await
+        .unwrap();
+    assert_eq!(res, id);
+
+    let now: Datetime = client
+        .query_required_single("select datetime_of_statement()", &())
+        .await
+        .unwrap();
+    let res: Datetime = client
+        .query_required_single("select <datetime>$0", &(now,))
+        .await
+        .unwrap();
+    assert_eq!(res, now);
+}
+
+#[tokio::test]
+async fn objects() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let username = random_name();
+    let inserted: QueryableAccount = client
+        .query_required_single(
+            "select (insert Account { username := <str>$0 }) { username, id }",
+            &(&username,),
+        )
+        .await
+        .unwrap();
+    assert_eq!(inserted.username, username);
+
+    // As a Value
+    let res: Value = client
+        .query_required_single(
+            "select Account { username } filter .id = <uuid>$0",
+            &(inserted.id,),
+        )
+        .await
+        .unwrap();
+    let Value::Object { fields, .. } = res else {
+        panic!("expected an object, got {res:?}");
+    };
+    assert!(fields.contains(&Some(Value::Str(username.clone()))));
+
+    // As json deserialized with serde
+    let json = client
+        .query_single_json(
+            "select Account { username, id } filter .id = <uuid>$0",
+            &(inserted.id,),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    let account: Account = serde_json::from_str(&json).unwrap();
+    assert_eq!(account.id, inserted.id);
+
+    // Queryable needs the fields in the struct's order
+    let res: Result<QueryableAccount, _> = client
+        .query_required_single(
+            "select Account { id, username } filter .id = <uuid>$0",
+            &(inserted.id,),
+        )
+        .await;
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn transactions_roll_back_on_error() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let username = random_name();
+    let res = client
+        .transaction(|mut tx| {
+            let username = username.clone();
+            async move {
+                tx.execute("insert Account { username := <str>$0 }", &(&username,))
+                    .await?;
+                // Fails on the exclusive constraint, undoing the insert above
+                tx.execute("insert Account { username := <str>$0 }", &(&username,))
+                    .await?;
+                Ok(())
+            }
+        })
+        .await;
+    assert!(res.unwrap_err().is::<ConstraintViolationError>());
+
+    let count: i64 = client
+        .query_required_single(
+            "select count(Account filter .username = <str>$0)",
+            &(&username,),
+        )
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+}
+
+#[tokio::test]
+async fn errors_have_kinds() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    // A parameter without a cast
+    let res: Result<Vec<String>, _> = client.query("select $0", &("text",)).await;
+    assert!(res.unwrap_err().is::<QueryError>());
+
+    let username = random_name();
+    client
+        .execute("insert Account { username := <str>$0 }", &(&username,))
+        .await
+        .unwrap();
+    let res = client
+        .execute("insert Account { username := <str>$0 }", &(&username,))
+        .await;
+    assert!(res.unwrap_err().is::<ConstraintViolationError>());
+}
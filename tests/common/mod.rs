@@ -0,0 +1,172 @@
+// Each test file only uses some of these helpers
+#![allow(dead_code)]
+
+use std::{future::Future, ops::Deref, path::Path, sync::OnceLock, time::Duration};
+
+use edgedb_client_example::rollback;
+use edgedb_tokio::{Builder, Client, TlsSecurity, Transaction};
+use testcontainers::{clients::Cli, core::WaitFor, Container, GenericImage};
+
+// The integration tests need an instance with the schema migrated. There are two ways to
+// get one:
+//
+// EDGEDB_INTEGRATION=1 cargo test
+//   uses the instance `edgedb project init` set up for `cargo run`. Each test makes its own
+//   data with random names and doesn't depend on what else is in the database, so the
+//   tests can run in parallel and against an instance that's also used for the examples.
+//
+// EDGEDB_TESTCONTAINERS=1 cargo test
+//   starts a throwaway EdgeDB container for every test with testcontainers, applies the
+//   schema from dbschema/ and removes the container when the test ends. Slower (each
+//   container takes a few seconds to boot) but hermetic, and only needs Docker.
+//
+// With neither set the tests return early, so a plain `cargo test` passes on machines
+// without an instance.
+
+// A client for one test. Derefs to Client, and keeps the test's container (if any)
+// running until the test is done with it.
+pub struct TestClient {
+    client: Client,
+    _container: Option<Container<'static, GenericImage>>,
+}
+
+impl Deref for TestClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        &self.client
+    }
+}
+
+pub async fn client() -> Option<TestClient> {
+    if std::env::var_os("EDGEDB_TESTCONTAINERS").is_some() {
+        return Some(ephemeral().await);
+    }
+    if std::env::var_os("EDGEDB_INTEGRATION").is_none() {
+        eprintln!("skipped, set EDGEDB_INTEGRATION=1 or EDGEDB_TESTCONTAINERS=1 to run");
+        return None;
+    }
+    let client = edgedb_tokio::create_client()
+        .await
+        .expect("EDGEDB_INTEGRATION is set but no instance could be found");
+    Some(TestClient {
+        client,
+        _container: None,
+    })
+}
+
+// A second, empty instance for tests that copy data between two of them. Only available
+// with EDGEDB_TESTCONTAINERS, as EDGEDB_INTEGRATION only provides the one instance.
+pub async fn second_client() -> Option<TestClient> {
+    if std::env::var_os("EDGEDB_TESTCONTAINERS").is_none() {
+        eprintln!("skipped, needs a second instance: set EDGEDB_TESTCONTAINERS=1 to run");
+        return None;
+    }
+    Some(ephemeral().await)
+}
+
+// Containers borrow the docker client they were started with, so it lives for the whole
+// test run. Dropping a container still stops and removes it.
+fn docker() -> &'static Cli {
+    static DOCKER: OnceLock<Cli> = OnceLock::new();
+    DOCKER.get_or_init(Cli::default)
+}
+
+async fn ephemeral() -> TestClient {
+    // insecure_dev_mode turns off password authentication and uses a self-signed
+    // certificate, which is what a throwaway test server wants. EdgeDB 5 is the first with
+    // the ai extension that the ai feature adds to the schema.
+    let image = GenericImage::new("edgedb/edgedb", "5")
+        .with_env_var("EDGEDB_SERVER_SECURITY", "insecure_dev_mode")
+        .with_exposed_port(5656)
+        .with_wait_for(WaitFor::message_on_stderr("Serving on"));
+    let container = docker().run(image);
+    let port = container.get_host_port_ipv4(5656);
+
+    // A single connection for the migration, so its statements all run in the same session
+    apply_schema(&container_client(port, 1).await).await;
+    TestClient {
+        client: container_client(port, 10).await,
+        _container: Some(container),
+    }
+}
+
+async fn container_client(port: u16, max_concurrency: usize) -> Client {
+    let config = Builder::new()
+        .dsn(&format!("edgedb://edgedb@localhost:{port}"))
+        .expect("valid dsn")
+        .tls_security(TlsSecurity::Insecure)
+        .wait_until_available(Duration::from_secs(60))
+        .max_concurrency(max_concurrency)
+        .build_env()
+        .await
+        .expect("client config for the container");
+    Client::new(&config)
+}
+
+// The parts of the schema in dbschema/extensions/ that the features of this build need.
+// postgis isn't there, as the container doesn't have the extension package installed (and
+// the postgis example is skipped without it).
+const EXTENSIONS: [(&str, bool); 3] = [
+    ("ai.esdl", cfg!(feature = "ai")),
+    ("auth_ext.esdl", cfg!(feature = "auth")),
+    ("trgm.esdl", cfg!(feature = "trgm")),
+];
+
+// The equivalent of `edgedb migration create` followed by `edgedb migrate`, without the
+// migration files: the server works out the DDL from the difference between the empty
+// schema and the one in dbschema/, plus the files from EXTENSIONS that aren't already in
+// dbschema/.
+async fn apply_schema(client: &Client) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("dbschema");
+    let mut paths: Vec<_> = std::fs::read_dir(&dir)
+        .expect("dbschema directory")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "esdl"))
+        .collect();
+    paths.sort();
+    for (file, enabled) in EXTENSIONS {
+        if enabled && !dir.join(file).exists() {
+            paths.push(dir.join("extensions").join(file));
+        }
+    }
+    let mut sdl = String::new();
+    for path in paths {
+        sdl.push_str(&std::fs::read_to_string(path).unwrap());
+        sdl.push('\n');
+    }
+    for statement in [
+        format!("start migration to {{ {sdl} }}"),
+        "populate migration".to_string(),
+        "commit migration".to_string(),
+    ] {
+        client
+            .execute(&statement, &())
+            .await
+            .unwrap_or_else(|e| panic!("applying the schema failed at `{statement:.40}`: {e}"));
+    }
+}
+
+// Runs a test's queries in a transaction that is always rolled back, so nothing the test
+// writes is ever committed (using rollback::in_rollback from the crate). `body` gets the
+// transaction and should make all its queries through it. Any error fails the test.
+//
+// The limits:
+// * Only queries made through the transaction are isolated. Code that takes a &Client
+//   (like the functions in src/accounts.rs) runs on another connection, where it can't see
+//   the test's uncommitted data and where its own writes are committed as usual.
+// * Code that starts its own transactions can't be tested this way, as EdgeDB has no nested
+//   transactions.
+// * Transactions are serializable, so tests running in parallel that touch the same data
+//   can fail with serialization errors. The client retries those by running `body` again,
+//   which is why it's an FnMut and why it should not have side effects outside the
+//   database.
+pub async fn in_rollback<B, F>(client: &Client, body: B)
+where
+    B: FnMut(Transaction) -> F,
+    F: Future<Output = Result<(), edgedb_tokio::Error>>,
+{
+    rollback::in_rollback(client, body)
+        .await
+        .unwrap_or_else(|e| panic!("test transaction failed: {e:#}"));
+}
@@ -0,0 +1,26 @@
+use edgedb_client_example::constraints::{parse_details, Violation};
+
+// Reading the field and constraint out of a violation's details, without an instance
+
+#[test]
+fn property_constraints_name_the_field() {
+    let details = "violated constraint 'std::max_len_value' on property 'handle' \
+        of object type 'default::Profile'";
+    assert_eq!(
+        parse_details("handles have at most 20 characters", details),
+        Violation {
+            field: Some("handle".to_string()),
+            constraint: Some("std::max_len_value".to_string()),
+            message: "handles have at most 20 characters".to_string(),
+        }
+    );
+}
+
+#[test]
+fn object_constraints_have_no_field() {
+    let details = "violated constraint 'std::expression' on object type 'default::Profile'";
+    let violation = parse_details("team plans need a company", details);
+    assert_eq!(violation.field, None);
+    assert_eq!(violation.constraint.as_deref(), Some("std::expression"));
+    assert_eq!(parse_details("oops", "").field, None);
+}
@@ -0,0 +1,825 @@
+mod common;
+
+use std::time::Duration;
+
+use edgedb_client_example::{
+    accounts::{self, AccountKey, AccountSummary},
+    aliases,
+    audit::{self, Audited},
+    blocking, bulk_delete, cardinality,
+    connection::ConnectOpts,
+    constraints, csv_import, ddl, defaults, describe, embeddings, enum_arrays, etl,
+    export::{self, Format},
+    factory::AccountFactory,
+    fts, functions, globals, groups, ingest, jobs, json_vs_binary,
+    lookup::{self, get_by_id},
+    modules, n_plus_one, optimistic, pagination, param_defaults, passwords, policies, random_name,
+    rate_limit::{Decision, RateLimiter},
+    recording::{self, Entry},
+    required_single, sampling, search, set_ops, settings, soft_delete, stats, sync, tenancy, tree,
+    triggers,
+};
+use edgedb_protocol::{
+    model::{Datetime, Json},
+    value::Value,
+};
+use uuid::Uuid;
+
+// The standalone examples check their own results with assertions, so running them is
+// enough. The modules used by the commands get a test of their main path.
+
+#[tokio::test]
+async fn policies_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    policies::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn tenancy_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    tenancy::run(&client).await.unwrap();
+}
+
+// Not async: it makes a client of its own from the environment, like `cargo run` does, so
+// it only runs against the project's instance
+#[test]
+fn blocking_example() {
+    if std::env::var_os("EDGEDB_INTEGRATION").is_none() {
+        return;
+    }
+    blocking::run(&ConnectOpts::default()).unwrap();
+}
+
+#[tokio::test]
+async fn cardinality_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    cardinality::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn constraints_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    constraints::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn tree_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    tree::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn triggers_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    triggers::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn aliases_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    aliases::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn functions_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    functions::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn modules_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    modules::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn json_vs_binary_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    json_vs_binary::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn lookup_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    lookup::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn get_by_id_returns_none_for_unknown_ids() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let id = Uuid::from_u128(fastrand::u128(..));
+    let account: Option<AccountSummary> = get_by_id(&client, "Account", id).await.unwrap();
+    assert!(account.is_none());
+    let res = get_by_id::<AccountSummary>(&client, "Season", id).await;
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn soft_delete_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    soft_delete::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn optimistic_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    optimistic::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn pagination_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    pagination::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn param_defaults_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    param_defaults::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn required_single_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    required_single::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn sampling_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    sampling::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn search_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    search::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn set_ops_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    set_ops::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn settings_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    settings::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn ddl_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    ddl::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn defaults_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    defaults::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn embeddings_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    embeddings::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn enum_arrays_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    enum_arrays::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn fts_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    fts::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn globals_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    globals::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn groups_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    groups::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn n_plus_one_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    n_plus_one::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn passwords_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    passwords::run(&client).await.unwrap();
+}
+
+#[tokio::test]
+async fn account_lifecycle() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let username = random_name();
+    let created = accounts::create(&client, &username).await.unwrap();
+
+    let by_name = accounts::get(&client, &AccountKey::Username(username.clone()))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(by_name.id, created.id);
+    let by_id = accounts::get(&client, &AccountKey::Id(created.id))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(by_id.username, username);
+
+    assert_eq!(
+        accounts::delete(&client, created.id).await.unwrap(),
+        Some(created.id)
+    );
+    assert_eq!(accounts::delete(&client, created.id).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn factory_creates_related_data() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let account = AccountFactory::new()
+        .with_posts(3)
+        .create(&client)
+        .await
+        .unwrap();
+    assert_eq!(account.posts.len(), 3);
+    let authored: i64 = client
+        .query_required_single(
+            "select count(Post filter .author.id = <uuid>$0)",
+            &(account.id,),
+        )
+        .await
+        .unwrap();
+    assert_eq!(authored, 3);
+
+    let named = AccountFactory::new()
+        .username(format!("named_{}", random_name()))
+        .create(&client)
+        .await
+        .unwrap();
+    assert!(named.username.starts_with("named_"));
+    assert!(named.posts.is_empty());
+}
+
+#[tokio::test]
+async fn jobs_are_claimed_once() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let ids = jobs::enqueue(&client, 2).await.unwrap();
+    assert_eq!(ids.len(), 2);
+    // Other tests (or workers) may have jobs pending too, so only check that two claims
+    // never return the same job
+    let first = jobs::claim(&client, "test").await.unwrap().unwrap();
+    let second = jobs::claim(&client, "test").await.unwrap().unwrap();
+    assert_ne!(first.id, second.id);
+    jobs::fail(&client, first.id, "test").await.unwrap();
+    jobs::fail(&client, second.id, "test").await.unwrap();
+}
+
+#[tokio::test]
+async fn rate_limiter_limits() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let limiter = RateLimiter::new(client.clone(), 2, Duration::from_secs(60));
+    let key = random_name();
+    assert_eq!(
+        limiter.check(&key).await.unwrap(),
+        Decision::Allowed { remaining: 1 }
+    );
+    assert_eq!(
+        limiter.check(&key).await.unwrap(),
+        Decision::Allowed { remaining: 0 }
+    );
+    assert!(matches!(
+        limiter.check(&key).await.unwrap(),
+        Decision::Limited { .. }
+    ));
+}
+
+#[tokio::test]
+async fn csv_import_rejects_invalid_records() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let username = format!("csv{}", random_name());
+    let csv = format!(
+        "username,title,body\n\
+        {username},First,\"Hello, world\"\n\
+        ,No author,Body\n\
+        {username},Second,Again\n\
+        {username},Missing body\n"
+    );
+    let path = std::env::temp_dir().join(format!("{username}.csv"));
+    std::fs::write(&path, csv).unwrap();
+    let report = csv_import::run(&client, &path, 1).await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(report.imported, 2);
+    let lines: Vec<_> = report.rejected.iter().map(|r| r.line).collect();
+    assert_eq!(lines, [3, 5]);
+    let posts: i64 = client
+        .query_required_single(
+            "select count(Post filter .author.username = <str>$0)",
+            &(&username,),
+        )
+        .await
+        .unwrap();
+    assert_eq!(posts, 2);
+}
+
+#[tokio::test]
+async fn export_pages_through_all_objects() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let mut usernames = Vec::new();
+    for _ in 0..3 {
+        let account = AccountFactory::new().create(&client).await.unwrap();
+        usernames.push(account.username);
+    }
+
+    // A page size smaller than the number of Accounts, so the export takes several pages
+    let mut jsonl = Vec::new();
+    let count = export::run(&client, "Account", Format::JsonLines, 2, &mut jsonl)
+        .await
+        .unwrap();
+    let exported: Vec<serde_json::Value> = String::from_utf8(jsonl)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(exported.len(), count);
+    for username in &usernames {
+        assert!(exported.iter().any(|a| a["username"] == username.as_str()));
+    }
+    assert!(exported.iter().all(|a| a.get("password_hash").is_none()));
+
+    let mut csv = Vec::new();
+    export::run(&client, "default::Account", Format::Csv, 2, &mut csv)
+        .await
+        .unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    let header = csv.lines().next().unwrap();
+    assert!(header.split(',').any(|column| column == "username"));
+    assert!(!header.split(',').any(|column| column == "password_hash"));
+    for username in &usernames {
+        assert!(csv.contains(username.as_str()));
+    }
+
+    assert!(export::run(
+        &client,
+        "Account; delete Account",
+        Format::Csv,
+        2,
+        Vec::new()
+    )
+    .await
+    .is_err());
+}
+
+#[cfg(feature = "parquet")]
+#[tokio::test]
+async fn parquet_export_writes_every_object() {
+    use edgedb_client_example::parquet_export;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    let Some(client) = common::client().await else {
+        return;
+    };
+    for _ in 0..3 {
+        AccountFactory::new().create(&client).await.unwrap();
+    }
+    let path = std::env::temp_dir().join(format!("{}.parquet", random_name()));
+    let count = parquet_export::run(&client, "Account", &path, 2)
+        .await
+        .unwrap();
+    let reader = SerializedFileReader::new(std::fs::File::open(&path).unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let metadata = reader.metadata().file_metadata();
+    assert_eq!(metadata.num_rows() as usize, count);
+    let columns: Vec<_> = metadata
+        .schema_descr()
+        .columns()
+        .iter()
+        .map(|column| column.name().to_string())
+        .collect();
+    for expected in ["created_at", "id", "username"] {
+        assert!(
+            columns.iter().any(|c| c == expected),
+            "no {expected} column"
+        );
+    }
+    assert!(!columns.iter().any(|c| c == "password_hash"));
+}
+
+#[cfg(feature = "trgm")]
+#[tokio::test]
+async fn trgm_search_ranks_by_similarity() {
+    use edgedb_client_example::trgm::{self, Mode};
+
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let suffix = random_name();
+    let close = format!("trgmsearch_{suffix}");
+    let far = format!("trgmsearch_{suffix}_with_a_much_longer_tail");
+    for username in [&close, &far] {
+        accounts::create(&client, username).await.unwrap();
+    }
+
+    let results = trgm::search(&client, &close, Mode::Similarity, 0.3, 10)
+        .await
+        .unwrap();
+    let usernames: Vec<_> = results.iter().map(|(username, _)| username).collect();
+    assert_eq!(usernames[..2], [&close, &far]);
+    assert_eq!(results[0].1, 1.0);
+    assert!(results.windows(2).all(|pair| pair[0].1 >= pair[1].1));
+    // Word similarity finds the query inside the longer username just as well
+    let results = trgm::search(&client, &close, Mode::WordSimilarity, 0.9, 10)
+        .await
+        .unwrap();
+    assert!(results.iter().any(|(username, _)| username == &far));
+    assert!(trgm::search(&client, &close, Mode::Similarity, 1.5, 10)
+        .await
+        .is_err());
+}
+
+// Passes without doing anything when the extension isn't enabled
+#[cfg(feature = "postgis")]
+#[tokio::test]
+async fn postgis_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    edgedb_client_example::postgis::run(&client).await.unwrap();
+}
+
+#[cfg(feature = "chrono")]
+#[tokio::test]
+async fn chrono_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    edgedb_client_example::with_chrono::run(&client)
+        .await
+        .unwrap();
+}
+
+#[cfg(feature = "time")]
+#[tokio::test]
+async fn time_example() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    edgedb_client_example::with_time::run(&client)
+        .await
+        .unwrap();
+}
+
+// Not async either, like blocking_example
+#[cfg(feature = "smol")]
+#[test]
+fn smol_example() {
+    if std::env::var_os("EDGEDB_INTEGRATION").is_none() {
+        return;
+    }
+    edgedb_client_example::smol_bridge::run(&ConnectOpts::default()).unwrap();
+}
+
+#[tokio::test]
+async fn sync_copies_changed_accounts() {
+    let Some(source) = common::client().await else {
+        return;
+    };
+    let Some(target) = common::second_client().await else {
+        return;
+    };
+    let account = AccountFactory::new().create(&source).await.unwrap();
+    let name = format!("test_{}", random_name());
+    let report = sync::run(&source, &target, &name, 2, Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert!(report.synced >= 1);
+
+    let key = AccountKey::Username(account.username);
+    assert!(accounts::get(&target, &key).await.unwrap().is_some());
+    let stored: Option<Datetime> = target
+        .query_single(
+            "select (select SyncWatermark filter .name = <str>$0).updated_at",
+            &(&name,),
+        )
+        .await
+        .unwrap();
+    assert_eq!(stored, report.watermark);
+}
+
+#[tokio::test]
+async fn etl_normalizes_and_deduplicates() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let name = random_name().to_lowercase();
+    let csv = format!("username\n  Etl {name} \netl_{name}\n\"ETL, {name}!\"\n  \nAdmin {name}\n");
+    let path = std::env::temp_dir().join(format!("{name}.csv"));
+    std::fs::write(&path, csv).unwrap();
+    let summary = etl::run(&client, etl::Source::Csv(path.clone()), 2, false)
+        .await
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(summary.extracted, 5);
+    assert_eq!(summary.invalid, 2);
+    assert_eq!(summary.duplicates, 2);
+    assert_eq!(summary.inserted, 1);
+    let key = AccountKey::Username(format!("etl_{name}"));
+    assert!(accounts::get(&client, &key).await.unwrap().is_some());
+}
+
+#[tokio::test]
+async fn ingest_batches_json_lines() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let username = format!("ingest{}", random_name());
+    let input = format!(
+        "{{\"username\": \"{username}\", \"title\": \"One\", \"body\": \"First\"}}\n\
+        not json\n\
+        \n\
+        {{\"username\": \"{username}\", \"title\": \"Two\"}}\n\
+        {{\"username\": \"{username}\", \"title\": \"Three\", \"body\": \"Last\"}}\n"
+    );
+    let report = ingest::run(&client, input.as_bytes(), 1, Duration::from_secs(1))
+        .await
+        .unwrap();
+
+    assert_eq!(report.imported, 2);
+    let lines: Vec<_> = report.rejected.iter().map(|r| r.line).collect();
+    assert_eq!(lines, [2, 4]);
+}
+
+#[tokio::test]
+async fn describe_lists_properties() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let text = describe::describe_type(&client, "Account", false)
+        .await
+        .unwrap();
+    assert!(text.contains("username"));
+    assert!(
+        describe::describe_type(&client, "Account; drop type Account", false)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn stats_counts_each_type() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let stats = stats::collect(&client).await.unwrap();
+    let account = stats
+        .iter()
+        .find(|type_stats| type_stats.name == "default::Account")
+        .unwrap();
+    assert!(account.properties > 0);
+    assert!(account.objects == 0 || account.size > 0);
+    assert!(!stats
+        .iter()
+        .any(|type_stats| type_stats.name.starts_with("std::")));
+}
+
+#[tokio::test]
+async fn replays_a_recorded_query() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let recorded = Entry {
+        name: "sum".to_string(),
+        method: "query".to_string(),
+        query: "select {1 + 1, 3}".to_string(),
+        args: "()".to_string(),
+        params: 0,
+        descriptor: None,
+        result: serde_json::json!([2, 3]),
+        error: None,
+        message: None,
+    };
+    let replayed = recording::replay_entry(&client, &recorded).await;
+    assert!(replayed.descriptor.is_some());
+    assert_eq!(recorded.differences(&replayed), Vec::<String>::new());
+
+    let changed = Entry {
+        query: "select {1 + 1, 4}".to_string(),
+        ..recorded.clone()
+    };
+    let replayed = recording::replay_entry(&client, &changed).await;
+    assert_eq!(recorded.differences(&replayed).len(), 1);
+}
+
+#[tokio::test]
+async fn replays_with_the_recorded_method() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let recorded = Entry {
+        name: "nothing".to_string(),
+        method: "query_required_single".to_string(),
+        query: "select <str>{}".to_string(),
+        args: "()".to_string(),
+        params: 0,
+        descriptor: None,
+        result: serde_json::json!([]),
+        error: None,
+        message: None,
+    };
+    let replayed = recording::replay_entry(&client, &recorded).await;
+    assert_eq!(replayed.error.as_deref(), Some("NoDataError"));
+
+    let single = Entry {
+        method: "query_single".to_string(),
+        ..recorded.clone()
+    };
+    let replayed = recording::replay_entry(&client, &single).await;
+    assert_eq!(single.differences(&replayed), Vec::<String>::new());
+}
+
+#[tokio::test]
+async fn audited_writes_are_recorded() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let author = AccountFactory::new().create(&client).await.unwrap();
+    let as_author = client.with_globals_fn(|globals| {
+        globals.set("current_user", Value::Uuid(author.id));
+    });
+    let audited = Audited::new(as_author);
+    let id = audited
+        .insert(
+            "Post",
+            "select (insert Post { title := <str>$0, body := 'Audited' }).id",
+            &("Before",),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    audited
+        .update(
+            "Post",
+            id,
+            "select (update Post filter .id = <uuid>$0 set { title := <str>$1 }).id",
+            &(id, "After"),
+        )
+        .await
+        .unwrap();
+    audited.delete("Post", id).await.unwrap();
+
+    let rows = audit::recent(&client, Some(id), 10).await.unwrap();
+    let actions: Vec<&str> = rows.iter().map(|row| row.action.as_str()).collect();
+    assert_eq!(actions, ["delete", "update", "insert"]);
+    assert!(rows
+        .iter()
+        .all(|row| row.actor.as_deref() == Some(author.username.as_str())));
+    let title = |json: &Option<Json>| {
+        let value: serde_json::Value =
+            serde_json::from_str(&json.as_ref().unwrap().to_string()).unwrap();
+        value["title"].as_str().unwrap().to_string()
+    };
+    assert_eq!(title(&rows[1].old_value), "Before");
+    assert_eq!(title(&rows[1].new_value), "After");
+    assert!(rows[0].new_value.is_none());
+
+    client
+        .execute("delete AuditEntry filter .object_id = <uuid>$0", &(id,))
+        .await
+        .unwrap();
+    client
+        .execute("delete Account filter .id = <uuid>$0", &(author.id,))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn prune_audit_deletes_in_batches() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let object_type = format!("Prune{}", random_name());
+    client
+        .execute(
+            "for n in range_unpack(range(0, 25)) union (
+                insert AuditEntry {
+                    object_type := <str>$0,
+                    object_id := <uuid>'00000000-0000-0000-0000-000000000000',
+                    action := 'delete',
+                    at := datetime_of_statement() - <duration>'48 hours'
+                        - to_duration(seconds := n),
+                }
+            )",
+            &(&object_type,),
+        )
+        .await
+        .unwrap();
+    let day = Duration::from_secs(24 * 60 * 60);
+
+    let report = bulk_delete::prune_audit(&client, day, Some(&object_type), 10, true)
+        .await
+        .unwrap();
+    assert_eq!((report.matched, report.deleted), (25, 0));
+    let report = bulk_delete::prune_audit(&client, 3 * day, Some(&object_type), 10, false)
+        .await
+        .unwrap();
+    assert_eq!(report.matched, 0);
+
+    let report = bulk_delete::prune_audit(&client, day, Some(&object_type), 10, false)
+        .await
+        .unwrap();
+    assert_eq!(
+        report,
+        bulk_delete::PruneReport {
+            matched: 25,
+            deleted: 25,
+            batches: 3
+        }
+    );
+}
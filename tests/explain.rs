@@ -0,0 +1,84 @@
+use edgedb_client_example::explain::render;
+use serde_json::json;
+
+// Drawing the plan tree of the explain command, from made up analyze output in the two
+// layouts it's read from: the server's own, with the EdgeQL each step comes from, and the
+// plain Postgres one.
+
+#[test]
+fn draws_the_servers_plan() {
+    let analysis = json!({
+        "buffers": ["select Account { username, posts: { title } }"],
+        "fine_grained": {
+            "pipeline": [{
+                "plan_type": "Seq Scan",
+                "relation_name": "Account",
+                "actual_total_time": 2.5,
+                "actual_rows": 100,
+                "actual_loops": 1,
+                "contexts": [{"buffer_idx": 0, "start": 7, "end": 45}],
+            }],
+            "subplans": [{
+                "plan_type": "Aggregate",
+                "actual_total_time": 0.02,
+                "actual_rows": 1,
+                "actual_loops": 100,
+                "plans": [{
+                    "plan_type": "Index Scan",
+                    "relation_name": "Post",
+                    "actual_total_time": 0.4,
+                    "actual_rows": 3,
+                    "actual_loops": 100,
+                    "contexts": [{"text": "posts: { title }"}],
+                }],
+            }],
+        },
+    });
+    assert_eq!(
+        render(&analysis, false),
+        "Seq Scan on Account  2.50 ms  100 rows  # Account { username, posts: { title } }
+Aggregate  0.02 ms  1 row x 100 loops
+└─ Index Scan on Post  0.40 ms  3 rows x 100 loops  # posts: { title }
+"
+    );
+}
+
+#[test]
+fn draws_a_postgres_plan_with_colors() {
+    let analysis = json!([{
+        "Plan": {
+            "Node Type": "Hash Join",
+            "Actual Total Time": 10.0,
+            "Actual Rows": 5,
+            "Actual Loops": 1,
+            "Plans": [
+                {"Node Type": "Seq Scan", "Relation Name": "a", "Actual Total Time": 6.0},
+                {"Node Type": "Hash", "Actual Total Time": 1.0, "Plans": [
+                    {"Node Type": "Seq Scan", "Relation Name": "b", "Actual Total Time": 0.5},
+                ]},
+            ],
+        },
+    }]);
+    let tree = render(&analysis, true);
+    // Half of the slowest step's time or more is red, a tenth or more yellow
+    assert!(tree.contains("\x1b[31m10.00 ms\x1b[0m"));
+    assert!(tree.contains("\x1b[31m6.00 ms\x1b[0m"));
+    assert!(tree.contains("\x1b[33m1.00 ms\x1b[0m"));
+    assert!(tree.contains("  0.50 ms"));
+
+    let lines: Vec<&str> = render(&analysis, false).lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "Hash Join  10.00 ms  5 rows",
+            "├─ Seq Scan on a  6.00 ms",
+            "└─ Hash  1.00 ms",
+            "   └─ Seq Scan on b  0.50 ms",
+        ]
+    );
+}
+
+#[test]
+fn nothing_to_draw_without_a_plan() {
+    assert_eq!(render(&json!({"buffers": []}), false), "");
+}
@@ -0,0 +1,83 @@
+use edgedb_client_example::{http::HttpError, Account, BankCustomer, JsonQueryableAccount};
+use serde::Deserialize;
+use uuid::Uuid;
+
+// Decodes json recorded from real server responses (in tests/fixtures) into the example
+// structs. No instance is needed, so these always run: if a struct changes in a way that no
+// longer matches what the server sends, `cargo test` catches it without a database.
+//
+// To refresh a fixture, run the query in the comment above its test in `edgedb` (the REPL
+// prints json with `\set output-format json`) and paste the result over the file.
+
+const ID: &str = "1094b032-d8e7-11ed-acbd-abc1449ffb3b";
+
+fn id() -> Uuid {
+    Uuid::parse_str(ID).unwrap()
+}
+
+// select Account { username, id } filter .username = 'rUQdaH9T'
+// through query_single_json
+#[test]
+fn account() {
+    let account: Account = serde_json::from_str(include_str!("fixtures/account.json")).unwrap();
+    assert_eq!(account.username, "rUQdaH9T");
+    assert_eq!(account.id, id());
+
+    // The same json works for the struct that's also Queryable with edgedb(json)
+    let account: JsonQueryableAccount =
+        serde_json::from_str(include_str!("fixtures/account.json")).unwrap();
+    assert_eq!(account.id, id());
+}
+
+// select Account { username, id } through query_json, which always returns an array
+#[test]
+fn accounts() {
+    let accounts: Vec<Account> =
+        serde_json::from_str(include_str!("fixtures/accounts.json")).unwrap();
+    assert_eq!(accounts.len(), 2);
+    assert_eq!(accounts[0].id, id());
+}
+
+// A shape with more fields than the struct, as when a query gains a property before the
+// struct does. Unknown fields are ignored, so adding to a shape is never a breaking change.
+#[test]
+fn extra_fields_are_ignored() {
+    let account: Account =
+        serde_json::from_str(include_str!("fixtures/account_with_extra_fields.json")).unwrap();
+    assert_eq!(account.username, "rUQdaH9T");
+}
+
+// select BankCustomer { name, bank_balance } through query_single_json
+#[test]
+fn bank_customer() {
+    let customer: BankCustomer =
+        serde_json::from_str(include_str!("fixtures/bank_customer.json")).unwrap();
+    assert_eq!(customer.name, "Customer 8sJ4lLj2");
+    assert_eq!(customer.bank_balance, 2000);
+}
+
+// Responses from the EdgeQL-over-HTTP endpoint, as decoded by src/http.rs
+#[derive(Deserialize)]
+struct HttpResponse<T> {
+    data: Option<Vec<T>>,
+    error: Option<HttpError>,
+}
+
+#[test]
+fn http_success() {
+    let res: HttpResponse<Account> =
+        serde_json::from_str(include_str!("fixtures/http_success.json")).unwrap();
+    assert!(res.error.is_none());
+    assert_eq!(res.data.unwrap()[0].id, id());
+}
+
+// select Account filter .username = $username (without the cast) over http
+#[test]
+fn http_error() {
+    let res: HttpResponse<Account> =
+        serde_json::from_str(include_str!("fixtures/http_error.json")).unwrap();
+    assert!(res.data.is_none());
+    let error = res.error.unwrap();
+    assert_eq!(error.kind, "QueryError");
+    assert_eq!(error.code, 67174656);
+}
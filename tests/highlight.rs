@@ -0,0 +1,63 @@
+use edgedb_client_example::highlight::{highlight, reindent};
+
+// The query highlighter and re-indenter behind display_result, which need no instance.
+// Colors are checked by stripping them again: the text has to come out unchanged.
+
+fn strip_colors(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|&c| c == 'm');
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[test]
+fn colors_tokens_without_changing_the_text() {
+    let query = "with name := <str>$0 # who
+select Account { username, `select` := 'it''s \\'select\\'', n := 1.5 }
+filter .username = name and .type = schema::Function.name limit 10";
+    let highlighted = highlight(query);
+    assert_eq!(strip_colors(&highlighted), query);
+
+    assert!(highlighted.contains("\x1b[1;35mwith\x1b[0m"));
+    assert!(highlighted.contains("\x1b[1;35mselect\x1b[0m Account"));
+    assert!(highlighted.contains("\x1b[33m$0\x1b[0m"));
+    assert!(highlighted.contains("\x1b[2m# who\x1b[0m"));
+    assert!(highlighted.contains("\x1b[36m1.5\x1b[0m"));
+    assert!(highlighted.contains("\x1b[32m'it'\x1b[0m"));
+    // Names that happen to be keywords
+    assert!(highlighted.contains("`select`"));
+    assert!(highlighted.contains(".type"));
+    assert!(highlighted.contains("::Function"));
+    assert!(!highlighted.contains("\x1b[1;35mtype"));
+    assert!(!highlighted.contains("\x1b[1;35mfunction"));
+}
+
+#[test]
+fn unterminated_tokens_run_to_the_end() {
+    for query in ["select 'oops", "select `oops", "select 1 # oops"] {
+        assert_eq!(strip_colors(&highlight(query)), query);
+    }
+}
+
+#[test]
+fn lines_up_continuation_lines() {
+    let query = "select Account {
+                username,
+            }
+            filter .id = <uuid>$0
+        ";
+    assert_eq!(
+        reindent(query, 9),
+        "select Account {
+             username,
+         }
+         filter .id = <uuid>$0"
+    );
+    assert_eq!(reindent("select 1", 9), "select 1");
+}
@@ -0,0 +1,71 @@
+use std::{fs, path::PathBuf};
+
+use edgedb_client_example::{
+    instances::{project_instance, read_instances, LocalInstance},
+    random_name,
+};
+
+// Reading the CLI's config directory for the instance picker, from a made up one in the
+// temp directory. The picker itself needs a terminal, so it isn't tested.
+
+#[test]
+fn reads_credentials_and_projects() {
+    let dir = std::env::temp_dir().join(random_name());
+    fs::create_dir_all(dir.join("credentials")).unwrap();
+    fs::write(
+        dir.join("credentials/app.json"),
+        r#"{"port": 10700, "user": "edgedb", "password": "secret", "branch": "main"}"#,
+    )
+    .unwrap();
+    fs::write(
+        dir.join("credentials/legacy.json"),
+        r#"{"host": "db.internal", "port": 5656, "database": "edgedb"}"#,
+    )
+    .unwrap();
+    fs::write(dir.join("credentials/broken.json"), "not json").unwrap();
+    fs::write(dir.join("credentials/notes.txt"), "ignored").unwrap();
+    for (project, instance, path) in [
+        ("app-1a2b", "app", "/home/me/app"),
+        ("api-3c4d", "app", "/home/me/api"),
+        ("cloud-5e6f", "org/cloud", "/home/me/cloud"),
+    ] {
+        let project = dir.join("projects").join(project);
+        fs::create_dir_all(&project).unwrap();
+        fs::write(project.join("instance-name"), format!("{instance}\n")).unwrap();
+        fs::write(project.join("project-path"), path).unwrap();
+    }
+
+    let instances = read_instances(&dir).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(
+        instances,
+        [
+            LocalInstance {
+                name: "app".to_string(),
+                address: "localhost:10700".to_string(),
+                database: Some("main".to_string()),
+                projects: vec![PathBuf::from("/home/me/api"), PathBuf::from("/home/me/app")],
+            },
+            LocalInstance {
+                name: "legacy".to_string(),
+                address: "db.internal:5656".to_string(),
+                database: Some("edgedb".to_string()),
+                projects: Vec::new(),
+            },
+        ]
+    );
+    assert_eq!(
+        instances[0].to_string(),
+        "app (localhost:10700, main) /home/me/api /home/me/app"
+    );
+
+    let inside = PathBuf::from("/home/me/app/src");
+    assert_eq!(project_instance(&instances, &inside), Some(0));
+    assert_eq!(project_instance(&instances, &PathBuf::from("/tmp")), None);
+}
+
+#[test]
+fn nothing_configured_is_no_instances() {
+    let dir = std::env::temp_dir().join(random_name());
+    assert!(read_instances(&dir).unwrap().is_empty());
+}
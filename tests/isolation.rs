@@ -0,0 +1,68 @@
+mod common;
+
+use common::in_rollback;
+use edgedb_client_example::random_name;
+
+// Tests whose writes never reach the database, using common::in_rollback.
+
+#[tokio::test]
+async fn writes_are_rolled_back() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    let username = random_name();
+    in_rollback(&client, |mut tx| {
+        let username = username.clone();
+        async move {
+            tx.execute("insert Account { username := <str>$0 }", &(&username,))
+                .await?;
+            // Inside the transaction the Account exists
+            let count: i64 = tx
+                .query_required_single(
+                    "select count(Account filter .username = <str>$0)",
+                    &(&username,),
+                )
+                .await?;
+            assert_eq!(count, 1);
+            Ok(())
+        }
+    })
+    .await;
+
+    // ...and after the rollback it never did
+    let count: i64 = client
+        .query_required_single(
+            "select count(Account filter .username = <str>$0)",
+            &(&username,),
+        )
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+}
+
+#[tokio::test]
+async fn other_connections_dont_see_the_test_data() {
+    let Some(client) = common::client().await else {
+        return;
+    };
+    // The main limit of the pattern: anything going through the Client instead of the
+    // transaction runs on another connection, so it can't see what the test inserted.
+    let username = random_name();
+    let outside = client.clone();
+    in_rollback(&client, |mut tx| {
+        let (username, outside) = (username.clone(), outside.clone());
+        async move {
+            tx.execute("insert Account { username := <str>$0 }", &(&username,))
+                .await?;
+            let seen: Option<String> = outside
+                .query_single(
+                    "select Account.username filter Account.username = <str>$0",
+                    &(&username,),
+                )
+                .await?;
+            assert_eq!(seen, None);
+            Ok(())
+        }
+    })
+    .await;
+}
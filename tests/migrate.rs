@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use edgedb_client_example::migrate::{parse_output, Action, MigrationError, MigrationOutcome};
+
+// Parsing the edgedb CLI's output, which doesn't need the CLI or an instance
+
+#[test]
+fn created_migrations_are_found() {
+    let output = "Created ./dbschema/migrations/00003-m1q4zx.edgeql, id: m1q4zx\n";
+    assert_eq!(
+        parse_output(Action::Create, Some(0), output).unwrap(),
+        MigrationOutcome::Created(PathBuf::from("./dbschema/migrations/00003-m1q4zx.edgeql"))
+    );
+    let res = parse_output(Action::Create, Some(4), "No schema changes detected.\n");
+    assert!(matches!(res, Err(MigrationError::NoChanges)));
+}
+
+#[test]
+fn applied_migrations_are_listed() {
+    let output = "Applied m1abc (00002-m1abc.edgeql)\nApplied m1def (00003-m1def.edgeql)\n";
+    assert_eq!(
+        parse_output(Action::Apply, Some(0), output).unwrap(),
+        MigrationOutcome::Applied(vec!["m1abc".to_string(), "m1def".to_string()])
+    );
+    let output = "Everything is up to date. Revision m1def\n";
+    assert_eq!(
+        parse_output(Action::Apply, Some(0), output).unwrap(),
+        MigrationOutcome::UpToDate
+    );
+}
+
+#[test]
+fn failures_are_told_apart() {
+    let output = "error: InvalidReferenceError: type 'default::Acount' does not exist\n  \
+        ┌─ dbschema/default.esdl:42:20\n";
+    let res = parse_output(Action::Create, Some(1), output);
+    assert!(matches!(res, Err(MigrationError::InvalidSchema(errors)) if errors.contains("Acount")));
+
+    let output = "edgedb error: ClientConnectionFailedError: connection refused\n";
+    let res = parse_output(Action::Apply, Some(1), output);
+    assert!(matches!(
+        res,
+        Err(MigrationError::Failed { code: Some(1), .. })
+    ));
+}
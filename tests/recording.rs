@@ -0,0 +1,77 @@
+use std::fs;
+
+use edgedb_client_example::{
+    random_name,
+    recording::{read, Entry},
+};
+use serde_json::json;
+
+// Reading recordings and comparing replayed queries with them, on made up entries.
+// Replaying against an instance is in tests/examples.rs.
+
+fn entry(result: serde_json::Value) -> Entry {
+    Entry {
+        name: "account.list".to_string(),
+        method: "query".to_string(),
+        query: "select Account { username } order by .username".to_string(),
+        args: "()".to_string(),
+        params: 0,
+        descriptor: Some("Object { .. }".to_string()),
+        result,
+        error: None,
+        message: None,
+    }
+}
+
+#[test]
+fn reads_a_recording_back() {
+    let path = std::env::temp_dir().join(format!("{}.jsonl", random_name()));
+    let recorded = entry(json!([{"username": "alice"}]));
+    let line = serde_json::to_string(&recorded).unwrap();
+    fs::write(&path, format!("{line}\n\n{line}\n")).unwrap();
+    let entries = read(&path);
+    fs::write(&path, "not json\n").unwrap();
+    let broken = read(&path);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(entries.unwrap(), [recorded.clone(), recorded]);
+    assert!(broken.unwrap_err().to_string().contains("line 1"));
+}
+
+#[test]
+fn finds_what_changed() {
+    let recorded = entry(json!([{"username": "alice"}]));
+    assert!(recorded.differences(&recorded.clone()).is_empty());
+
+    let other_rows = entry(json!([{"username": "bob"}]));
+    assert_eq!(
+        recorded.differences(&other_rows),
+        [r#"result: [{"username":"bob"}] was [{"username":"alice"}]"#]
+    );
+
+    let other_descriptor = Entry {
+        descriptor: Some("Object { other }".to_string()),
+        ..recorded.clone()
+    };
+    assert_eq!(recorded.differences(&other_descriptor).len(), 1);
+    // No rows came back, so there was no descriptor to compare
+    let no_rows = Entry {
+        descriptor: None,
+        ..entry(json!([]))
+    };
+    assert_eq!(
+        recorded.differences(&no_rows),
+        [r#"result: [] was [{"username":"alice"}]"#]
+    );
+
+    let failed = Entry {
+        descriptor: None,
+        result: serde_json::Value::Null,
+        error: Some("InvalidReferenceError".to_string()),
+        message: Some("object type 'default::Account' does not exist".to_string()),
+        ..recorded.clone()
+    };
+    assert_eq!(
+        recorded.differences(&failed),
+        ["error: InvalidReferenceError: object type 'default::Account' does not exist was no error"]
+    );
+}
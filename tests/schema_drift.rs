@@ -0,0 +1,67 @@
+use std::path::Path;
+
+use edgedb_client_example::schema_drift::{parse_sdl, read_schema_dir};
+
+// Reading declarations out of SDL, which doesn't need an instance
+
+#[test]
+fn declarations_are_found() {
+    let declared = parse_sdl(
+        "using extension pgvector;
+        module default {
+          # type Commented { }
+          global locale -> str;
+          scalar type Mood extending enum<Happy, Sad>;
+          abstract type Named {
+            required name: str { constraint exclusive; };
+          }
+          type Person extending Named {
+            multi link friends -> Person;
+            property mood -> Mood { default := Mood.Happy; };
+            greeting := 'Hi, ' ++ .name ++ '; {not a block}';
+            link best := (select .friends { name } order by .name limit 1);
+            index on (.name);
+            access policy anyone allow all using (true);
+          }
+          module nested {
+            type Thing;
+          }
+        }",
+    );
+    let items: Vec<&str> = declared.items.iter().map(String::as_str).collect();
+    assert_eq!(
+        items,
+        [
+            "default::Mood",
+            "default::Named",
+            "default::Named.name",
+            "default::Person",
+            "default::Person.best",
+            "default::Person.friends",
+            "default::Person.greeting",
+            "default::Person.mood",
+            "default::locale",
+            "default::nested::Thing",
+        ]
+    );
+    let modules: Vec<&str> = declared.modules.iter().map(String::as_str).collect();
+    assert_eq!(modules, ["default", "default::nested"]);
+}
+
+#[test]
+fn the_repo_schema_is_read() {
+    let declared = read_schema_dir(Path::new("dbschema")).unwrap();
+    for item in [
+        "default::Account",
+        "default::Account.username",
+        "default::Account.watchlist",
+        "default::Post.author",
+        "default::JobStatus",
+        "default::current_tenant",
+        "test::Account.username",
+        "auth::Token.account",
+    ] {
+        assert!(declared.items.contains(item), "{item} not found");
+    }
+    assert!(!declared.items.iter().any(|item| item.ends_with(".index")));
+}
@@ -0,0 +1,80 @@
+use edgedb_client_example::schema_explorer::{modules, ObjectType, Pointer};
+
+// How the schema explorer lists what it read from the schema module, from made up types.
+// The browsing itself needs a terminal, so it isn't tested.
+
+fn pointer(name: &str, is_link: bool, target: &str) -> Pointer {
+    Pointer {
+        name: name.to_string(),
+        is_link,
+        target: target.to_string(),
+        multi: false,
+        required: false,
+        readonly: false,
+        computed: false,
+        owned: true,
+        constraints: Vec::new(),
+    }
+}
+
+fn object_type(name: &str, bases: &[&str]) -> ObjectType {
+    ObjectType {
+        name: name.to_string(),
+        is_abstract: false,
+        bases: bases.iter().map(|base| base.to_string()).collect(),
+        pointers: Vec::new(),
+    }
+}
+
+#[test]
+fn writes_pointers_like_sdl() {
+    let username = Pointer {
+        required: true,
+        constraints: vec![
+            "std::exclusive".to_string(),
+            "std::max_len_value".to_string(),
+        ],
+        ..pointer("username", false, "std::str")
+    };
+    assert_eq!(
+        username.to_string(),
+        "required property username -> str  constraint exclusive, max_len_value"
+    );
+    let posts = Pointer {
+        multi: true,
+        computed: true,
+        owned: false,
+        ..pointer("posts", true, "default::Post")
+    };
+    assert_eq!(
+        posts.to_string(),
+        "multi link posts -> default::Post  computed  inherited"
+    );
+}
+
+#[test]
+fn groups_types_by_module() {
+    let types = [
+        object_type("default::Account", &["std::Object"]),
+        ObjectType {
+            is_abstract: true,
+            ..object_type("default::HasTimestamps", &["std::BaseObject"])
+        },
+        object_type("default::Post", &["default::HasTimestamps"]),
+        object_type("test::Note", &["std::Object"]),
+    ];
+    let modules = modules(&types);
+    assert_eq!(
+        modules.keys().copied().collect::<Vec<_>>(),
+        ["default", "test"]
+    );
+    let listed: Vec<String> = modules["default"].iter().map(|t| t.to_string()).collect();
+    assert_eq!(
+        listed,
+        [
+            "type Account",
+            "abstract type HasTimestamps",
+            "type Post extending default::HasTimestamps",
+        ]
+    );
+}
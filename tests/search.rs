@@ -0,0 +1,49 @@
+use edgedb_client_example::search::{arguments, build, AccountStatus, SearchParams, Sort};
+
+// The query text for a search is put together without an instance, so these check what
+// goes into it: only the fixed fragments, picked by which fields are set
+
+const HOSTILE: [&str; 4] = [
+    "' or true",
+    "x\"); delete Account; select (\"",
+    "} filter true #",
+    "$0 ++ $1",
+];
+
+#[test]
+fn values_never_reach_the_query_text() {
+    let plain = SearchParams {
+        username_contains: Some("ada".to_string()),
+        status: Some(AccountStatus::Active),
+        ..Default::default()
+    };
+    for hostile in HOSTILE {
+        let params = SearchParams {
+            username_contains: Some(hostile.to_string()),
+            ..plain.clone()
+        };
+        let query = build(&params);
+        assert_eq!(query, build(&plain));
+        assert!(!query.contains(hostile));
+        // It's passed as an argument instead
+        assert_eq!(arguments(&params, 10).0.as_deref(), Some(hostile));
+    }
+}
+
+#[test]
+fn only_the_fields_set_are_filtered_on() {
+    let all = build(&SearchParams::default());
+    assert!(!all.contains("filter"));
+    let by_status = build(&SearchParams {
+        status: Some(AccountStatus::Suspended),
+        sort: Sort::Username,
+        ..Default::default()
+    });
+    assert!(by_status.contains("filter .status = <AccountStatus>status"));
+    assert!(by_status.contains("order by .username"));
+    assert!(!by_status.contains(".created_at >"));
+    assert_eq!(
+        arguments(&SearchParams::default(), 5),
+        (None, None, None, 5)
+    );
+}
@@ -0,0 +1,45 @@
+use edgedb_client_example::settings::merge_patch;
+use serde_json::json;
+
+// The client side merge of the settings example, which needs no instance
+
+#[test]
+fn objects_merge_recursively() {
+    let mut settings = json!({ "theme": "dark", "notifications": { "email": true } });
+    merge_patch(
+        &mut settings,
+        &json!({ "notifications": { "push": true }, "language": "fr" }),
+    );
+    assert_eq!(
+        settings,
+        json!({
+            "theme": "dark",
+            "language": "fr",
+            "notifications": { "email": true, "push": true },
+        })
+    );
+}
+
+#[test]
+fn null_removes_a_key() {
+    let mut settings = json!({ "theme": "dark", "notifications": { "email": true, "sms": true } });
+    merge_patch(
+        &mut settings,
+        &json!({ "theme": null, "notifications": { "sms": null }, "missing": null }),
+    );
+    assert_eq!(settings, json!({ "notifications": { "email": true } }));
+}
+
+#[test]
+fn anything_but_an_object_replaces() {
+    let mut settings = json!({ "tags": ["a", "b"], "volume": { "level": 3 } });
+    merge_patch(&mut settings, &json!({ "tags": ["c"], "volume": 5 }));
+    assert_eq!(settings, json!({ "tags": ["c"], "volume": 5 }));
+
+    // Including the whole value, and an object patch turns a non-object into an object
+    let mut settings = json!("not an object");
+    merge_patch(&mut settings, &json!({ "a": 1 }));
+    assert_eq!(settings, json!({ "a": 1 }));
+    merge_patch(&mut settings, &json!([1, 2]));
+    assert_eq!(settings, json!([1, 2]));
+}
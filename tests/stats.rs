@@ -0,0 +1,33 @@
+use edgedb_client_example::stats::{table, TypeStats};
+
+// The stats command's table, from made up numbers. Gathering them needs an instance, see
+// stats_counts_each_type in tests/examples.rs.
+
+#[test]
+fn lines_up_the_columns_with_a_total() {
+    let stats = [
+        TypeStats {
+            name: "default::Account".to_string(),
+            objects: 1000,
+            properties: 6,
+            links: 2,
+            size: 115_045,
+        },
+        TypeStats {
+            name: "test::Note".to_string(),
+            objects: 0,
+            properties: 1,
+            links: 0,
+            size: 0,
+        },
+    ];
+    assert_eq!(
+        table(&stats),
+        "\
+type               objects  properties  links  ~size
+default::Account      1000           6      2  112.35 KiB
+test::Note               0           1      0  0 B
+total                 1000                       112.35 KiB
+"
+    );
+}
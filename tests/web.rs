@@ -0,0 +1,152 @@
+#![cfg(feature = "web")]
+
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    http::{HeaderName, Request, StatusCode},
+    Extension, Router,
+};
+use edgedb_client_example::{
+    repository::{AccountRepository, InMemoryAccounts, RepositoryError},
+    web::{
+        self,
+        layer::{EdgeDbLayer, RequestGlobals},
+    },
+};
+use edgedb_tokio::{Builder, Client};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+// Handler tests against repository::InMemoryAccounts, built with the web feature
+// (`cargo test --features web`). Unlike the integration tests these need no server or
+// database: each request goes straight through the Router with oneshot.
+
+async fn app(usernames: &[&str]) -> (Router, Arc<InMemoryAccounts>) {
+    let repo = Arc::new(InMemoryAccounts::new());
+    for username in usernames {
+        repo.create(username).await.unwrap();
+    }
+    (web::accounts::routes(repo.clone()), repo)
+}
+
+async fn get(app: Router, uri: &str) -> (StatusCode, String) {
+    let response = app
+        .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    let status = response.status();
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+#[tokio::test]
+async fn get_account_by_username() {
+    let (app, _) = app(&["alice", "bob"]).await;
+    let (status, body) = get(app, "/accounts/bob").await;
+    assert_eq!(status, StatusCode::OK);
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["username"], "bob");
+}
+
+#[tokio::test]
+async fn get_account_by_id() {
+    let (app, repo) = app(&["alice"]).await;
+    let id = repo.list(0, 1).await.unwrap()[0].id;
+    let (status, body) = get(app, &format!("/accounts/{id}")).await;
+    assert_eq!(status, StatusCode::OK);
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["id"], id.to_string());
+    assert_eq!(json["username"], "alice");
+}
+
+#[tokio::test]
+async fn unknown_account_is_not_found() {
+    let (app, _) = app(&["alice"]).await;
+    let (status, _) = get(app, "/accounts/nobody").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+// The fake has to behave like the database for the handler tests to mean anything
+#[tokio::test]
+async fn fake_enforces_unique_usernames() {
+    let repo = InMemoryAccounts::new();
+    repo.create("alice").await.unwrap();
+    assert!(matches!(
+        repo.create("alice").await,
+        Err(RepositoryError::UsernameTaken)
+    ));
+}
+
+#[tokio::test]
+async fn fake_lists_newest_first() {
+    let repo = InMemoryAccounts::new();
+    for username in ["alice", "bob", "carol"] {
+        repo.create(username).await.unwrap();
+    }
+    let usernames: Vec<_> = repo
+        .list(1, 10)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|a| a.username)
+        .collect();
+    assert_eq!(usernames, ["bob", "alice"]);
+}
+
+// EdgeDbLayer on its own, with a handler that answers with the globals it derived the
+// request's client with. Building the client doesn't connect, so no server is needed here
+// either.
+async fn layer_app() -> Router {
+    let config = Builder::new()
+        .dsn("edgedb://edgedb@localhost:5656")
+        .expect("valid dsn")
+        .build_env()
+        .await
+        .unwrap();
+    let layer = EdgeDbLayer::new(Client::new(&config))
+        .global_from_header(HeaderName::from_static("x-tenant-id"), "current_tenant");
+    Router::new()
+        .route(
+            "/",
+            axum::routing::get(|Extension(globals): Extension<RequestGlobals>| async move {
+                format!("{:?}", globals.0)
+            }),
+        )
+        .layer(layer)
+}
+
+async fn globals_for(tenant_id: Option<&str>) -> String {
+    let mut request = Request::get("/");
+    if let Some(tenant_id) = tenant_id {
+        request = request.header("x-tenant-id", tenant_id);
+    }
+    let response = layer_app()
+        .await
+        .oneshot(request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn layer_sets_the_global_from_a_header() {
+    let id = Uuid::from_u128(42);
+    let globals = globals_for(Some(&id.to_string())).await;
+    assert_eq!(
+        globals,
+        format!("{:?}", [("current_tenant".to_string(), id)])
+    );
+}
+
+#[tokio::test]
+async fn layer_leaves_the_client_alone_without_the_header() {
+    assert_eq!(globals_for(None).await, "[]");
+}
+
+#[tokio::test]
+async fn layer_ignores_a_malformed_header() {
+    assert_eq!(globals_for(Some("not-a-uuid")).await, "[]");
+}